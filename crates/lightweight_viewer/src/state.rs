@@ -5,13 +5,13 @@ use nest_struct::nest_struct;
 use std::rc::Rc;
 use std::sync::{Mutex, MutexGuard, OnceLock};
 use std::{f32::consts::FRAC_PI_2, fs::File, sync::Arc};
-use vfl::impl_wgpu::ProgramState;
 use vfl::impl_wgpu::draw::CharModel;
 use vfl::impl_wgpu::texture::TextureBundle;
+use vfl::impl_wgpu::{ProgramState, compressed_texture_features};
 use vfl::parse::{BinRead, NxCharInfo};
-use vfl::res::shape::ResourceShape;
-use vfl::res::tex::ResourceTexture;
-use wgpu::{Backends, Features, util::DeviceExt};
+use vfl::res::ResourceStore;
+use vfl::res::source::{ResourceTier, ShapeSource, TextureSource};
+use wgpu::{Backends, util::DeviceExt};
 use winit::window::Window;
 
 /// Yeah, yeah.
@@ -63,12 +63,7 @@ pub struct State {
     pub(crate) camera_bind_group_layout: wgpu::BindGroupLayout,
     pub(crate) depth_texture: TextureBundle,
     pub(crate) camera_rotations: usize,
-    pub(crate) resources: ResourceData! {
-        pub(crate) texture_header: ResourceTexture,
-        pub(crate) shape_header: ResourceShape,
-        pub(crate) texture_data: Rc<Vec<u8>>,
-        pub(crate) shape_data: Rc<Vec<u8>>,
-    },
+    pub(crate) resources: Rc<ResourceStore>,
 }
 
 impl State {
@@ -83,7 +78,10 @@ impl State {
             .await
             .unwrap();
         let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor::default())
+            .request_device(&wgpu::DeviceDescriptor {
+                required_features: compressed_texture_features(&adapter),
+                ..Default::default()
+            })
             .await
             .unwrap();
 
@@ -154,17 +152,8 @@ impl State {
 
         let camera_rotations = 0;
 
-        let shape_file_path = format!(
-            "{}/resources_here/ShapeMid.dat",
-            std::env::var("CARGO_WORKSPACE_DIR").unwrap_or(
-                std::env::current_dir()
-                    .unwrap()
-                    .to_string_lossy()
-                    .to_string()
-            ),
-        );
-        let tex_file_path = format!(
-            "{}/resources_here/NXTextureMidSRGB.dat",
+        let resources_dir = format!(
+            "{}/resources_here",
             std::env::var("CARGO_WORKSPACE_DIR").unwrap_or(
                 std::env::current_dir()
                     .unwrap()
@@ -172,18 +161,14 @@ impl State {
                     .to_string()
             ),
         );
-        let shape_header = ResourceShape::read(&mut File::open(&shape_file_path).unwrap()).unwrap();
-        let texture_header =
-            ResourceTexture::read(&mut File::open(&tex_file_path).unwrap()).unwrap();
-        let shape_data = Rc::new(std::fs::read(&shape_file_path).unwrap());
-        let texture_data = Rc::new(std::fs::read(&tex_file_path).unwrap());
-
-        let resources = ResourceData {
-            shape_header,
-            texture_header,
-            shape_data,
-            texture_data,
-        };
+        // `low`, `mid` or `high`, picks between e.g. `ShapeMid.dat` and `ShapeHigh.dat`.
+        let tier = std::env::var("RESOURCE_TIER").map_or(ResourceTier::default(), |tier| {
+            tier.parse().unwrap_or_else(|error| {
+                eprintln!("{error} Using {:?}.", ResourceTier::default());
+                ResourceTier::default()
+            })
+        });
+        let resources = Rc::new(ResourceStore::open_nx_tier(resources_dir, tier).unwrap());
 
         let state = State {
             window,
@@ -258,16 +243,18 @@ impl State {
 
         // Instantiate a CharModel if we need it.
         if CHAR_MODEL.get().is_none() {
-            let new_model = CharModel::new(self, &char_info, &mut encoder);
+            let new_model = CharModel::new(self, &char_info, &mut encoder)
+                .unwrap_or_else(|error| panic!("Failed to build character: {error}"));
             CHAR_MODEL.set(Mutex::new(new_model)).unwrap();
         }
         if self.char_remake {
             self.char_remake = false;
 
-            let new_model = CharModel::new(self, &char_info, &mut encoder);
-
-            let mut state = CHAR_MODEL.get().unwrap().lock().unwrap();
-            *state = new_model;
+            // Keep showing the last character that could be built.
+            match CharModel::new(self, &char_info, &mut encoder) {
+                Ok(new_model) => *CHAR_MODEL.get().unwrap().lock().unwrap() = new_model,
+                Err(error) => eprintln!("Failed to build character: {error}"),
+            }
         }
 
         // Actually render a CharModel.
@@ -377,19 +364,11 @@ impl ProgramState for State {
         &self.depth_texture
     }
 
-    fn texture_header(&self) -> ResourceTexture {
-        self.resources.texture_header
-    }
-
-    fn shape_header(&self) -> ResourceShape {
-        self.resources.shape_header
-    }
-
-    fn texture_data(&self) -> Rc<Vec<u8>> {
-        self.resources.texture_data.clone()
+    fn texture_source(&self) -> Rc<dyn TextureSource> {
+        self.resources.clone()
     }
 
-    fn shape_data(&self) -> Rc<Vec<u8>> {
-        self.resources.shape_data.clone()
+    fn shape_source(&self) -> Rc<dyn ShapeSource> {
+        self.resources.clone()
     }
 }
@@ -16,6 +16,7 @@ use vfl::parse::{
     generic::{AsGenericChar, FromGenericChar},
     studio::studio_url_obfuscation_decode,
 };
+use vfl::res::source::ResourceTier;
 
 pub mod render;
 
@@ -111,6 +112,16 @@ pub async fn get() -> Markup {
     }
 }
 
+/// Resource quality tier to render with, from `RESOURCE_TIER` (`low`, `mid` or `high`).
+fn resource_tier() -> poem::Result<ResourceTier> {
+    match std::env::var("RESOURCE_TIER") {
+        Ok(tier) => tier
+            .parse()
+            .map_err(|e: String| poem::Error::from_string(e, StatusCode::INTERNAL_SERVER_ERROR)),
+        Err(_) => Ok(ResourceTier::default()),
+    }
+}
+
 async fn render_charinfo(char_info: NxCharInfo) -> poem::Result<Response> {
     let res_path: PathBuf = [
         std::env::var("CARGO_WORKSPACE_DIR").unwrap(),
@@ -119,7 +130,7 @@ async fn render_charinfo(char_info: NxCharInfo) -> poem::Result<Response> {
     .iter()
     .collect();
 
-    let image_buffer = render_to_texture(&char_info, &res_path, 512, 512)
+    let image_buffer = render_to_texture(&char_info, &res_path, resource_tier()?, 512, 512)
         .await
         .unwrap();
 
@@ -0,0 +1,313 @@
+//! Animating the face, by redrawing the mask for in-between states of an expression.
+//!
+//! [`FaceAnimator`] turns elapsed time and a voice amplitude into a [`FaceState`], and
+//! [`mask_draws`] draws the mask for it. Only the mask changes, so a renderer only has to
+//! redraw that one texture each frame.
+use crate::building::{MaskTextures, mask_models};
+use crate::char_model::CharResources;
+use crate::expression::{EYE_TEXTURE_BLINK, Expression, MOUTH_TEXTURE_OPEN, MouthTexture};
+use crate::model::Model2d;
+use crate::positioning::MaskFaceParts;
+use std::error::Error;
+use vee_parse::NxCharInfo;
+use vee_resources::color::CharColors;
+
+/// Past this, closing eyes switch to the blink texture.
+const EYES_SHUT: f32 = 0.8;
+/// Past this, the mouth switches to the open mouth texture.
+const MOUTH_OPENED: f32 = 0.2;
+/// How tall the open mouth is when it first opens, relative to its full height.
+const MOUTH_OPEN_MIN: f32 = 0.4;
+
+/// An in-between state of the face, on top of an [`Expression`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FaceState {
+    /// 0 for open eyes, 1 for closed.
+    pub eyes_closed: f32,
+    /// 0 for the expression's own mouth, 1 for fully open.
+    pub mouth_open: f32,
+}
+
+/// The mask for `state` on top of `expression`.
+///
+/// Eyes squash shut and then switch to the blink texture, and the mouth switches to the open
+/// mouth texture and grows with [`FaceState::mouth_open`]. Eyes that are already shut, and
+/// mouths that are already open, stay as the expression has them.
+/// A default `state` draws the same mask as [`crate::char_model::mask_draws`].
+/// # Errors
+/// - See [`mask_models`]
+pub fn mask_draws(
+    char: &NxCharInfo,
+    colors: &CharColors,
+    resources: CharResources,
+    expression: Expression,
+    state: FaceState,
+) -> Result<Vec<Model2d>, Box<dyn Error>> {
+    let mut parts = MaskFaceParts::init(char, expression, 256.0);
+    let mut mask_textures = MaskTextures::new(char, expression);
+
+    let eyes_closed = state.eyes_closed.clamp(0.0, 1.0);
+    for (eye, texture) in parts.eye.iter_mut().zip(&mut mask_textures.eyes) {
+        if *texture == EYE_TEXTURE_BLINK {
+            continue;
+        }
+
+        if eyes_closed >= EYES_SHUT {
+            *texture = EYE_TEXTURE_BLINK;
+        } else {
+            eye.height *= 1.0 - eyes_closed;
+        }
+    }
+
+    let mouth_open = state.mouth_open.clamp(0.0, 1.0);
+    if expression.mouth() == MouthTexture::Own && mouth_open >= MOUTH_OPENED {
+        mask_textures.mouth = MOUTH_TEXTURE_OPEN;
+        parts.mouth.height *= MOUTH_OPEN_MIN + (1.0 - MOUTH_OPEN_MIN) * mouth_open;
+    }
+
+    Ok(mask_models(
+        char,
+        colors,
+        &parts,
+        mask_textures,
+        resources.textures,
+        resources.compressed_formats,
+    )?
+    .all())
+}
+
+// Timings of a relaxed blink, in seconds.
+const BLINK_CLOSING: f32 = 0.07;
+const BLINK_SHUT: f32 = 0.03;
+const BLINK_OPENING: f32 = 0.12;
+const BLINK_DURATION: f32 = BLINK_CLOSING + BLINK_SHUT + BLINK_OPENING;
+
+const BLINK_INTERVAL_MIN: f32 = 1.5;
+const BLINK_INTERVAL_MAX: f32 = 7.0;
+/// How often a blink is quickly followed by another.
+const DOUBLE_BLINK_CHANCE: f32 = 0.1;
+const DOUBLE_BLINK_GAP: f32 = 0.15;
+
+/// Blinks at irregular, human-looking intervals.
+///
+/// Gaps between blinks lean short with a long tail, and some blinks come in pairs.
+/// Blinkers with different seeds don't blink in step, which keeps a crowd from looking robotic.
+#[derive(Clone, Debug)]
+pub struct Blinker {
+    rng: u32,
+    /// Seconds until the next blink starts.
+    until_blink: f32,
+    /// Seconds into the current blink.
+    blinking: Option<f32>,
+}
+
+impl Blinker {
+    #[must_use]
+    pub fn new(seed: u32) -> Blinker {
+        let mut blinker = Blinker {
+            // Xorshift gets stuck on zero.
+            rng: seed | 1,
+            until_blink: 0.0,
+            blinking: None,
+        };
+        blinker.until_blink = blinker.interval();
+
+        blinker
+    }
+
+    /// Advances by `dt` seconds and returns how closed the eyes are, from 0 to 1.
+    pub fn update(&mut self, dt: f32) -> f32 {
+        match self.blinking {
+            Some(elapsed) if elapsed + dt < BLINK_DURATION => {
+                self.blinking = Some(elapsed + dt);
+            }
+            Some(_) => {
+                self.blinking = None;
+                self.until_blink = if self.random() < DOUBLE_BLINK_CHANCE {
+                    DOUBLE_BLINK_GAP
+                } else {
+                    self.interval()
+                };
+            }
+            None => {
+                self.until_blink -= dt;
+
+                if self.until_blink <= 0.0 {
+                    self.blinking = Some((-self.until_blink).min(BLINK_CLOSING));
+                }
+            }
+        }
+
+        self.eyes_closed()
+    }
+
+    /// How closed the eyes are, from 0 to 1.
+    #[must_use]
+    pub fn eyes_closed(&self) -> f32 {
+        let Some(elapsed) = self.blinking else {
+            return 0.0;
+        };
+
+        let closed = if elapsed < BLINK_CLOSING {
+            elapsed / BLINK_CLOSING
+        } else if elapsed < BLINK_CLOSING + BLINK_SHUT {
+            1.0
+        } else {
+            1.0 - (elapsed - BLINK_CLOSING - BLINK_SHUT) / BLINK_OPENING
+        };
+
+        smoothstep(closed.clamp(0.0, 1.0))
+    }
+
+    /// Seconds to the next blink, leaning towards the short end.
+    fn interval(&mut self) -> f32 {
+        let random = self.random();
+
+        BLINK_INTERVAL_MIN + (BLINK_INTERVAL_MAX - BLINK_INTERVAL_MIN) * random * random
+    }
+
+    /// Uniform in `0.0..1.0`.
+    fn random(&mut self) -> f32 {
+        // Xorshift32
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+
+        (self.rng >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+fn smoothstep(x: f32) -> f32 {
+    x * x * (3.0 - 2.0 * x)
+}
+
+/// Opens the mouth along with a voice, from its amplitude.
+///
+/// Opens quickly and closes a little slower, so the mouth doesn't flutter between syllables.
+#[derive(Clone, Copy, Debug)]
+pub struct LipSync {
+    /// Amplitudes below this count as silence.
+    pub gate: f32,
+    /// Scales the amplitude above `gate` into how open the mouth is.
+    pub gain: f32,
+    /// Seconds to mostly open.
+    pub attack: f32,
+    /// Seconds to mostly close.
+    pub release: f32,
+    level: f32,
+}
+
+impl Default for LipSync {
+    fn default() -> Self {
+        LipSync {
+            gate: 0.02,
+            gain: 4.0,
+            attack: 0.03,
+            release: 0.1,
+            level: 0.0,
+        }
+    }
+}
+
+impl LipSync {
+    /// Advances by `dt` seconds towards `amplitude`, like the RMS of the audio since the last
+    /// update, and returns how open the mouth is, from 0 to 1.
+    pub fn update(&mut self, amplitude: f32, dt: f32) -> f32 {
+        let target = ((amplitude - self.gate).max(0.0) * self.gain).min(1.0);
+        let time = if target > self.level {
+            self.attack
+        } else {
+            self.release
+        };
+
+        // Frame rate independent easing towards the target.
+        let step = if time > 0.0 {
+            1.0 - (-dt / time).exp()
+        } else {
+            1.0
+        };
+        self.level += (target - self.level) * step;
+
+        self.level
+    }
+
+    /// How open the mouth is, from 0 to 1.
+    #[must_use]
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+}
+
+/// Blinking and lip-sync together.
+#[derive(Clone, Debug)]
+pub struct FaceAnimator {
+    pub blinker: Blinker,
+    pub lip_sync: LipSync,
+}
+
+impl FaceAnimator {
+    /// `seed` picks when the blinks happen. See [`Blinker::new`].
+    #[must_use]
+    pub fn new(seed: u32) -> FaceAnimator {
+        FaceAnimator {
+            blinker: Blinker::new(seed),
+            lip_sync: LipSync::default(),
+        }
+    }
+
+    /// Advances by `dt` seconds, with the voice at `amplitude`. Pass 0 while silent.
+    pub fn update(&mut self, dt: f32, amplitude: f32) -> FaceState {
+        FaceState {
+            eyes_closed: self.blinker.update(dt),
+            mouth_open: self.lip_sync.update(amplitude, dt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    #[test]
+    fn blinks_now_and_then() {
+        let mut blinker = Blinker::new(7);
+        let closed: Vec<f32> = (0..60 * 60).map(|_| blinker.update(FRAME)).collect();
+
+        // A blink is a run of frames with the eyes partly closed.
+        let blinks = closed
+            .windows(2)
+            .filter(|pair| pair[0] == 0.0 && pair[1] > 0.0)
+            .count();
+        assert!((8..=40).contains(&blinks), "{blinks} blinks in a minute");
+
+        assert!(closed.iter().all(|closed| (0.0..=1.0).contains(closed)));
+        assert!(closed.contains(&1.0));
+    }
+
+    #[test]
+    fn seeds_blink_apart() {
+        let mut a = Blinker::new(1);
+        let mut b = Blinker::new(2);
+
+        let differ = (0..60 * 20).any(|_| a.update(FRAME) != b.update(FRAME));
+        assert!(differ);
+    }
+
+    #[test]
+    fn lip_sync_follows_amplitude() {
+        let mut lip_sync = LipSync::default();
+
+        assert_eq!(lip_sync.update(0.01, FRAME), 0.0);
+
+        for _ in 0..30 {
+            lip_sync.update(0.5, FRAME);
+        }
+        assert!(lip_sync.level() > 0.99);
+
+        // Closes slower than it opens.
+        let after_one = lip_sync.update(0.0, FRAME);
+        assert!(after_one > 0.5 && after_one < 1.0);
+    }
+}
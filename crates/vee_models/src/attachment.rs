@@ -0,0 +1,176 @@
+//! Where accessories (hats, headphones, badges...) go on a head, like FFL's `FFLPartsTransform`.
+//!
+//! Frames are in the same space as the head's models, so an accessory modelled around the
+//! origin can be drawn with [`AttachmentFrame::matrix`], after whatever places the head itself.
+use glam::{EulerRot, Mat4, Vec3};
+use std::error::Error;
+use vee_parse::NxCharInfo;
+use vee_resources::shape::{ResourceShapeFacelineTransform, ResourceShapeHairTransform};
+use vee_resources::source::ShapeSource;
+
+/// Named points on the head.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AttachmentPoint {
+    /// Where the hat mesh goes. Not rotated.
+    Hat,
+    Front,
+    Side,
+    Top,
+}
+
+impl AttachmentPoint {
+    pub const ALL: [AttachmentPoint; 4] = [
+        AttachmentPoint::Hat,
+        AttachmentPoint::Front,
+        AttachmentPoint::Side,
+        AttachmentPoint::Top,
+    ];
+}
+
+/// A position and orientation on the head.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AttachmentFrame {
+    pub translate: Vec3,
+    /// Euler angles in degrees, applied around X, then Y, then Z.
+    pub rotate: Vec3,
+}
+
+impl AttachmentFrame {
+    /// Transforms from the frame's space into the head's.
+    #[must_use]
+    pub fn matrix(&self) -> Mat4 {
+        let [x, y, z] = self.rotate.to_array().map(f32::to_radians);
+
+        Mat4::from_translation(self.translate) * Mat4::from_euler(EulerRot::ZYX, z, y, x)
+    }
+
+    /// Mirrors the frame across the YZ plane, as a flipped hair mesh is.
+    /// Rotations around Y and Z turn the other way.
+    fn mirrored(self) -> AttachmentFrame {
+        AttachmentFrame {
+            translate: self.translate * Vec3::new(-1.0, 1.0, 1.0),
+            rotate: self.rotate * Vec3::new(1.0, -1.0, -1.0),
+        }
+    }
+
+    fn from_arrays(translate: [f32; 3], rotate: [f32; 3]) -> AttachmentFrame {
+        AttachmentFrame {
+            translate: Vec3::from_array(translate),
+            rotate: Vec3::from_array(rotate),
+        }
+    }
+}
+
+/// Every [`AttachmentPoint`] of a character.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AttachmentFrames {
+    pub hat: AttachmentFrame,
+    pub front: AttachmentFrame,
+    pub side: AttachmentFrame,
+    pub top: AttachmentFrame,
+}
+
+impl AttachmentFrames {
+    /// Places the points of a hairstyle on a faceline. `flip` mirrors them,
+    /// for characters with `hair_flip` set.
+    // FFLiCharModelCreator.cpp :638, the hair mesh is moved the same way.
+    #[must_use]
+    pub fn new(
+        hair: &ResourceShapeHairTransform,
+        faceline: &ResourceShapeFacelineTransform,
+        flip: bool,
+    ) -> AttachmentFrames {
+        let hair_translate = Vec3::from_array(faceline.hair_translate);
+
+        let place = |translate, rotate| {
+            let frame = AttachmentFrame::from_arrays(translate, rotate);
+            let frame = if flip { frame.mirrored() } else { frame };
+
+            AttachmentFrame {
+                translate: frame.translate + hair_translate,
+                ..frame
+            }
+        };
+
+        AttachmentFrames {
+            hat: AttachmentFrame {
+                translate: hair_translate,
+                rotate: Vec3::ZERO,
+            },
+            front: place(hair.front_translate, hair.front_rotate),
+            side: place(hair.side_translate, hair.side_rotate),
+            top: place(hair.top_translate, hair.top_rotate),
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self, point: AttachmentPoint) -> AttachmentFrame {
+        match point {
+            AttachmentPoint::Hat => self.hat,
+            AttachmentPoint::Front => self.front,
+            AttachmentPoint::Side => self.side,
+            AttachmentPoint::Top => self.top,
+        }
+    }
+
+    /// The frame of `point` in world space, given the matrix that places the head.
+    #[must_use]
+    pub fn world_matrix(&self, point: AttachmentPoint, head_to_world: Mat4) -> Mat4 {
+        head_to_world * self.get(point).matrix()
+    }
+}
+
+/// Reads the attachment frames of `char`'s hairstyle and faceline.
+/// Returns `None` if `shapes` has no transform for either.
+///
+/// # Errors
+/// - The shape data is malformed
+pub fn attachment_frames(
+    char: &NxCharInfo,
+    shapes: &dyn ShapeSource,
+) -> Result<Option<AttachmentFrames>, Box<dyn Error>> {
+    let Some(hair) = shapes.hair_transform(usize::from(char.hair_type))? else {
+        return Ok(None);
+    };
+    let Some(faceline) = shapes.face_line_transform(usize::from(char.faceline_type))? else {
+        return Ok(None);
+    };
+
+    Ok(Some(AttachmentFrames::new(
+        &hair,
+        &faceline,
+        char.hair_flip != 0,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flip_mirrors_around_hair() {
+        let hair = ResourceShapeHairTransform {
+            side_translate: [9.0, 2.0, -1.0],
+            side_rotate: [10.0, 20.0, 30.0],
+            ..Default::default()
+        };
+        let faceline = ResourceShapeFacelineTransform {
+            hair_translate: [1.0, 14.0, 0.0],
+            ..Default::default()
+        };
+
+        let frames = AttachmentFrames::new(&hair, &faceline, false);
+        let flipped = AttachmentFrames::new(&hair, &faceline, true);
+
+        assert_eq!(frames.side.translate, Vec3::new(10.0, 16.0, -1.0));
+        assert_eq!(flipped.side.translate, Vec3::new(-8.0, 16.0, -1.0));
+        assert_eq!(flipped.side.rotate, Vec3::new(10.0, -20.0, -30.0));
+        assert_eq!(flipped.hat, frames.hat);
+
+        // A point on the frame's X axis lands mirrored, relative to the hair.
+        let hair_translate = Vec3::from_array(faceline.hair_translate);
+        let point = frames.side.matrix().transform_point3(Vec3::X) - hair_translate;
+        let flipped_point = flipped.side.matrix().transform_point3(-Vec3::X) - hair_translate;
+        assert!(flipped_point.abs_diff_eq(point * Vec3::new(-1.0, 1.0, 1.0), 1e-5));
+    }
+}
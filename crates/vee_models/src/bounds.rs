@@ -0,0 +1,192 @@
+//! Bounding boxes of parts and whole heads, for framing cameras and culling.
+use crate::placement::{HEAD_SHAPES, PartPlacement, shape_index};
+use glam::{Mat4, Vec3};
+use std::error::Error;
+use vee_parse::NxCharInfo;
+use vee_resources::shape::Shape;
+use vee_resources::source::ShapeSource;
+
+/// Axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// From `[min, max]`, as stored in shape resources.
+    #[must_use]
+    pub fn from_array([min, max]: [[f32; 3]; 2]) -> Aabb {
+        Aabb {
+            min: Vec3::from_array(min),
+            max: Vec3::from_array(max),
+        }
+    }
+
+    /// Smallest box around every point. `None` without any points.
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Aabb> {
+        points
+            .into_iter()
+            .map(|point| Aabb {
+                min: point,
+                max: point,
+            })
+            .reduce(Aabb::union)
+    }
+
+    /// Smallest box around both boxes.
+    #[must_use]
+    pub fn union(self, other: Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    #[must_use]
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    #[must_use]
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// Radius of the sphere around the box, centered on [`Self::center`].
+    #[must_use]
+    pub fn radius(&self) -> f32 {
+        self.size().length() / 2.0
+    }
+
+    #[must_use]
+    pub fn corners(&self) -> [Vec3; 8] {
+        let Aabb { min, max } = *self;
+
+        std::array::from_fn(|i| {
+            Vec3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        })
+    }
+
+    /// Box around this box after `matrix` moves it. Mirroring matrices are fine;
+    /// rotations make the box looser than the mesh inside it.
+    #[must_use]
+    pub fn transformed(&self, matrix: Mat4) -> Aabb {
+        Aabb::from_points(self.corners().map(|corner| matrix.transform_point3(corner)))
+            .expect("A box has corners")
+    }
+
+    /// How far from [`Self::center`] a perspective camera has to be for the whole box to fit.
+    /// `fov_y` is in radians, and `aspect` is width over height.
+    #[must_use]
+    pub fn fit_distance(&self, fov_y: f32, aspect: f32) -> f32 {
+        let half_y = fov_y / 2.0;
+        let half_x = (half_y.tan() * aspect).atan();
+
+        self.radius() / half_y.min(half_x).sin()
+    }
+}
+
+/// Bounds of a part of `char`'s head, in the head's space.
+/// Returns `None` if the character doesn't have the part, or `shapes` doesn't have its mesh.
+///
+/// # Errors
+/// - The shape data is malformed
+pub fn part_bounds(
+    char: &NxCharInfo,
+    shapes: &dyn ShapeSource,
+    shape: Shape,
+) -> Result<Option<Aabb>, Box<dyn Error>> {
+    let Some(index) = shape_index(char, shape) else {
+        return Ok(None);
+    };
+    let Some(faceline) = shapes.face_line_transform(usize::from(char.faceline_type))? else {
+        return Ok(None);
+    };
+    let Some(bounding_box) = shapes.bounding_box(shape, usize::from(index))? else {
+        return Ok(None);
+    };
+
+    let placement = PartPlacement::new(char, shape, &faceline);
+
+    Ok(Some(
+        Aabb::from_array(bounding_box).transformed(placement.matrix()),
+    ))
+}
+
+/// Bounds of every part of `char`'s head, in the head's space.
+/// Returns `None` if there are no parts at all.
+///
+/// # Errors
+/// - The shape data is malformed
+pub fn head_bounds(
+    char: &NxCharInfo,
+    shapes: &dyn ShapeSource,
+) -> Result<Option<Aabb>, Box<dyn Error>> {
+    let mut bounds = None;
+
+    for shape in HEAD_SHAPES {
+        if let Some(part) = part_bounds(char, shapes, shape)? {
+            bounds = Some(bounds.map_or(part, |bounds: Aabb| bounds.union(part)));
+        }
+    }
+
+    Ok(bounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use vee_parse::generic::{AsGenericChar, FromGenericChar};
+    use vee_parse::{BinRead, CtrStoreData};
+
+    fn char_info() -> NxCharInfo {
+        let data = include_bytes!("../../../resources_here/j0.ffsd");
+        let char = CtrStoreData::read(&mut Cursor::new(data)).unwrap();
+
+        NxCharInfo::from_generic(char.as_generic().unwrap())
+    }
+
+    #[test]
+    fn flipped_box_stays_ordered() {
+        let bounds = Aabb::from_array([[1.0, 0.0, -2.0], [4.0, 3.0, 2.0]]);
+        let flipped = bounds.transformed(Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0)));
+
+        assert_eq!(flipped.min, Vec3::new(-4.0, 0.0, -2.0));
+        assert_eq!(flipped.max, Vec3::new(-1.0, 3.0, 2.0));
+    }
+
+    #[test]
+    fn placed_part() {
+        let char = NxCharInfo {
+            nose_y: 8,
+            nose_scale: 4,
+            ..char_info()
+        };
+        let faceline = vee_resources::shape::ResourceShapeFacelineTransform {
+            nose_translate: [0.0, 10.0, 5.0],
+            ..Default::default()
+        };
+
+        let placement = PartPlacement::new(&char, Shape::Nose, &faceline);
+        let bounds = Aabb::from_array([[-1.0; 3], [1.0; 3]]).transformed(placement.matrix());
+
+        assert!(bounds.center().abs_diff_eq(Vec3::new(0.0, 10.0, 5.0), 1e-5));
+        assert!(bounds.size().abs_diff_eq(Vec3::splat(2.0 * 1.1), 1e-5));
+    }
+
+    #[test]
+    fn fit_distance() {
+        let bounds = Aabb::from_array([[-1.0; 3], [1.0; 3]]);
+        let fov_y = 60f32.to_radians();
+
+        // A narrow view is limited by its width.
+        assert!(bounds.fit_distance(fov_y, 0.5) > bounds.fit_distance(fov_y, 1.0));
+        assert!((bounds.fit_distance(fov_y, 1.0) - 3f32.sqrt() * 2.0).abs() < 1e-4);
+    }
+}
@@ -2,15 +2,20 @@
 use super::positioning::{ImageOrigin, MaskFacePart, MaskFaceParts};
 
 use glam::{Mat4, Quat, Vec2, vec2};
+use image::{DynamicImage, imageops};
+use std::error::Error;
 
 pub const FACE_OUTPUT_SIZE: u16 = 512;
-use crate::model::{Model2d, Vertex};
+use crate::expression::Expression;
+use crate::model::{Model2d, ModelTexture, Vertex};
 use crate::{TEX_SCALE_X, TEX_SCALE_Y};
 pub use bytemuck::cast_slice;
 use vee_parse::NxCharInfo;
-use vee_resources::color::nx::{ColorModulated, modulate};
+use vee_resources::color::CharColors;
+use vee_resources::color::nx::ColorModulated;
 use vee_resources::packing::Float16;
-use vee_resources::tex::{ResourceTexture, TextureElement};
+use vee_resources::source::TextureSource;
+use vee_resources::tex::{ResourceTextureFormat, TexturePart};
 
 /// All the models required for rendering the mask texture.
 pub struct MaskModels {
@@ -50,18 +55,79 @@ impl MaskModels {
     }
 }
 
-/// Returns the models needed for the mask texture.
-/// # Panics
-/// - Panics if image loading fails.
+/// Returns the models needed for the mask texture, making `expression` in `char`'s Nx colors.
+/// # Errors
+/// - The texture data is malformed
+/// - `textures` has no eye or mouth texture for `char` and `expression`
 pub fn mask_texture_meshes(
     char: &NxCharInfo,
-    res_texture: &ResourceTexture,
-    file_texture: &[u8],
-) -> MaskModels {
-    let mask = MaskFaceParts::init(char, 256.0);
+    expression: Expression,
+    textures: &dyn TextureSource,
+) -> Result<MaskModels, Box<dyn Error>> {
+    mask_texture_meshes_with(char, &CharColors::nx(char), expression, textures, &[])
+}
+
+/// Returns the models needed for the mask texture in `colors`, keeping textures in
+/// `compressed_formats` compressed. See [`ModelTexture::load`].
+/// # Errors
+/// - The texture data is malformed
+/// - `textures` has no eye or mouth texture for `char` and `expression`
+pub fn mask_texture_meshes_with(
+    char: &NxCharInfo,
+    colors: &CharColors,
+    expression: Expression,
+    textures: &dyn TextureSource,
+    compressed_formats: &[ResourceTextureFormat],
+) -> Result<MaskModels, Box<dyn Error>> {
+    mask_models(
+        char,
+        colors,
+        &MaskFaceParts::init(char, expression, 256.0),
+        MaskTextures::new(char, expression),
+        textures,
+        compressed_formats,
+    )
+}
+
+/// Which eye and mouth textures the mask is drawn with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaskTextures {
+    /// Left and right eye.
+    pub eyes: [usize; 2],
+    pub mouth: usize,
+}
 
-    let make_shape = |part: MaskFacePart, modulated: ColorModulated, tex_data: TextureElement| {
-        let (vertices, indices, mtx) = quad(
+impl MaskTextures {
+    /// The textures `char` uses to make `expression`.
+    #[must_use]
+    pub fn new(char: &NxCharInfo, expression: Expression) -> MaskTextures {
+        MaskTextures {
+            eyes: expression.eye_textures(char.eye_type),
+            mouth: expression.mouth_texture(char.mouth_type),
+        }
+    }
+}
+
+/// Returns the models for the mask texture from parts that are already positioned,
+/// for posing the face beyond what [`MaskFaceParts::init`] does.
+/// # Errors
+/// - The texture data is malformed
+/// - `textures` has no eye or mouth texture in `mask_textures`
+pub fn mask_models(
+    char: &NxCharInfo,
+    colors: &CharColors,
+    mask: &MaskFaceParts,
+    mask_textures: MaskTextures,
+    textures: &dyn TextureSource,
+    compressed_formats: &[ResourceTextureFormat],
+) -> Result<MaskModels, Box<dyn Error>> {
+    let [left_eye_index, right_eye_index] = mask_textures.eyes;
+
+    let make_shape = |part: MaskFacePart,
+                      modulated: ColorModulated,
+                      (tex_part, index): (TexturePart, usize)|
+     -> Result<Option<Model2d>, Box<dyn Error>> {
+        let (mut vertices, indices, mtx) = quad(
             part.x,
             part.y,
             part.width,
@@ -72,77 +138,95 @@ pub fn mask_texture_meshes(
         );
 
         if part.width <= 0.0 || part.height <= 0.0 {
-            return None;
+            return Ok(None);
         };
 
-        let tex = tex_data.get_image(file_texture).unwrap()?;
+        let Some(tex) = ModelTexture::load(textures, tex_part, index, compressed_formats)? else {
+            return Ok(None);
+        };
+        let tex = match tex {
+            ModelTexture::Image(tex) => ModelTexture::Image(tex.flipv()),
+            ModelTexture::Shared(tex) => {
+                ModelTexture::Image(DynamicImage::ImageRgba8(imageops::flip_vertical(&*tex)))
+            }
+            // Blocks can't be flipped, so flip where they're sampled instead.
+            ModelTexture::Compressed(tex) => {
+                for vertex in &mut vertices {
+                    let v = vertex.tex_coords[1].as_f32();
+                    vertex.tex_coords[1] = Float16::from_f32(1.0 - v);
+                }
+                ModelTexture::Compressed(tex)
+            }
+        };
 
-        Some(Model2d {
+        Ok(Some(Model2d {
             vertices,
             indices,
-            tex: image::DynamicImage::ImageRgba8(tex).flipv(),
+            tex,
             mvp_matrix: mtx,
-            modulation: modulate(modulated, char),
+            modulation: colors.modulate(modulated),
             opaque: None,
             label: Some(format!("{modulated:?}")),
-        })
+        }))
     };
 
     let left_eye = make_shape(
         mask.eye[0],
         ColorModulated::Eye,
-        res_texture.eye[char.eye_type as usize],
+        (TexturePart::Eye, left_eye_index),
     );
     let right_eye = make_shape(
         mask.eye[1],
         ColorModulated::Eye,
-        res_texture.eye[char.eye_type as usize],
+        (TexturePart::Eye, right_eye_index),
     );
 
     let left_brow = make_shape(
         mask.eyebrow[0],
         ColorModulated::Eyebrow,
-        res_texture.eyebrow[char.eyebrow_type as usize],
+        (TexturePart::Eyebrow, char.eyebrow_type as usize),
     );
     let right_brow = make_shape(
         mask.eyebrow[1],
         ColorModulated::Eyebrow,
-        res_texture.eyebrow[char.eyebrow_type as usize],
+        (TexturePart::Eyebrow, char.eyebrow_type as usize),
     );
 
     let mouth = make_shape(
         mask.mouth,
         ColorModulated::Mouth,
-        res_texture.mouth[char.mouth_type as usize],
+        (TexturePart::Mouth, mask_textures.mouth),
     );
 
     let left_mustache = make_shape(
         mask.mustache[0],
         ColorModulated::Mustache,
-        res_texture.mustache[char.mustache_type as usize],
+        (TexturePart::Mustache, char.mustache_type as usize),
     );
     let right_mustache = make_shape(
         mask.mustache[1],
         ColorModulated::Mustache,
-        res_texture.mustache[char.mustache_type as usize],
+        (TexturePart::Mustache, char.mustache_type as usize),
     );
 
     let mole = make_shape(
         mask.mole,
         ColorModulated::Mole,
-        res_texture.mole[if char.mole_type == 0 { 0 } else { 1 }],
+        (TexturePart::Mole, if char.mole_type == 0 { 0 } else { 1 }),
     );
 
-    MaskModels {
-        left_eye: left_eye.unwrap(),
-        right_eye: right_eye.unwrap(),
-        left_brow,
-        right_brow,
-        left_mustache,
-        right_mustache,
-        mouth: mouth.unwrap(),
-        mole,
-    }
+    let missing = |part| format!("The {part} texture is missing from the resources.");
+
+    Ok(MaskModels {
+        left_eye: left_eye?.ok_or_else(|| missing("eye"))?,
+        right_eye: right_eye?.ok_or_else(|| missing("eye"))?,
+        left_brow: left_brow?,
+        right_brow: right_brow?,
+        left_mustache: left_mustache?,
+        right_mustache: right_mustache?,
+        mouth: mouth?.ok_or_else(|| missing("mouth"))?,
+        mole: mole?,
+    })
 }
 
 /// Constructs an [MV Matrix](https://developer.mozilla.org/en-US/docs/Web/API/WebGL_API/WebGL_model_view_projection)
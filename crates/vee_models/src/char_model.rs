@@ -0,0 +1,511 @@
+//! Assembling a character's head from resources, without a renderer.
+//!
+//! [`build`] gives every part as a [`GenericModel3d`], placed in the head's space and colored.
+//! Parts with a texture carry the [`Model2d`]s to draw into it, which the renderer draws
+//! before drawing the part itself.
+use crate::building::mask_texture_meshes_with;
+use crate::expression::{Expression, ExpressionSet};
+use crate::model::{DrawableTexture, GenericModel3d, Model2d, ModelTexture, Vertex};
+use crate::placement::{PartPlacement, shape_index};
+use glam::{UVec2, Vec4, uvec2, vec4};
+use std::error::Error;
+use std::iter::zip;
+use vee_parse::NxCharInfo;
+use vee_resources::color::CharColors;
+use vee_resources::color::nx::{ColorModulated, ModulationIntent};
+use vee_resources::packing::{Float16, Vec3PackedSnorm};
+use vee_resources::shape::{Shape, ShapeMesh};
+use vee_resources::source::{ShapeSource, TextureSource};
+use vee_resources::tex::{ResourceTextureFormat, TexturePart};
+
+/// Where [`build`] reads parts from.
+#[derive(Clone, Copy)]
+pub struct CharResources<'a> {
+    pub shapes: &'a dyn ShapeSource,
+    pub textures: &'a dyn TextureSource,
+    /// Formats to keep textures compressed in. See [`ModelTexture::load`].
+    pub compressed_formats: &'a [ResourceTextureFormat],
+}
+
+impl<'a> CharResources<'a> {
+    /// Resources that decode every texture.
+    #[must_use]
+    pub fn new(shapes: &'a dyn ShapeSource, textures: &'a dyn TextureSource) -> CharResources<'a> {
+        CharResources {
+            shapes,
+            textures,
+            compressed_formats: &[],
+        }
+    }
+}
+
+/// A texture to render before the part it's on, by drawing `draws` in order.
+/// Starts out transparent, and may stay that way if there's nothing to draw.
+#[derive(Debug)]
+pub struct ProjectedTexture {
+    pub size: UVec2,
+    pub draws: Vec<Model2d>,
+}
+
+/// A part of the head, in the head's space.
+pub type PartModel = GenericModel3d<ProjectedTexture>;
+
+/// Every part of a character's head.
+#[derive(Debug)]
+pub struct CharModelData {
+    pub face_line: PartModel,
+    pub forehead: Option<PartModel>,
+    pub mask: PartModel,
+    pub hair: Option<PartModel>,
+    pub nose: Option<PartModel>,
+    pub glasses: Option<PartModel>,
+    pub nose_line: PartModel,
+    pub beard: Option<PartModel>,
+    pub hat: Option<PartModel>,
+}
+
+impl CharModelData {
+    /// Every part, in the order they should be drawn.
+    pub fn parts(&self) -> impl Iterator<Item = &PartModel> {
+        [
+            Some(&self.face_line),
+            self.forehead.as_ref(),
+            self.hair.as_ref(),
+            Some(&self.mask),
+            self.nose.as_ref(),
+            Some(&self.nose_line),
+            self.glasses.as_ref(),
+            self.beard.as_ref(),
+            self.hat.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+/// Builds every part of `char`'s head in `colors`, with the mask making `expression`.
+/// [`CharColors::nx`] gives the colors `char` has on its own.
+///
+/// # Errors
+/// - The resource data is malformed
+/// - The faceline, mask or nose line is missing from `resources`
+/// - The eye or mouth texture for the mask is missing from `resources`
+pub fn build(
+    char: &NxCharInfo,
+    colors: &CharColors,
+    resources: CharResources,
+    expression: Expression,
+) -> Result<CharModelData, Box<dyn Error>> {
+    let part = |shape| part(char, colors, resources, shape, expression);
+    let required = |shape| -> Result<PartModel, Box<dyn Error>> {
+        part(shape)?.ok_or_else(|| format!("{shape:?} is missing from the resources.").into())
+    };
+
+    Ok(CharModelData {
+        face_line: required(Shape::FaceLine)?,
+        forehead: part(Shape::ForeheadNormal)?,
+        mask: required(Shape::Mask)?,
+        hair: part(Shape::HairNormal)?,
+        nose: part(Shape::Nose)?,
+        glasses: part(Shape::Glasses)?,
+        nose_line: required(Shape::NoseLine)?,
+        beard: part(Shape::Beard)?,
+        hat: part(Shape::HatNormal)?,
+    })
+}
+
+/// Builds one part of `char`'s head. Returns `None` if the character doesn't have it,
+/// or its mesh is empty. Only the mask depends on `expression`.
+///
+/// # Errors
+/// - The resource data is malformed
+/// - The eye or mouth texture for the mask is missing from `resources`
+pub fn part(
+    char: &NxCharInfo,
+    colors: &CharColors,
+    resources: CharResources,
+    shape: Shape,
+    expression: Expression,
+) -> Result<Option<PartModel>, Box<dyn Error>> {
+    let Some(index) = shape_index(char, shape) else {
+        return Ok(None);
+    };
+    let Some(faceline_transform) = resources
+        .shapes
+        .face_line_transform(usize::from(char.faceline_type))?
+    else {
+        return Ok(None);
+    };
+
+    // Empty and out of range shapes come back as `None`.
+    let Some(mesh) = resources.shapes.mesh_shared(shape, usize::from(index))? else {
+        return Ok(None);
+    };
+
+    let placement = PartPlacement::new(char, shape, &faceline_transform);
+
+    let texture = match shape {
+        Shape::NoseLine => Some(projected(
+            uvec2(256, 256),
+            noseline_draws(char, colors, resources)?,
+        )),
+        Shape::Mask => Some(mask_texture(char, colors, resources, expression)?),
+        Shape::FaceLine => Some(projected(
+            uvec2(512, 512),
+            faceline_draws(char, colors, resources)?,
+        )),
+        Shape::Glasses => Some(projected(
+            uvec2(512, 512),
+            glass_draws(char, colors, resources)?,
+        )),
+        Shape::HatNormal => Some(projected(
+            uvec2(128, 32),
+            hat_draws(char, colors, resources)?,
+        )), // It's just that size.
+        _ => None,
+    };
+
+    mesh_to_model(&mesh, shape_color(colors, shape), placement, texture).map(Some)
+}
+
+fn projected(size: UVec2, draws: Vec<Model2d>) -> ProjectedTexture {
+    ProjectedTexture { size, draws }
+}
+
+/// Diffuse color of a part. Textured parts are black, and only use their texture.
+fn shape_color(colors: &CharColors, shape: Shape) -> Vec4 {
+    match shape {
+        Shape::HairNormal => colors.hair.into(),
+        Shape::Beard => colors.beard.into(),
+        Shape::HatNormal => colors.favorite.into(),
+        Shape::FaceLine | Shape::ForeheadNormal | Shape::Nose => colors.faceline.into(),
+        _ => vec4(0.0, 0.0, 0.0, 0.0),
+    }
+}
+
+/// Converts a `ShapeMesh` into a part.
+///
+/// # Errors
+/// - The mesh has no normals
+pub fn mesh_to_model<Tex>(
+    d: &ShapeMesh,
+    color: Vec4,
+    PartPlacement { position, scale }: PartPlacement,
+    texture: Option<Tex>,
+) -> Result<GenericModel3d<Tex>, Box<dyn Error>> {
+    let vertices_count = d.positions.len();
+
+    // Drop the w component in positions
+    let positions: Vec<_> = d.positions.iter().map(|&[x, y, z, _w]| [x, y, z]).collect();
+
+    // Unwrap UVs and replace with NaNs if needed...
+    let tex_coords: Vec<_> = d
+        .uvs // Go on, return NULL. See if I care.
+        .clone()
+        .unwrap_or_else(|| vec![[f32::NAN, f32::NAN].map(Float16::from_f32); vertices_count]);
+
+    // Unpack normals, keeping the packed ones for `PackedVertex`.
+    let packed_normals = d.normals.clone().ok_or("Shape has no normals.")?;
+    let normals: Vec<_> = packed_normals
+        .iter()
+        .copied()
+        .map(Vec3PackedSnorm::unpack)
+        .collect();
+
+    // Build vertex vector
+    let vertices = zip(zip(positions, tex_coords), normals)
+        .map(|((position, tex_coords), normal)| Vertex {
+            position,
+            _pad: 0,
+            tex_coords,
+            normal,
+        })
+        .collect();
+
+    let indices = d.indices.iter().copied().map(u32::from).collect();
+
+    Ok(GenericModel3d {
+        vertices,
+        packed_normals,
+        tangents: d.tangents.clone(),
+        color_params: d.color_params.clone(),
+        indices,
+        color,
+        texture,
+        position,
+        scale,
+    })
+}
+
+/// Looks up a texture, and returns the texture with any modulation that needs to be done.
+/// Returns an `Option<T>` because the texture could not exist (e.g. `CharInfo` w/o `Beard`)
+fn load_texture(
+    colors: &CharColors,
+    resources: CharResources,
+    (part, index): (TexturePart, usize),
+    modulated: ColorModulated,
+) -> Result<Option<(ModelTexture, ModulationIntent)>, Box<dyn Error>> {
+    Ok(ModelTexture::load(
+        resources.textures,
+        part,
+        index,
+        resources.compressed_formats,
+    )?
+    .map(|tex| (tex, colors.modulate(modulated))))
+}
+
+fn texture_draws(texture: Option<(ModelTexture, ModulationIntent)>) -> Vec<Model2d> {
+    texture
+        .map(|(rendered_texture, modulation)| {
+            DrawableTexture {
+                rendered_texture,
+                modulation,
+                opaque: None,
+            }
+            .model_2d()
+        })
+        .into_iter()
+        .collect()
+}
+
+fn noseline_draws(
+    char: &NxCharInfo,
+    colors: &CharColors,
+    resources: CharResources,
+) -> Result<Vec<Model2d>, Box<dyn Error>> {
+    let texture = load_texture(
+        colors,
+        resources,
+        (TexturePart::Noseline, usize::from(char.nose_type)),
+        ColorModulated::NoseLineShape,
+    )?;
+
+    Ok(texture_draws(texture))
+}
+
+/// The eyes, eyebrows, mouth, mustache and mole, making `expression`.
+/// # Errors
+/// - See [`mask_texture_meshes_with`]
+pub fn mask_draws(
+    char: &NxCharInfo,
+    colors: &CharColors,
+    resources: CharResources,
+    expression: Expression,
+) -> Result<Vec<Model2d>, Box<dyn Error>> {
+    Ok(mask_texture_meshes_with(
+        char,
+        colors,
+        expression,
+        resources.textures,
+        resources.compressed_formats,
+    )?
+    .all())
+}
+
+/// The mask's texture for `expression`. Swapping it into [`CharModelData::mask`] changes
+/// the character's expression without rebuilding anything else.
+/// # Errors
+/// - See [`mask_texture_meshes_with`]
+pub fn mask_texture(
+    char: &NxCharInfo,
+    colors: &CharColors,
+    resources: CharResources,
+    expression: Expression,
+) -> Result<ProjectedTexture, Box<dyn Error>> {
+    Ok(projected(
+        uvec2(512, 512),
+        mask_draws(char, colors, resources, expression)?,
+    ))
+}
+
+/// The mask's texture for every expression in `expressions`, in `FFLExpression` order.
+/// # Errors
+/// - See [`mask_texture_meshes_with`]
+pub fn expression_masks(
+    char: &NxCharInfo,
+    colors: &CharColors,
+    resources: CharResources,
+    expressions: ExpressionSet,
+) -> Result<Vec<(Expression, ProjectedTexture)>, Box<dyn Error>> {
+    expressions
+        .iter()
+        .map(|expression| {
+            Ok((
+                expression,
+                mask_texture(char, colors, resources, expression)?,
+            ))
+        })
+        .collect()
+}
+
+/// Wrinkles, makeup and beard, drawn over the faceline color.
+fn faceline_draws(
+    char: &NxCharInfo,
+    colors: &CharColors,
+    resources: CharResources,
+) -> Result<Vec<Model2d>, Box<dyn Error>> {
+    // Load faceline textures in order [wrinkle, makeup, beard], and remove any that don't exist
+    let textures = [
+        (char.faceline_wrinkle != 0).then(|| {
+            load_texture(
+                colors,
+                resources,
+                (TexturePart::Wrinkle, usize::from(char.faceline_wrinkle)),
+                ColorModulated::FacelineWrinkle,
+            )
+        }),
+        (char.faceline_make != 0).then(|| {
+            load_texture(
+                colors,
+                resources,
+                (TexturePart::Makeup, usize::from(char.faceline_make)),
+                ColorModulated::FacelineMakeup,
+            )
+        }),
+        (char.beard_type >= 4).then(|| {
+            load_texture(
+                colors,
+                resources,
+                (TexturePart::Beard, usize::from(char.beard_type - 4)),
+                ColorModulated::FacelineBeard,
+            )
+        }),
+    ];
+
+    let mut draws = Vec::new();
+
+    for texture in textures.into_iter().flatten() {
+        let Some((rendered_texture, modulation)) = texture? else {
+            continue;
+        };
+
+        // Check if we are the first to be rendered out, then add an opaque background.
+        // We don't want an opaque redraw happening over our other faceline textures.
+        let opaque = draws.is_empty().then_some(colors.faceline);
+
+        draws.push(
+            DrawableTexture {
+                rendered_texture,
+                modulation,
+                opaque,
+            }
+            .model_2d(),
+        );
+    }
+
+    Ok(draws)
+}
+
+fn glass_draws(
+    char: &NxCharInfo,
+    colors: &CharColors,
+    resources: CharResources,
+) -> Result<Vec<Model2d>, Box<dyn Error>> {
+    let texture = load_texture(
+        colors,
+        resources,
+        (TexturePart::Glass, usize::from(char.glass_type)),
+        ColorModulated::Glass,
+    )?;
+
+    Ok(texture_draws(texture))
+}
+
+fn hat_draws(
+    char: &NxCharInfo,
+    colors: &CharColors,
+    resources: CharResources,
+) -> Result<Vec<Model2d>, Box<dyn Error>> {
+    let texture = load_texture(
+        colors,
+        resources,
+        (TexturePart::Hat, usize::from(char.hair_type)),
+        ColorModulated::Hat,
+    )?;
+
+    Ok(texture_draws(texture))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+
+    #[test]
+    fn packed_normals_are_kept() -> Result<(), Box<dyn Error>> {
+        // X = -512, which doesn't survive unpacking, and the w bits set.
+        let normal = Vec3PackedSnorm(0x200 | (0x1ff << 10) | (3 << 30));
+        let mesh = ShapeMesh {
+            positions: vec![[Float16::from_f32(1.0); 4]],
+            indices: vec![0],
+            normals: Some(vec![normal]),
+            uvs: None,
+            tangents: None,
+            color_params: None,
+        };
+        let placement = PartPlacement {
+            position: Vec3::ZERO,
+            scale: Vec3::ONE,
+        };
+
+        let model = mesh_to_model::<()>(&mesh, Vec4::ONE, placement, None)?;
+
+        assert_eq!(model.packed_vertices()[0].normal.0, normal.0);
+        assert_eq!(model.vertices[0].normal, normal.unpack());
+
+        Ok(())
+    }
+
+    #[test]
+    fn vertex_sizes() {
+        // Tangents and color parameters go in their own buffer.
+        assert_eq!(size_of::<Vertex>(), 24);
+        assert_eq!(size_of::<crate::model::PackedVertex>(), 16);
+        assert_eq!(size_of::<crate::model::VertexParams>(), 8);
+    }
+
+    #[test]
+    fn vertex_params() -> Result<(), Box<dyn Error>> {
+        use crate::model::VertexParams;
+
+        let mut mesh = ShapeMesh {
+            positions: vec![[Float16::from_f32(1.0); 4]; 2],
+            indices: vec![0, 1],
+            normals: Some(vec![Vec3PackedSnorm(0); 2]),
+            uvs: None,
+            tangents: Some(vec![[127, 0, 0, 127], [0, -127, 0, 127]]),
+            color_params: None,
+        };
+        let placement = PartPlacement {
+            position: Vec3::ZERO,
+            scale: Vec3::ONE,
+        };
+
+        let model = mesh_to_model::<()>(&mesh, Vec4::ONE, placement, None)?;
+        assert_eq!(
+            model.vertex_params(),
+            [
+                VertexParams {
+                    tangent: [127, 0, 0, 127],
+                    color_param: [0; 4],
+                },
+                VertexParams {
+                    tangent: [0, -127, 0, 127],
+                    color_param: [0; 4],
+                },
+            ]
+        );
+
+        // Not one per vertex, so left out.
+        mesh.color_params = Some(vec![[255, 0, 0, 0]]);
+        let model = mesh_to_model::<()>(&mesh, Vec4::ONE, placement, None)?;
+        assert!(
+            model
+                .vertex_params()
+                .iter()
+                .all(|params| params.color_param == [0; 4])
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+//! Facial expressions, which change the eyes, eyebrows and mouth drawn on the mask.
+//!
+//! Expressions swap the eye and mouth textures, and move the parts with [`PartOffsets`].
+//! The special textures come right after the regular ones in the texture resource:
+//! 62 eye textures for the 60 eye types, and 37 mouth textures for the 36 mouth types.
+//! There are no special eyebrow textures, so eyebrows only move.
+
+/// Number of `eye_type`s, and so the first special eye texture.
+const EYE_TYPE_COUNT: usize = 60;
+/// Number of `mouth_type`s, and so the special mouth texture.
+const MOUTH_TYPE_COUNT: usize = 36;
+
+/// Eyes closed into a line.
+pub const EYE_TEXTURE_BLINK: usize = EYE_TYPE_COUNT;
+/// Eyes closed into an arc, for smiling.
+pub const EYE_TEXTURE_SMILE: usize = EYE_TYPE_COUNT + 1;
+/// A round open mouth.
+pub const MOUTH_TEXTURE_OPEN: usize = MOUTH_TYPE_COUNT;
+
+/// A facial expression, numbered like FFL's `FFLExpression`.
+/// "Left" is the mask's left eye, `eye[0]` in [`crate::positioning::MaskFaceParts`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Expression {
+    #[default]
+    Normal = 0,
+    Smile,
+    Anger,
+    Sorrow,
+    Surprise,
+    Blink,
+    OpenMouth,
+    Happy,
+    AngerOpenMouth,
+    SorrowOpenMouth,
+    SurpriseOpenMouth,
+    BlinkOpenMouth,
+    WinkLeft,
+    WinkRight,
+    WinkLeftOpenMouth,
+    WinkRightOpenMouth,
+    LikeWinkLeft,
+    LikeWinkRight,
+    Frustrated,
+}
+
+/// Which texture an eye uses in an expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EyeTexture {
+    /// The character's `eye_type`.
+    Own,
+    Blink,
+    Smile,
+}
+
+/// Which texture the mouth uses in an expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouthTexture {
+    /// The character's `mouth_type`.
+    Own,
+    Open,
+}
+
+/// How an expression moves the parts away from the character's own placement.
+/// Each offset is added to the character data field of the same name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PartOffsets {
+    pub eye_rotate: i8,
+    pub eye_scale: i8,
+    pub eyebrow_rotate: i8,
+    pub eyebrow_y: i8,
+}
+
+impl PartOffsets {
+    const NONE: PartOffsets = PartOffsets::new(0, 0, 0, 0);
+    /// Brows slanted in and lowered, eyes tilted with them.
+    const ANGER: PartOffsets = PartOffsets::new(1, 0, 3, 1);
+    /// Brows slanted the other way and raised.
+    const SORROW: PartOffsets = PartOffsets::new(-1, 0, -3, -1);
+    /// Wide eyes under raised brows.
+    const SURPRISE: PartOffsets = PartOffsets::new(0, 1, 0, -2);
+    const HAPPY: PartOffsets = PartOffsets::new(0, 0, 0, -1);
+    /// Shut eyes under sad, lowered brows.
+    const FRUSTRATED: PartOffsets = PartOffsets::new(0, 0, -3, 1);
+
+    const fn new(eye_rotate: i8, eye_scale: i8, eyebrow_rotate: i8, eyebrow_y: i8) -> Self {
+        PartOffsets {
+            eye_rotate,
+            eye_scale,
+            eyebrow_rotate,
+            eyebrow_y,
+        }
+    }
+}
+
+/// Eye textures, mouth texture and part offsets of every expression, in `FFLExpression` order.
+const EXPRESSIONS: [([EyeTexture; 2], MouthTexture, PartOffsets); 19] = {
+    use EyeTexture::{Blink, Own as OwnEye, Smile};
+    use MouthTexture::{Open, Own as OwnMouth};
+
+    [
+        ([OwnEye, OwnEye], OwnMouth, PartOffsets::NONE), // Normal
+        ([Smile, Smile], OwnMouth, PartOffsets::NONE),   // Smile
+        ([OwnEye, OwnEye], OwnMouth, PartOffsets::ANGER), // Anger
+        ([OwnEye, OwnEye], OwnMouth, PartOffsets::SORROW), // Sorrow
+        ([OwnEye, OwnEye], OwnMouth, PartOffsets::SURPRISE), // Surprise
+        ([Blink, Blink], OwnMouth, PartOffsets::NONE),   // Blink
+        ([OwnEye, OwnEye], Open, PartOffsets::NONE),     // OpenMouth
+        ([Smile, Smile], Open, PartOffsets::HAPPY),      // Happy
+        ([OwnEye, OwnEye], Open, PartOffsets::ANGER),    // AngerOpenMouth
+        ([OwnEye, OwnEye], Open, PartOffsets::SORROW),   // SorrowOpenMouth
+        ([OwnEye, OwnEye], Open, PartOffsets::SURPRISE), // SurpriseOpenMouth
+        ([Blink, Blink], Open, PartOffsets::NONE),       // BlinkOpenMouth
+        ([Blink, OwnEye], OwnMouth, PartOffsets::NONE),  // WinkLeft
+        ([OwnEye, Blink], OwnMouth, PartOffsets::NONE),  // WinkRight
+        ([Blink, OwnEye], Open, PartOffsets::NONE),      // WinkLeftOpenMouth
+        ([OwnEye, Blink], Open, PartOffsets::NONE),      // WinkRightOpenMouth
+        ([Smile, OwnEye], Open, PartOffsets::NONE),      // LikeWinkLeft
+        ([OwnEye, Smile], Open, PartOffsets::NONE),      // LikeWinkRight
+        ([Blink, Blink], OwnMouth, PartOffsets::FRUSTRATED), // Frustrated
+    ]
+};
+
+impl Expression {
+    pub const ALL: [Expression; 19] = [
+        Expression::Normal,
+        Expression::Smile,
+        Expression::Anger,
+        Expression::Sorrow,
+        Expression::Surprise,
+        Expression::Blink,
+        Expression::OpenMouth,
+        Expression::Happy,
+        Expression::AngerOpenMouth,
+        Expression::SorrowOpenMouth,
+        Expression::SurpriseOpenMouth,
+        Expression::BlinkOpenMouth,
+        Expression::WinkLeft,
+        Expression::WinkRight,
+        Expression::WinkLeftOpenMouth,
+        Expression::WinkRightOpenMouth,
+        Expression::LikeWinkLeft,
+        Expression::LikeWinkRight,
+        Expression::Frustrated,
+    ];
+
+    /// `FFLExpression` value. Returns `None` past `FFL_EXPRESSION_MAX`.
+    #[must_use]
+    pub fn from_index(index: u8) -> Option<Expression> {
+        Expression::ALL.get(usize::from(index)).copied()
+    }
+
+    /// Textures of the left and right eye.
+    #[must_use]
+    pub fn eyes(self) -> [EyeTexture; 2] {
+        EXPRESSIONS[self as usize].0
+    }
+
+    #[must_use]
+    pub fn mouth(self) -> MouthTexture {
+        EXPRESSIONS[self as usize].1
+    }
+
+    /// How far the parts move from the character's own placement.
+    #[must_use]
+    pub fn offsets(self) -> PartOffsets {
+        EXPRESSIONS[self as usize].2
+    }
+
+    /// Texture indices of the left and right eye, for a character with `eye_type`.
+    #[must_use]
+    pub fn eye_textures(self, eye_type: u8) -> [usize; 2] {
+        self.eyes().map(|eye| match eye {
+            EyeTexture::Own => usize::from(eye_type),
+            EyeTexture::Blink => EYE_TEXTURE_BLINK,
+            EyeTexture::Smile => EYE_TEXTURE_SMILE,
+        })
+    }
+
+    /// Texture index of the mouth, for a character with `mouth_type`.
+    #[must_use]
+    pub fn mouth_texture(self, mouth_type: u8) -> usize {
+        match self.mouth() {
+            MouthTexture::Own => usize::from(mouth_type),
+            MouthTexture::Open => MOUTH_TEXTURE_OPEN,
+        }
+    }
+}
+
+/// A set of expressions, laid out like FFL's `FFLExpressionFlag`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ExpressionSet(pub u32);
+
+impl ExpressionSet {
+    pub const EMPTY: ExpressionSet = ExpressionSet(0);
+
+    #[must_use]
+    pub const fn single(expression: Expression) -> ExpressionSet {
+        ExpressionSet(1 << expression as u8)
+    }
+
+    #[must_use]
+    pub const fn with(self, expression: Expression) -> ExpressionSet {
+        ExpressionSet(self.0 | ExpressionSet::single(expression).0)
+    }
+
+    #[must_use]
+    pub const fn contains(self, expression: Expression) -> bool {
+        self.0 & ExpressionSet::single(expression).0 != 0
+    }
+
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The expressions in the set, in `FFLExpression` order.
+    pub fn iter(self) -> impl Iterator<Item = Expression> {
+        Expression::ALL
+            .into_iter()
+            .filter(move |expression| self.contains(*expression))
+    }
+
+    /// The lowest expression in the set, which FFL shows first.
+    #[must_use]
+    pub fn first(self) -> Option<Expression> {
+        self.iter().next()
+    }
+}
+
+/// Just [`Expression::Normal`].
+impl Default for ExpressionSet {
+    fn default() -> Self {
+        ExpressionSet::single(Expression::Normal)
+    }
+}
+
+impl FromIterator<Expression> for ExpressionSet {
+    fn from_iter<T: IntoIterator<Item = Expression>>(iter: T) -> Self {
+        iter.into_iter()
+            .fold(ExpressionSet::EMPTY, ExpressionSet::with)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indices_match_ffl() {
+        for (index, expression) in Expression::ALL.into_iter().enumerate() {
+            assert_eq!(expression as usize, index);
+            assert_eq!(Expression::from_index(index as u8), Some(expression));
+        }
+        assert_eq!(Expression::from_index(19), None);
+    }
+
+    #[test]
+    fn winks_close_one_eye() {
+        assert_eq!(Expression::WinkLeft.eye_textures(3), [EYE_TEXTURE_BLINK, 3]);
+        assert_eq!(
+            Expression::WinkRight.eye_textures(3),
+            [3, EYE_TEXTURE_BLINK]
+        );
+        assert_eq!(Expression::Normal.mouth_texture(7), 7);
+        assert_eq!(Expression::Happy.mouth_texture(7), MOUTH_TEXTURE_OPEN);
+    }
+
+    #[test]
+    fn special_textures_follow_the_regular_ones() {
+        assert_eq!(Expression::Smile.eye_textures(3), [61, 61]);
+        assert_eq!(Expression::Frustrated.eye_textures(3), [60, 60]);
+        assert_eq!(Expression::LikeWinkRight.eye_textures(59), [59, 61]);
+        assert_eq!(Expression::SurpriseOpenMouth.mouth_texture(35), 36);
+        assert_eq!(Expression::Anger.mouth_texture(35), 35);
+    }
+
+    #[test]
+    fn set_round_trips() {
+        let set: ExpressionSet = [Expression::Frustrated, Expression::Blink]
+            .into_iter()
+            .collect();
+
+        assert!(set.contains(Expression::Blink));
+        assert!(!set.contains(Expression::Normal));
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            [Expression::Blink, Expression::Frustrated]
+        );
+        assert_eq!(set.first(), Some(Expression::Blink));
+        assert_eq!(ExpressionSet::default().first(), Some(Expression::Normal));
+    }
+}
@@ -1,6 +1,12 @@
 //! Library for turning parsed models into real vertex and texture data, ready for rendering.
+pub mod animation;
+pub mod attachment;
+pub mod bounds;
 pub mod building;
+pub mod char_model;
+pub mod expression;
 pub mod model;
+pub mod placement;
 pub mod positioning;
 
 pub use model::GenericModel3d;
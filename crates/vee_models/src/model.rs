@@ -1,10 +1,16 @@
 //! Representing models, and other related structures.
 
+use crate::bounds::Aabb;
 use crate::building::trivial_quad;
 use glam::{Mat4, Vec3, Vec4, vec3};
-use image::DynamicImage;
+use image::{DynamicImage, RgbaImage};
+use std::error::Error;
+use std::iter::zip;
+use std::sync::Arc;
 use vee_resources::color::nx::ModulationIntent;
-use vee_resources::packing::Float16;
+use vee_resources::packing::{Float16, Vec3PackedSnorm};
+use vee_resources::source::TextureSource;
+use vee_resources::tex::{CompressedTexture, ResourceTextureFormat, TexturePart};
 
 type Color = [f32; 4];
 
@@ -18,9 +24,97 @@ pub struct Vertex {
     pub normal: [f32; 3],
 }
 
+/// A [`Vertex`] with its normal left packed, to be unpacked in a shader.
+/// 16 bytes instead of 24.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PackedVertex {
+    pub position: [Float16; 3],
+    pub _pad: u16,
+    pub tex_coords: [Float16; 2],
+    pub normal: Vec3PackedSnorm,
+}
+
+impl PackedVertex {
+    /// `vertex`, with `normal` in place of its unpacked one.
+    #[must_use]
+    pub fn new(vertex: Vertex, normal: Vec3PackedSnorm) -> PackedVertex {
+        PackedVertex {
+            position: vertex.position,
+            _pad: 0,
+            tex_coords: vertex.tex_coords,
+            normal,
+        }
+    }
+}
+
+/// The tangent and color parameter of a vertex, uploaded as a second vertex buffer
+/// next to [`Vertex`] or [`PackedVertex`]. Zeroed for models that don't have them.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VertexParams {
+    /// Snorm, the direction hair strands run in, for its highlight.
+    pub tangent: [i8; 4],
+    /// Unorm, the first channel is how strong the highlight is.
+    pub color_param: [u8; 4],
+}
+
+/// The texture of a [`Model2d`]. Either already decoded, or still block compressed
+/// for a GPU that can decode it itself.
+#[derive(Debug, Clone)]
+pub enum ModelTexture {
+    Image(DynamicImage),
+    /// A decoded texture shared with the source it was read from, e.g. a resource cache.
+    Shared(Arc<RgbaImage>),
+    Compressed(CompressedTexture),
+}
+
+impl ModelTexture {
+    /// Reads a texture from `textures`, keeping it compressed if it's in one of
+    /// `compressed_formats`, and decoding it otherwise.
+    /// Returns `None` if the texture doesn't exist.
+    ///
+    /// # Errors
+    /// - The texture data is malformed
+    pub fn load(
+        textures: &dyn TextureSource,
+        part: TexturePart,
+        index: usize,
+        compressed_formats: &[ResourceTextureFormat],
+    ) -> Result<Option<ModelTexture>, Box<dyn Error>> {
+        if !compressed_formats.is_empty()
+            && let Some(texture) = textures.compressed_texture(part, index)?
+        {
+            let (block_size, _) = texture.format.block_layout();
+
+            // GPUs only take compressed textures that are a whole number of blocks.
+            if compressed_formats.contains(&texture.format)
+                && texture.width % block_size == 0
+                && texture.height % block_size == 0
+            {
+                return Ok(Some(ModelTexture::Compressed(texture)));
+            }
+
+            return Ok(Some(ModelTexture::Image(DynamicImage::ImageRgba8(
+                texture.decode()?,
+            ))));
+        }
+
+        Ok(textures
+            .texture_shared(part, index)?
+            .map(ModelTexture::Shared))
+    }
+}
+
+impl From<DynamicImage> for ModelTexture {
+    fn from(image: DynamicImage) -> Self {
+        ModelTexture::Image(image)
+    }
+}
+
 /// A texture prepared to be drawn onto a quad.
 pub struct DrawableTexture {
-    pub rendered_texture: DynamicImage,
+    pub rendered_texture: ModelTexture,
     pub modulation: ModulationIntent,
     pub opaque: Option<[f32; 4]>,
 }
@@ -51,7 +145,7 @@ impl DrawableTexture {
 pub struct Model2d {
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
-    pub tex: DynamicImage,
+    pub tex: ModelTexture,
     pub mvp_matrix: Mat4,
     pub modulation: ModulationIntent,
     pub opaque: Option<Color>,
@@ -62,9 +156,66 @@ pub struct Model2d {
 #[derive(Default, Debug)]
 pub struct GenericModel3d<Tex> {
     pub vertices: Vec<Vertex>,
+    /// Normals as packed in the shape, one per vertex. Empty if the model isn't from a shape,
+    /// or its normals have changed since.
+    pub packed_normals: Vec<Vec3PackedSnorm>,
+    /// Snorm tangents from the shape, one per vertex, for anisotropic hair specular.
+    /// Drawn from [`Self::vertex_params`].
+    pub tangents: Option<Vec<[i8; 4]>>,
+    /// Unorm color parameters from the shape, one per vertex. Drawn from [`Self::vertex_params`].
+    pub color_params: Option<Vec<[u8; 4]>>,
     pub indices: Vec<u32>,
     pub color: Vec4,
     pub texture: Option<Tex>,
     pub position: Vec3,
     pub scale: Vec3,
 }
+
+impl<Tex> GenericModel3d<Tex> {
+    /// The vertices as [`PackedVertex`]. Normals are taken from [`Self::packed_normals`] as
+    /// they are, and only packed from [`Vertex::normal`] without them.
+    #[must_use]
+    pub fn packed_vertices(&self) -> Vec<PackedVertex> {
+        if self.packed_normals.len() == self.vertices.len() {
+            zip(&self.vertices, &self.packed_normals)
+                .map(|(vertex, normal)| PackedVertex::new(*vertex, *normal))
+                .collect()
+        } else {
+            self.vertices
+                .iter()
+                .map(|vertex| PackedVertex::new(*vertex, Vec3PackedSnorm::pack(vertex.normal)))
+                .collect()
+        }
+    }
+
+    /// [`Self::tangents`] and [`Self::color_params`], one per vertex.
+    /// Either is zeroed if it's missing or doesn't have a value for every vertex.
+    #[must_use]
+    pub fn vertex_params(&self) -> Vec<VertexParams> {
+        let count = self.vertices.len();
+        let tangents = self
+            .tangents
+            .as_ref()
+            .filter(|values| values.len() == count);
+        let color_params = self
+            .color_params
+            .as_ref()
+            .filter(|values| values.len() == count);
+
+        (0..count)
+            .map(|i| VertexParams {
+                tangent: tangents.map_or([0; 4], |tangents| tangents[i]),
+                color_param: color_params.map_or([0; 4], |params| params[i]),
+            })
+            .collect()
+    }
+
+    /// Bounds of the vertices after `scale` and `position` are applied.
+    /// `None` without any vertices.
+    #[must_use]
+    pub fn bounds(&self) -> Option<Aabb> {
+        Aabb::from_points(self.vertices.iter().map(|vertex| {
+            Vec3::from_array(vertex.position.map(Float16::as_f32)) * self.scale + self.position
+        }))
+    }
+}
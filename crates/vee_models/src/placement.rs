@@ -0,0 +1,97 @@
+//! Which meshes make up a head, and where each one goes within it.
+use glam::{Mat4, Vec3, vec3};
+use vee_parse::NxCharInfo;
+use vee_resources::shape::{ResourceShapeFacelineTransform, Shape};
+
+/// Every shape a head can be made of.
+pub const HEAD_SHAPES: [Shape; 9] = [
+    Shape::FaceLine,
+    Shape::ForeheadNormal,
+    Shape::HairNormal,
+    Shape::Mask,
+    Shape::Nose,
+    Shape::NoseLine,
+    Shape::Glasses,
+    Shape::Beard,
+    Shape::HatNormal,
+];
+
+/// Which mesh of `shape` a character uses.
+/// Returns `None` for parts the character doesn't have, like glasses when it isn't wearing any.
+#[must_use]
+pub fn shape_index(char: &NxCharInfo, shape: Shape) -> Option<u8> {
+    match shape {
+        Shape::FaceLine | Shape::Mask => Some(char.faceline_type),
+        Shape::ForeheadNormal | Shape::HairNormal | Shape::HatNormal => Some(char.hair_type),
+        Shape::Nose | Shape::NoseLine => Some(char.nose_type),
+        Shape::Glasses => (char.glass_type != 0).then_some(0),
+        // Beard types past 3 are drawn on the faceline texture instead.
+        Shape::Beard => (char.beard_type < 4 && char.beard_type != 0).then_some(char.beard_type),
+        _ => None,
+    }
+}
+
+/// Offset and scale of a part within the head. The shader applies them as
+/// `vertex * scale + position`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PartPlacement {
+    pub position: Vec3,
+    /// Negative on X for flipped hair.
+    pub scale: Vec3,
+}
+
+impl PartPlacement {
+    #[must_use]
+    pub fn new(
+        char: &NxCharInfo,
+        shape: Shape,
+        faceline: &ResourceShapeFacelineTransform,
+    ) -> PartPlacement {
+        // Some meshes need positioning.
+        let position = match shape {
+            Shape::HairNormal | Shape::ForeheadNormal | Shape::HatNormal => {
+                // FFLiCharModelCreator.cpp :638
+                Vec3::from_array(faceline.hair_translate)
+            }
+            Shape::Beard => Vec3::from_array(faceline.beard_translate),
+            Shape::Nose | Shape::NoseLine => {
+                let nose = Vec3::from_array(faceline.nose_translate);
+                let nose_y = f32::from(char.nose_y);
+
+                // FFLiCharModelCreator.cpp :638
+                vec3(nose.x, nose.y + (nose_y - 8.0) * -1.5, nose.z)
+            }
+            Shape::Glasses => {
+                let nose = Vec3::from_array(faceline.nose_translate);
+                let glass_y = f32::from(char.glass_y);
+
+                // FFLiCharModelCreator.cpp fn:InitShapes
+                vec3(nose.x, nose.y + (glass_y - 11.0) * -1.5 + 5.0, nose.z + 2.0)
+            }
+            _ => Vec3::ZERO,
+        };
+
+        let scale = match shape {
+            // RFL_Model.c :784
+            Shape::Glasses => Vec3::splat(0.15 * f32::from(char.glass_scale) + 0.4),
+            // RFL_Model.c :705
+            Shape::Nose | Shape::NoseLine => Vec3::splat(0.175 * f32::from(char.nose_scale) + 0.4),
+            Shape::HairNormal | Shape::ForeheadNormal | Shape::HatNormal => {
+                if char.hair_flip != 0 {
+                    vec3(-1.0, 1.0, 1.0)
+                } else {
+                    Vec3::ONE
+                }
+            }
+            _ => Vec3::ONE,
+        };
+
+        PartPlacement { position, scale }
+    }
+
+    /// Transforms from the mesh's space into the head's.
+    #[must_use]
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::from_translation(self.position) * Mat4::from_scale(self.scale)
+    }
+}
@@ -1,4 +1,5 @@
 //! Positioning (transforming) textures before they become models. Only the mask needs this operation.
+use crate::expression::Expression;
 use crate::{TEX_SCALE_X, TEX_SCALE_Y};
 use vee_parse::NxCharInfo;
 
@@ -8,7 +9,7 @@ const fn tex_scale2dim(scale: f32) -> f32 {
 
 // i16 to not lose precision
 const fn tex_rotate2ang(rotate: i16) -> f32 {
-    (360.0 / 32.0) * (rotate % 32) as f32
+    (360.0 / 32.0) * rotate.rem_euclid(32) as f32
 }
 
 const fn tex_unit(x: f32) -> f32 {
@@ -47,10 +48,16 @@ const fn eye_rot_offset(i: usize) -> u8 {
     32 - OFFSETS[i]
 }
 
-// TODO: impl above
-const EYEBROW_ROT_OFFSET: [u8; 24] = [
-    26, 26, 27, 25, 26, 25, 26, 25, 28, 25, 26, 24, 27, 27, 26, 26, 25, 25, 26, 26, 27, 26, 25, 27,
-];
+// FFLiCharInfo fn FFLiiGetEyebrowRotateOffset
+const fn eyebrow_rot_offset(i: usize) -> u8 {
+    const OFFSETS: [u8; 24] = [
+        26, 26, 27, 25, 26, 25, 26, 25, 28, 25, 26, 24, 27, 27, 26, 26, 25, 25, 26, 26, 27, 26, 25,
+        27,
+    ];
+
+    // Miitomo's extra eyebrows aren't in FFL's table, so use the most common offset.
+    if i < OFFSETS.len() { OFFSETS[i] } else { 26 }
+}
 
 // Found in RFL, no idea what it is
 const RFL_MAGIC_Y_OFFSET: f32 = 1.160_000_1;
@@ -86,20 +93,24 @@ pub struct MaskFaceParts {
 }
 
 impl MaskFaceParts {
-    pub fn init(info: &NxCharInfo, resolution: f32) -> MaskFaceParts {
+    /// Positions `info`'s face parts for a `resolution` wide mask, moved for `expression`.
+    pub fn init(info: &NxCharInfo, expression: Expression, resolution: f32) -> MaskFaceParts {
         // RFLi_TEX_UNIT
         let base_scale = tex_unit(resolution);
+        let offsets = expression.offsets();
 
-        let eye_base_scale = tex_scale2dim(info.eye_scale.into());
+        let eye_base_scale =
+            tex_scale2dim(f32::from(info.eye_scale) + f32::from(offsets.eye_scale));
         let eye_base_scale_y = 0.12 * f32::from(info.eye_aspect) + 0.64;
 
         let eye_spacing_x = TEX_EYE_BASE_X + TEX_SCALE_X * f32::from(info.eye_x);
         let eye_y = TEX_EYE_BASE_Y + RFL_MAGIC_Y_OFFSET * TEX_SCALE_Y * f32::from(info.eye_y);
         let eye_w = TEX_EYE_BASE_W * eye_base_scale;
         let eye_h = TEX_EYE_BASE_H * eye_base_scale * eye_base_scale_y;
-        let eye_a = tex_rotate2ang(i16::from(
-            info.eye_rotate + eye_rot_offset(info.eye_type as usize),
-        ));
+        let eye_a = tex_rotate2ang(
+            i16::from(info.eye_rotate + eye_rot_offset(info.eye_type as usize))
+                + i16::from(offsets.eye_rotate),
+        );
 
         let eye_l = MaskFacePart {
             x: base_scale * (32.0 + eye_spacing_x),
@@ -122,12 +133,15 @@ impl MaskFaceParts {
         let eb_base_scale_y = 0.12 * f32::from(info.eyebrow_aspect) + 0.64;
 
         let eb_spacing_x = TEX_EYEBROW_BASE_X + TEX_SCALE_X * f32::from(info.eyebrow_x);
-        let eb_y =
-            TEX_EYEBROW_BASE_Y + RFL_MAGIC_Y_OFFSET * TEX_SCALE_Y * f32::from(info.eyebrow_y);
+        let eb_y = TEX_EYEBROW_BASE_Y
+            + RFL_MAGIC_Y_OFFSET
+                * TEX_SCALE_Y
+                * (f32::from(info.eyebrow_y) + f32::from(offsets.eyebrow_y));
         let eb_w = TEX_EYEBROW_BASE_W * eb_base_scale;
         let eb_h = TEX_EYEBROW_BASE_H * eb_base_scale * eb_base_scale_y;
         let eb_a = tex_rotate2ang(
-            (info.eyebrow_rotate + EYEBROW_ROT_OFFSET[info.eyebrow_type as usize]).into(),
+            i16::from(info.eyebrow_rotate + eyebrow_rot_offset(info.eyebrow_type as usize))
+                + i16::from(offsets.eyebrow_rotate),
         );
         let eb_l = MaskFacePart {
             x: base_scale * (32.0 + eb_spacing_x),
@@ -209,6 +223,63 @@ impl MaskFaceParts {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use glam::{vec3, vec4};
+    use std::io::Cursor;
+    use vee_parse::generic::{AsGenericChar, FromGenericChar};
+    use vee_parse::{BinRead, CtrStoreData};
+
+    fn char_info() -> NxCharInfo {
+        let data = include_bytes!("../../../resources_here/j0.ffsd");
+        let char = CtrStoreData::read(&mut Cursor::new(data)).unwrap();
+
+        NxCharInfo::from_generic(char.as_generic().unwrap())
+    }
+
+    #[test]
+    fn expressions_move_the_parts() {
+        let char = char_info();
+        let normal = MaskFaceParts::init(&char, Expression::Normal, 256.0);
+
+        // The brows stay where the character has them, mirrored.
+        let [left, right] = normal.eyebrow;
+        assert!(
+            vec3(left.x, left.y, left.angle_deg).abs_diff_eq(vec3(135.117, 109.243, 11.25), 1e-3)
+        );
+        assert!(
+            vec3(right.x, right.y, right.angle_deg)
+                .abs_diff_eq(vec3(120.883, 109.243, 348.75), 1e-3)
+        );
+
+        let corners = |parts: MaskFaceParts| {
+            parts
+                .eye
+                .into_iter()
+                .chain(parts.eyebrow)
+                .map(|part| vec4(part.y, part.width, part.height, part.angle_deg))
+                .collect::<Vec<_>>()
+        };
+        for expression in [Expression::Anger, Expression::Sorrow, Expression::Surprise] {
+            let parts = MaskFaceParts::init(&char, expression, 256.0);
+            assert_ne!(corners(parts), corners(normal), "{expression:?}");
+        }
+
+        // Anger and sorrow slant the brows opposite ways.
+        let anger = MaskFaceParts::init(&char, Expression::Anger, 256.0).eyebrow[0];
+        let sorrow = MaskFaceParts::init(&char, Expression::Sorrow, 256.0).eyebrow[0];
+        let signed = |part: MaskFacePart| (part.angle_deg + 180.0).rem_euclid(360.0) - 180.0;
+        assert!(signed(anger) < signed(left) && signed(left) < signed(sorrow));
+        // Surprise widens the eyes and raises the brows.
+        let surprise = MaskFaceParts::init(&char, Expression::Surprise, 256.0);
+        assert!(surprise.eye[0].width > normal.eye[0].width);
+        assert!(surprise.eyebrow[0].y < left.y);
+        // Winking only swaps textures.
+        assert_eq!(
+            corners(MaskFaceParts::init(&char, Expression::WinkLeft, 256.0)),
+            corners(normal)
+        );
+    }
+
     // use crate::res::shape::nx::{ResourceShape, SHAPE_MID_DAT};
     // use binrw::BinRead;
     // use std::error::Error;
@@ -0,0 +1,56 @@
+//! The CRC used to guard `StoreData` formats.
+//!
+//! Every platform uses the same CRC-16 (CCITT polynomial, zero initial value),
+//! stored big-endian in the last two bytes of the structure.
+
+const POLYNOMIAL: u16 = 0x1021;
+
+/// Calculates the checksum of `data`.
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ (u16::from(byte) << 8), |crc, _| {
+            if crc & 0x8000 == 0 {
+                crc << 1
+            } else {
+                (crc << 1) ^ POLYNOMIAL
+            }
+        })
+    })
+}
+
+/// Splits `StoreData` into its body and stored checksum,
+/// returning `(stored, calculated)`.
+///
+/// Returns `None` if `data` is too short to have a checksum.
+pub fn store_data_checksums(data: &[u8]) -> Option<(u16, u16)> {
+    let (body, stored) = data.split_last_chunk::<2>()?;
+
+    Some((u16::from_be_bytes(*stored), crc16(body)))
+}
+
+/// Whether the checksum at the end of `StoreData` matches its contents.
+pub fn verify_store_data(data: &[u8]) -> bool {
+    store_data_checksums(data).is_some_and(|(stored, calculated)| stored == calculated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_value() {
+        // CRC-16/XMODEM check value.
+        assert_eq!(crc16(b"123456789"), 0x31C3);
+    }
+
+    #[test]
+    fn verify() {
+        let mut data = b"123456789".to_vec();
+        data.extend_from_slice(&crc16(&data).to_be_bytes());
+
+        assert!(verify_store_data(&data));
+
+        data[0] ^= 1;
+        assert!(!verify_store_data(&data));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::checksum::crc16;
 use crate::error::CharConversionError;
 use crate::generic::{
     AsGenericChar, Beard, Body, CreationData, CtrCreationData, Eye, Eyebrow, Faceline,
@@ -7,8 +8,10 @@ use crate::generic::{
 use crate::seal::Sealant;
 use crate::{FixedLengthWideString, GenericChar, u8_to_bool};
 use GenericColor as Color;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use bilge::prelude::*;
-use binrw::{BinRead, BinWrite, binrw};
+use binrw::{BinRead, BinWrite, binrw, io::Cursor};
 use vee_parse_macros::bitfield;
 
 #[bitfield(32)]
@@ -184,6 +187,17 @@ pub struct CtrStoreData {
 
 impl Sealant for CtrStoreData {}
 
+impl CtrStoreData {
+    /// Recalculates [`Self::crc`] after the data has been modified.
+    pub fn update_crc(&mut self) {
+        // The checksum covers everything before it, and is stored big-endian.
+        let mut bytes = Cursor::new(Vec::new());
+        self.write(&mut bytes).expect("writing to a Vec can't fail");
+        let bytes = bytes.into_inner();
+        self.crc = crc16(&bytes[..bytes.len() - 2]).swap_bytes();
+    }
+}
+
 impl AsGenericChar for CtrStoreData {
     fn as_generic(&self) -> Result<GenericChar, CharConversionError> {
         let char = GenericChar {
@@ -290,10 +304,8 @@ impl AsGenericChar for CtrStoreData {
                 },
             },
             meta_data: MetaData {
-                special: {
-                    println!("Warn: Special flag is NOT being read. Placeholder value used.");
-                    false
-                },
+                // TODO: The special flag isn't read yet.
+                special: false,
                 favorite_color: Color::favorite_color(
                     self.personal_info_2.favorite_color().as_u8(),
                 ),
@@ -313,7 +325,7 @@ impl FromGenericChar for CtrStoreData {
             color.raw_index()
         }
 
-        CtrStoreData {
+        let mut store_data = CtrStoreData {
             // Personal info 1 - metadata about the Mii
             personal_info_1: PersonalInfo1Field::new(
                 0,
@@ -442,8 +454,12 @@ impl FromGenericChar for CtrStoreData {
 
             creator_name: FixedLengthWideString::from_string(String::new()),
             padding: 0,
-            crc: 0, // TODO
-        }
+            crc: 0,
+        };
+
+        store_data.update_crc();
+
+        store_data
     }
 }
 
@@ -1,3 +1,5 @@
+use crate::format::CharFormat;
+use alloc::string::String;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -5,3 +7,25 @@ pub enum CharConversionError {
     #[error("Field `{0}` is out of bounds.")]
     FieldOob(String),
 }
+
+/// Errors from detecting, reading or writing a [`CharFormat`].
+#[derive(Error, Debug)]
+pub enum FormatError {
+    #[error("Data of length {0} does not match any known character format.")]
+    UnknownFormat(usize),
+    #[error("Checksum mismatch: stored {stored:#06x}, calculated {calculated:#06x}.")]
+    Checksum { stored: u16, calculated: u16 },
+    #[error("Writing {0} data is not supported.")]
+    Unsupported(CharFormat),
+    // `binrw::Error` only implements `Error` with `std`, so it can't be a `#[source]`.
+    #[error("{0}")]
+    Read(binrw::Error),
+    #[error(transparent)]
+    Conversion(#[from] CharConversionError),
+}
+
+impl From<binrw::Error> for FormatError {
+    fn from(error: binrw::Error) -> Self {
+        FormatError::Read(error)
+    }
+}
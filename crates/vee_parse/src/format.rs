@@ -0,0 +1,292 @@
+//! Detecting, reading and writing character data when the format isn't known up front.
+//!
+//! Character data doesn't carry a magic number, so formats are told apart by their size.
+//! The only ambiguity is Rvl/Ntr, which only differ in endianness;
+//! these are told apart by the byte order of the first character of the name.
+//!
+//! ```no_run
+//! use vee_parse::format::{self, CharFormat};
+//!
+//! # fn main() -> Result<(), vee_parse::error::FormatError> {
+//! let data = std::fs::read("./Alice.ffsd").unwrap();
+//!
+//! let (format, char) = format::read_generic(&data)?;
+//! assert_eq!(format, CharFormat::CtrStoreData);
+//!
+//! let charinfo = CharFormat::NxCharInfo.write_generic(char)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    CtrStoreData, GenericChar, NtrCharData, NtrStoreData, NxCharInfo, RvlCharData, RvlStoreData,
+    StudioCharInfo,
+    checksum::store_data_checksums,
+    error::{CharConversionError, FormatError},
+    generic::{AsGenericChar, FromGenericChar},
+    studio::{
+        STUDIO_DATA_ENCODED_LENGTH, studio_url_obfuscation_decode, studio_url_obfuscation_encode,
+    },
+};
+use alloc::{string::ToString, vec::Vec};
+use binrw::{BinRead, BinWrite, io::Cursor};
+use core::fmt;
+
+/// Every character data format that can be read through this module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CharFormat {
+    NxCharInfo,
+    CtrStoreData,
+    RvlCharData,
+    RvlStoreData,
+    NtrCharData,
+    NtrStoreData,
+    StudioCharInfo,
+    /// [`StudioCharInfo`], as obfuscated in Mii Studio URLs.
+    StudioObfuscated,
+}
+
+impl CharFormat {
+    pub const ALL: [CharFormat; 8] = [
+        CharFormat::NxCharInfo,
+        CharFormat::CtrStoreData,
+        CharFormat::RvlCharData,
+        CharFormat::RvlStoreData,
+        CharFormat::NtrCharData,
+        CharFormat::NtrStoreData,
+        CharFormat::StudioCharInfo,
+        CharFormat::StudioObfuscated,
+    ];
+
+    /// Size of the format, in bytes.
+    pub const fn size(self) -> usize {
+        match self {
+            CharFormat::NxCharInfo => 0x58,
+            CharFormat::CtrStoreData => 0x60,
+            CharFormat::RvlCharData | CharFormat::NtrCharData => 0x4A,
+            CharFormat::RvlStoreData | CharFormat::NtrStoreData => 0x4C,
+            CharFormat::StudioCharInfo => STUDIO_DATA_ENCODED_LENGTH - 1,
+            CharFormat::StudioObfuscated => STUDIO_DATA_ENCODED_LENGTH,
+        }
+    }
+
+    /// Short name of the format. This is the usual file extension, where there is one.
+    pub const fn name(self) -> &'static str {
+        match self {
+            CharFormat::NxCharInfo => "charinfo",
+            CharFormat::CtrStoreData => "ffsd",
+            CharFormat::RvlCharData => "rcd",
+            CharFormat::RvlStoreData => "rsd",
+            CharFormat::NtrCharData => "ncd",
+            CharFormat::NtrStoreData => "nsd",
+            CharFormat::StudioCharInfo => "mnms",
+            CharFormat::StudioObfuscated => "studio",
+        }
+    }
+
+    /// Inverse of [`Self::name`].
+    pub fn from_name(name: &str) -> Option<CharFormat> {
+        CharFormat::ALL
+            .into_iter()
+            .find(|format| format.name() == name)
+    }
+
+    /// Whether the format ends with a checksum. See [`crate::checksum`].
+    pub const fn has_checksum(self) -> bool {
+        matches!(
+            self,
+            CharFormat::CtrStoreData | CharFormat::RvlStoreData | CharFormat::NtrStoreData
+        )
+    }
+
+    /// Guesses the format of `data`. Returns `None` if the size doesn't match any format.
+    pub fn detect(data: &[u8]) -> Option<CharFormat> {
+        // Rvl/Ntr names start at offset 2. An ASCII character has a null high byte,
+        // which comes first on big-endian Rvl.
+        let is_ntr = || matches!(data.get(2..4), Some([low, 0]) if *low != 0);
+
+        match data.len() {
+            0x58 => Some(CharFormat::NxCharInfo),
+            0x60 => Some(CharFormat::CtrStoreData),
+            0x4A if is_ntr() => Some(CharFormat::NtrCharData),
+            0x4A => Some(CharFormat::RvlCharData),
+            0x4C if is_ntr() => Some(CharFormat::NtrStoreData),
+            0x4C => Some(CharFormat::RvlStoreData),
+            46 => Some(CharFormat::StudioCharInfo),
+            47 => Some(CharFormat::StudioObfuscated),
+            _ => None,
+        }
+    }
+
+    /// Checks the checksum at the end of `data`.
+    /// Formats without a checksum always pass.
+    ///
+    /// # Errors
+    /// - The checksum doesn't match
+    pub fn verify_checksum(self, data: &[u8]) -> Result<(), FormatError> {
+        if !self.has_checksum() {
+            return Ok(());
+        }
+
+        match store_data_checksums(data) {
+            Some((stored, calculated)) if stored == calculated => Ok(()),
+            Some((stored, calculated)) => Err(FormatError::Checksum { stored, calculated }),
+            None => Err(FormatError::UnknownFormat(data.len())),
+        }
+    }
+
+    /// Reads `data` in this format.
+    ///
+    /// # Errors
+    /// - `data` is the wrong size for this format
+    /// - `data` can't be read or converted
+    pub fn read_generic(self, data: &[u8]) -> Result<GenericChar, FormatError> {
+        if data.len() != self.size() {
+            return Err(FormatError::UnknownFormat(data.len()));
+        }
+
+        let mut reader = Cursor::new(data);
+
+        let char = match self {
+            CharFormat::NxCharInfo => NxCharInfo::read(&mut reader)?.as_generic(),
+            CharFormat::CtrStoreData => CtrStoreData::read(&mut reader)?.as_generic(),
+            CharFormat::RvlCharData => RvlCharData::read(&mut reader)?.as_generic(),
+            CharFormat::RvlStoreData => RvlStoreData::read(&mut reader)?.as_generic(),
+            CharFormat::NtrCharData => NtrCharData::read(&mut reader)?.as_generic(),
+            CharFormat::NtrStoreData => NtrStoreData::read(&mut reader)?.as_generic(),
+            CharFormat::StudioCharInfo => StudioCharInfo::read(&mut reader)?.as_generic(),
+            CharFormat::StudioObfuscated => {
+                let mut data = data.to_vec();
+                studio_url_obfuscation_decode(&mut data);
+
+                StudioCharInfo::read(&mut Cursor::new(&data))?.as_generic()
+            }
+        }?;
+
+        Ok(char)
+    }
+
+    /// Writes `char` in this format. The character is validated first (see [`NxCharInfo::validate`]).
+    ///
+    /// Only formats with a [`FromGenericChar`] implementation can be written.
+    ///
+    /// # Errors
+    /// - The format can't be written
+    /// - `char` has out of range fields, for Nx or for the target format
+    pub fn write_generic(self, char: GenericChar) -> Result<Vec<u8>, FormatError> {
+        // Going through `NxCharInfo` validates ranges, so that the packed formats can't overflow.
+        let nx = NxCharInfo::from_generic(char);
+        nx.validate()?;
+
+        let mut writer = Cursor::new(Vec::with_capacity(self.size()));
+
+        match self {
+            CharFormat::NxCharInfo => nx.write(&mut writer)?,
+            CharFormat::CtrStoreData => {
+                // Cafe has fewer glasses than Nx.
+                if nx.glass_type > 8 {
+                    return Err(CharConversionError::FieldOob("glass::ty".to_string()).into());
+                }
+                CtrStoreData::from_generic(nx.as_generic()?).write(&mut writer)?;
+            }
+            CharFormat::StudioCharInfo => {
+                StudioCharInfo::from_generic(nx.as_generic()?).write(&mut writer)?;
+            }
+            CharFormat::StudioObfuscated => {
+                StudioCharInfo::from_generic(nx.as_generic()?).write(&mut writer)?;
+
+                let data: [u8; STUDIO_DATA_ENCODED_LENGTH - 1] = writer
+                    .get_ref()
+                    .as_slice()
+                    .try_into()
+                    .expect("StudioCharInfo is 46 bytes");
+                return Ok(studio_url_obfuscation_encode(&data, 0).to_vec());
+            }
+            format => return Err(FormatError::Unsupported(format)),
+        }
+
+        Ok(writer.into_inner())
+    }
+}
+
+impl fmt::Display for CharFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Detects the format of `data` and reads it.
+///
+/// # Errors
+/// - The format can't be detected
+/// - `data` can't be read or converted
+pub fn read_generic(data: &[u8]) -> Result<(CharFormat, GenericChar), FormatError> {
+    let format = CharFormat::detect(data).ok_or(FormatError::UnknownFormat(data.len()))?;
+
+    Ok((format, format.read_generic(data)?))
+}
+
+/// Detects the format of `data`, and checks that it is well formed:
+/// the checksum matches (where there is one) and every field is in range for Nx.
+///
+/// # Errors
+/// - The format can't be detected
+/// - The checksum doesn't match
+/// - `data` can't be read or converted
+/// - A field is out of range
+pub fn validate(data: &[u8]) -> Result<CharFormat, FormatError> {
+    let format = CharFormat::detect(data).ok_or(FormatError::UnknownFormat(data.len()))?;
+
+    format.verify_checksum(data)?;
+    NxCharInfo::from_generic(format.read_generic(data)?).validate()?;
+
+    Ok(format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+    use core::error::Error;
+
+    type R = Result<(), Box<dyn Error>>;
+
+    // Embedded rather than read from disk, so these also run without `std`.
+    const J0_FFSD: &[u8] = include_bytes!("../../../resources_here/j0.ffsd");
+    const JAIN_RCD: &[u8] = include_bytes!("../../../resources_here/Jain.rcd");
+
+    #[test]
+    fn detect() -> R {
+        assert_eq!(CharFormat::detect(J0_FFSD), Some(CharFormat::CtrStoreData));
+        assert_eq!(CharFormat::detect(JAIN_RCD), Some(CharFormat::RvlCharData));
+        assert_eq!(CharFormat::detect(&[0; 3]), None);
+
+        assert_eq!(validate(J0_FFSD)?, CharFormat::CtrStoreData);
+
+        for format in CharFormat::ALL {
+            assert_eq!(CharFormat::from_name(format.name()), Some(format));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn convert() -> R {
+        let (_, char) = read_generic(J0_FFSD)?;
+
+        let charinfo = CharFormat::NxCharInfo.write_generic(char)?;
+        assert_eq!(charinfo.len(), CharFormat::NxCharInfo.size());
+
+        let (format, char) = read_generic(&charinfo)?;
+        assert_eq!(format, CharFormat::NxCharInfo);
+        assert_eq!(char.name, "Jo Null");
+
+        let ffsd = CharFormat::CtrStoreData.write_generic(char)?;
+        assert_eq!(validate(&ffsd)?, CharFormat::CtrStoreData);
+
+        let studio = CharFormat::StudioObfuscated.write_generic(read_generic(&ffsd)?.1)?;
+        assert_eq!(validate(&studio)?, CharFormat::StudioObfuscated);
+
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{error::CharConversionError, nx::UuidVer4, seal::Sealant};
+use alloc::string::{String, ToString};
 
 pub struct Position {
     pub x: u8,
@@ -40,6 +41,7 @@ pub struct Rotation {
 ///
 /// Cafe and RVL/NTR use separate color tables for each face part,
 /// while Nx uses a 100-color CommonColor table.
+/// RVL/NTR indices line up with Cafe's, but the colors are RFL's.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GenericColor {
     /// 6 colors
@@ -53,6 +55,17 @@ pub enum GenericColor {
     /// 6 colors
     CafeGlass(u8),
 
+    /// 6 colors
+    RvlFaceline(u8),
+    /// 8 colors
+    RvlHair(u8),
+    /// 6 colors
+    RvlEye(u8),
+    /// 3 colors
+    RvlMouth(u8),
+    /// 6 colors
+    RvlGlass(u8),
+
     /// 10 colors
     NxFaceline(u8),
     /// 100 colors
@@ -84,6 +97,26 @@ impl GenericColor {
         GenericColor::CafeGlass(index.min(5))
     }
 
+    pub fn rvl_faceline(index: u8) -> Self {
+        GenericColor::RvlFaceline(index.min(5))
+    }
+
+    pub fn rvl_hair(index: u8) -> Self {
+        GenericColor::RvlHair(index.min(7))
+    }
+
+    pub fn rvl_eye(index: u8) -> Self {
+        GenericColor::RvlEye(index.min(5))
+    }
+
+    pub fn rvl_mouth(index: u8) -> Self {
+        GenericColor::RvlMouth(index.min(2))
+    }
+
+    pub fn rvl_glass(index: u8) -> Self {
+        GenericColor::RvlGlass(index.min(5))
+    }
+
     pub fn nx_faceline(index: u8) -> Self {
         GenericColor::NxFaceline(index.min(9))
     }
@@ -107,6 +140,11 @@ impl GenericColor {
             GenericColor::CafeEye(i) => *i,
             GenericColor::CafeMouth(i) => *i,
             GenericColor::CafeGlass(i) => *i,
+            GenericColor::RvlFaceline(i) => *i,
+            GenericColor::RvlHair(i) => *i,
+            GenericColor::RvlEye(i) => *i,
+            GenericColor::RvlMouth(i) => *i,
+            GenericColor::RvlGlass(i) => *i,
             GenericColor::NxFaceline(i) => *i,
             GenericColor::NxCommon(i) => *i,
             GenericColor::FavoriteColor(i) => *i,
@@ -119,19 +157,24 @@ const CAFE_EYE_TO_NX: [u8; 6] = [8, 9, 10, 11, 12, 13];
 const CAFE_MOUTH_TO_NX: [u8; 5] = [19, 20, 21, 22, 23];
 const CAFE_GLASS_TO_NX: [u8; 6] = [8, 14, 15, 16, 17, 18];
 
+// The Nx colors that look closest to RFL's, which `vee_resources` tests against its tables.
+pub const RVL_FACELINE_TO_NX: [u8; 6] = [0, 1, 2, 3, 4, 5];
+pub const RVL_HAIR_TO_NX: [u8; 8] = [0, 1, 2, 3, 9, 5, 6, 91];
+pub const RVL_EYE_TO_NX: [u8; 6] = [8, 9, 10, 11, 12, 13];
+pub const RVL_MOUTH_TO_NX: [u8; 3] = [19, 20, 21];
+pub const RVL_GLASS_TO_NX: [u8; 6] = [0, 14, 15, 16, 17, 18];
+
 impl GenericColor {
     /// Convert this color to a Nx color representation
     ///
     /// This is infallible because all Cafe colors have equivalent Nx colors.
     /// The reverse (Nx -> Cafe) is fallible because Nx has more colors.
     pub fn to_nx(&self) -> GenericColor {
-        dbg!(&self);
         match self {
             // First 6 Nx indices match Cafe directly
             GenericColor::CafeFaceline(i) => GenericColor::NxFaceline(*i),
 
             GenericColor::CafeHair(i) => {
-                dbg!(i);
                 let nx_index = CAFE_HAIR_TO_NX[*i as usize];
                 GenericColor::NxCommon(nx_index)
             }
@@ -148,6 +191,23 @@ impl GenericColor {
                 GenericColor::NxCommon(nx_index)
             }
 
+            // Rvl colors are matched by how they look, as RFL's palette differs from Nx's.
+            GenericColor::RvlFaceline(i) => {
+                GenericColor::NxFaceline(RVL_FACELINE_TO_NX[usize::from(*i).min(5)])
+            }
+            GenericColor::RvlHair(i) => {
+                GenericColor::NxCommon(RVL_HAIR_TO_NX[usize::from(*i).min(7)])
+            }
+            GenericColor::RvlEye(i) => {
+                GenericColor::NxCommon(RVL_EYE_TO_NX[usize::from(*i).min(5)])
+            }
+            GenericColor::RvlMouth(i) => {
+                GenericColor::NxCommon(RVL_MOUTH_TO_NX[usize::from(*i).min(2)])
+            }
+            GenericColor::RvlGlass(i) => {
+                GenericColor::NxCommon(RVL_GLASS_TO_NX[usize::from(*i).min(5)])
+            }
+
             // Already Nx, return as-is
             GenericColor::NxFaceline(_) | GenericColor::NxCommon(_) => *self,
 
@@ -163,6 +223,7 @@ impl GenericColor {
     pub fn to_cafe_faceline(&self) -> Option<GenericColor> {
         match self {
             GenericColor::CafeFaceline(_) => Some(*self),
+            GenericColor::RvlFaceline(i) => Some(GenericColor::CafeFaceline(*i)),
             GenericColor::NxFaceline(i) if *i < 6 => Some(GenericColor::CafeFaceline(*i)),
             _ => None,
         }
@@ -171,6 +232,7 @@ impl GenericColor {
     pub fn to_cafe_hair(&self) -> Option<GenericColor> {
         match self {
             GenericColor::CafeHair(_) => Some(*self),
+            GenericColor::RvlHair(i) => Some(GenericColor::CafeHair(*i)),
             GenericColor::NxCommon(nx_idx) => {
                 let common_idx = nx_idx;
                 CAFE_HAIR_TO_NX
@@ -185,6 +247,7 @@ impl GenericColor {
     pub fn to_cafe_eye(&self) -> Option<GenericColor> {
         match self {
             GenericColor::CafeEye(_) => Some(*self),
+            GenericColor::RvlEye(i) => Some(GenericColor::CafeEye(*i)),
             GenericColor::NxCommon(nx_idx) => {
                 let common_idx = nx_idx;
                 CAFE_EYE_TO_NX
@@ -199,6 +262,7 @@ impl GenericColor {
     pub fn to_cafe_mouth(&self) -> Option<GenericColor> {
         match self {
             GenericColor::CafeMouth(_) => Some(*self),
+            GenericColor::RvlMouth(i) => Some(GenericColor::CafeMouth(*i)),
             GenericColor::NxCommon(nx_idx) => {
                 let common_idx = nx_idx;
                 CAFE_MOUTH_TO_NX
@@ -213,6 +277,7 @@ impl GenericColor {
     pub fn to_cafe_glass(&self) -> Option<GenericColor> {
         match self {
             GenericColor::CafeGlass(_) => Some(*self),
+            GenericColor::RvlGlass(i) => Some(GenericColor::CafeGlass(*i)),
             GenericColor::NxCommon(nx_idx) => {
                 let common_idx = nx_idx;
                 CAFE_GLASS_TO_NX
@@ -277,6 +342,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_rvl_keeps_cafe_indices() {
+        assert_eq!(
+            GenericColor::rvl_hair(3).to_cafe_hair(),
+            Some(GenericColor::CafeHair(3))
+        );
+        assert_eq!(
+            GenericColor::rvl_mouth(7).to_cafe_mouth(),
+            Some(GenericColor::CafeMouth(2))
+        );
+        assert!(GenericColor::rvl_eye(0).to_cafe_hair().is_none());
+    }
+
     #[test]
     fn test_cafe_to_nx_faceline() {
         // First 6 faceline colors map directly
@@ -1,5 +1,13 @@
 //! Library for parsing and converting Mii character data.
 //!
+//! Works without `std` (but with `alloc`) when the default `std` feature is disabled.
+//! To check this, build for a target without `std`, and run the tests without `std`:
+//!
+//! ```sh
+//! cargo check -p vee_parse --no-default-features --target thumbv7em-none-eabihf
+//! cargo test -p vee_parse --no-default-features --lib
+//! ```
+//!
 //! # Parsing
 //!
 //! There are, roughly, two kinds of Mii data type.
@@ -81,15 +89,23 @@
 //!
 //! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod checksum;
 pub mod ctr;
 pub mod error;
+pub mod format;
 pub mod generic;
 pub mod nx;
 pub mod rvl_ntr;
 pub mod studio;
 
 use crate::error::CharConversionError;
+use alloc::string::String;
 pub use binrw::{BinRead, NullWideString, binrw};
+use core::fmt;
 pub use ctr::CtrStoreData;
 pub use generic::GenericChar;
 pub use nx::NxCharInfo;
@@ -120,20 +136,47 @@ pub(crate) mod seal {
 pub struct FixedLengthWideString<const CHARS: usize>(pub [u16; CHARS]);
 
 impl<const N: usize> FixedLengthWideString<N> {
-    fn from_string(str: String) -> FixedLengthWideString<N> {
-        let vec: Vec<u16> = str.encode_utf16().collect();
-        FixedLengthWideString(vec.try_into().unwrap_or([0; N]))
+    /// Truncates to `N` characters, and pads the rest with nulls.
+    pub fn from_string(str: String) -> FixedLengthWideString<N> {
+        let mut chars = [0; N];
+        for (char, unit) in chars.iter_mut().zip(str.encode_utf16()) {
+            *char = unit;
+        }
+        FixedLengthWideString(chars)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<const N: usize> serde::Serialize for FixedLengthWideString<N> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> serde::Deserialize<'de> for FixedLengthWideString<N> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let str = String::deserialize(deserializer)?;
+
+        if str.encode_utf16().count() > N {
+            return Err(serde::de::Error::invalid_length(
+                str.encode_utf16().count(),
+                &alloc::format!("at most {N} UTF-16 characters").as_str(),
+            ));
+        }
+
+        Ok(FixedLengthWideString::from_string(str))
     }
 }
 
-impl<const N: usize> std::fmt::Debug for FixedLengthWideString<N> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<const N: usize> fmt::Debug for FixedLengthWideString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "FixedLengthWideString(\"{}\")", self)
     }
 }
 
-impl<const N: usize> std::fmt::Display for FixedLengthWideString<N> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<const N: usize> fmt::Display for FixedLengthWideString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.parse_utf16())
     }
 }
@@ -149,7 +192,7 @@ impl<const N: usize> FixedLengthWideString<N> {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use crate::{CtrStoreData, NxCharInfo, RvlCharData, rvl_ntr::FavoriteColor};
     use binrw::BinRead;
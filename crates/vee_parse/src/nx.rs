@@ -9,6 +9,7 @@ use crate::{
     seal::Sealant,
     u8_to_bool,
 };
+use alloc::string::ToString;
 use binrw::binrw;
 
 /// Wrapper for nn::mii color index.
@@ -19,6 +20,7 @@ pub struct NxColor(pub u8);
 
 /// Simple UuidV4. No logic provided as create info is not useful for anything... yet.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[binrw]
 #[brw(little)]
 pub struct UuidVer4 {
@@ -32,6 +34,7 @@ pub struct UuidVer4 {
 /// For more information on the layout, check the ImHex struct pattern:
 /// [/testbed/nx_charinfo.hexpat](https://github.com/j0lol/vee/blob/main/testbed/nx_charinfo.hexpat)
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[binrw]
 #[brw(little)]
 pub struct NxCharInfo {
@@ -91,6 +94,71 @@ pub struct NxCharInfo {
 
 use GenericColor as Color;
 
+impl NxCharInfo {
+    /// Checks every field is in the range accepted by `nn::mii`.
+    ///
+    /// # Errors
+    /// - A field is out of range. The error names the first offending field.
+    pub fn validate(&self) -> Result<(), CharConversionError> {
+        let fields = [
+            ("font_region", self.font_region, 3),
+            ("favorite_color", self.favorite_color, 11),
+            ("gender", self.gender, 1),
+            ("height", self.height, 127),
+            ("build", self.build, 127),
+            ("is_special", self.is_special, 1),
+            ("region_move", self.region_move, 3),
+            ("faceline_type", self.faceline_type, 11),
+            ("faceline_color", self.faceline_color, 9),
+            ("faceline_wrinkle", self.faceline_wrinkle, 11),
+            ("faceline_make", self.faceline_make, 11),
+            ("hair_type", self.hair_type, 131),
+            ("hair_color", self.hair_color, 99),
+            ("hair_flip", self.hair_flip, 1),
+            ("eye_type", self.eye_type, 59),
+            ("eye_color", self.eye_color, 99),
+            ("eye_scale", self.eye_scale, 7),
+            ("eye_aspect", self.eye_aspect, 6),
+            ("eye_rotate", self.eye_rotate, 7),
+            ("eye_x", self.eye_x, 12),
+            ("eye_y", self.eye_y, 18),
+            ("eyebrow_type", self.eyebrow_type, 23),
+            ("eyebrow_color", self.eyebrow_color, 99),
+            ("eyebrow_scale", self.eyebrow_scale, 8),
+            ("eyebrow_aspect", self.eyebrow_aspect, 6),
+            ("eyebrow_rotate", self.eyebrow_rotate, 11),
+            ("eyebrow_x", self.eyebrow_x, 12),
+            ("eyebrow_y", self.eyebrow_y, 18),
+            ("nose_type", self.nose_type, 17),
+            ("nose_scale", self.nose_scale, 8),
+            ("nose_y", self.nose_y, 18),
+            ("mouth_type", self.mouth_type, 35),
+            ("mouth_color", self.mouth_color, 99),
+            ("mouth_scale", self.mouth_scale, 8),
+            ("mouth_aspect", self.mouth_aspect, 6),
+            ("mouth_y", self.mouth_y, 18),
+            ("beard_color", self.beard_color, 99),
+            ("beard_type", self.beard_type, 5),
+            ("mustache_type", self.mustache_type, 5),
+            ("mustache_scale", self.mustache_scale, 8),
+            ("mustache_y", self.mustache_y, 16),
+            ("glass_type", self.glass_type, 19),
+            ("glass_color", self.glass_color, 99),
+            ("glass_scale", self.glass_scale, 7),
+            ("glass_y", self.glass_y, 20),
+            ("mole_type", self.mole_type, 1),
+            ("mole_scale", self.mole_scale, 8),
+            ("mole_x", self.mole_x, 16),
+            ("mole_y", self.mole_y, 30),
+        ];
+
+        match fields.into_iter().find(|(_, value, max)| value > max) {
+            Some((name, _, _)) => Err(CharConversionError::FieldOob(name.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
 impl Sealant for NxCharInfo {}
 
 impl AsGenericChar for NxCharInfo {
@@ -164,7 +232,7 @@ impl AsGenericChar for NxCharInfo {
                 color: Color::nx_common(self.beard_color),
             },
             mustache: Mustache {
-                ty: self.mustache_y,
+                ty: self.mustache_type,
                 pos: PositionY { y: self.mustache_y },
                 scale: ScaleX {
                     w: self.mustache_scale,
@@ -254,7 +322,7 @@ impl FromGenericChar for NxCharInfo {
             mole_type: char.mole.ty,
             mole_scale: char.mole.scale.w,
             mole_x: char.mole.pos.x,
-            mole_y: char.mole.pos.x,
+            mole_y: char.mole.pos.y,
             reserved: 0,
         }
     }
@@ -9,6 +9,7 @@ use crate::{
     seal::Sealant,
     u8_to_bool,
 };
+use alloc::string::ToString;
 use bilge::prelude::*;
 use binrw::{BinRead, BinWrite, binrw};
 use paste::paste;
@@ -333,7 +334,7 @@ impl AsGenericChar for RvlStoreData {
 
 impl AsGenericChar for RvlCharData {
     fn as_generic(&self) -> Result<GenericChar, CharConversionError> {
-        println!("WARN: Part indices are definitely wrong!");
+        // TODO: Part indices aren't converted yet, so some parts come out wrong.
         Ok(GenericChar {
             name: self.name.to_string(),
 
@@ -348,7 +349,7 @@ impl AsGenericChar for RvlCharData {
 
             faceline: Faceline {
                 ty: self.face.face_type().as_u8(),
-                color: GenericColor::cafe_faceline(self.face.face_color().as_u8()),
+                color: GenericColor::rvl_faceline(self.face.face_color().as_u8()),
                 wrinkle_ty: 0, // TODO: read face_tex
                 makeup_ty: 0,  // TODO: read face_tex
             },
@@ -356,14 +357,14 @@ impl AsGenericChar for RvlCharData {
             hair: Hair {
                 ty: self.hair.hair_type().as_u8(),
                 // Type-safe hair color
-                color: GenericColor::cafe_hair(self.hair.hair_color().as_u8()),
+                color: GenericColor::rvl_hair(self.hair.hair_color().as_u8()),
                 flip: u8_to_bool(self.hair.hair_flip().as_u8(), "hair::flip".to_string())?,
             },
 
             eye: Eye {
                 ty: self.eye.eye_type().as_u8(),
                 // Type-safe eye color
-                color: GenericColor::cafe_eye(self.eye.eye_color().as_u8()),
+                color: GenericColor::rvl_eye(self.eye.eye_color().as_u8()),
                 pos: Position {
                     x: self.eye.eye_x().as_u8(),
                     y: self.eye.eye_y().as_u8(),
@@ -380,7 +381,7 @@ impl AsGenericChar for RvlCharData {
             eyebrow: Eyebrow {
                 ty: self.eyebrow.eyebrow_type().as_u8(),
                 // Type-safe eyebrow color (uses hair color table)
-                color: GenericColor::cafe_hair(self.eyebrow.eyebrow_color().as_u8()),
+                color: GenericColor::rvl_hair(self.eyebrow.eyebrow_color().as_u8()),
                 pos: Position {
                     x: self.eyebrow.eyebrow_x().as_u8(),
                     y: self.eyebrow.eyebrow_y().as_u8(),
@@ -407,7 +408,7 @@ impl AsGenericChar for RvlCharData {
             mouth: Mouth {
                 ty: self.mouth.mouth_type().as_u8(),
                 // Type-safe mouth color
-                color: GenericColor::cafe_mouth(self.mouth.mouth_color().as_u8()),
+                color: GenericColor::rvl_mouth(self.mouth.mouth_color().as_u8()),
                 pos: PositionY {
                     y: self.mouth.mouth_y().as_u8(),
                 },
@@ -420,7 +421,7 @@ impl AsGenericChar for RvlCharData {
             beard: Beard {
                 ty: self.face_hair.beard_type().as_u8(),
                 // Type-safe beard color (uses hair color table)
-                color: GenericColor::cafe_hair(self.face_hair.beard_color().as_u8()),
+                color: GenericColor::rvl_hair(self.face_hair.beard_color().as_u8()),
             },
 
             mustache: Mustache {
@@ -436,7 +437,7 @@ impl AsGenericChar for RvlCharData {
             glass: Glass {
                 ty: self.glass.glass_type().as_u8(),
                 // Type-safe glass color
-                color: GenericColor::cafe_glass(self.glass.glass_color().as_u8()),
+                color: GenericColor::rvl_glass(self.glass.glass_color().as_u8()),
                 pos: PositionY {
                     y: self.glass.glass_y().as_u8(),
                 },
@@ -470,8 +471,32 @@ impl AsGenericChar for RvlCharData {
     }
 }
 
+impl AsGenericChar for NtrStoreData {
+    fn as_generic(&self) -> Result<GenericChar, CharConversionError> {
+        self.data.as_generic()
+    }
+}
+
 impl AsGenericChar for NtrCharData {
     fn as_generic(&self) -> Result<GenericChar, CharConversionError> {
-        todo!() // is anybody going to use this ever?
+        // Fields are identical once read, only the endianness on disk differs.
+        RvlCharData {
+            personal_info: self.personal_info,
+            name: self.name,
+            height: self.height,
+            build: self.build,
+            create_id: self.create_id,
+            face: self.face,
+            hair: self.hair,
+            eyebrow: self.eyebrow,
+            eye: self.eye,
+            nose: self.nose,
+            mouth: self.mouth,
+            glass: self.glass,
+            face_hair: self.face_hair,
+            mole: self.mole,
+            creator_name: self.creator_name,
+        }
+        .as_generic()
     }
 }
@@ -0,0 +1,25 @@
+//! Checks `vee_parse` still builds without `std`, for a bare-metal target.
+//!
+//! Needs the target installed: `rustup target add thumbv7em-none-eabihf`.
+
+use std::process::Command;
+
+const TARGET: &str = "thumbv7em-none-eabihf";
+
+#[test]
+fn builds_without_std() {
+    for features in ["", "serde"] {
+        let output = Command::new(env!("CARGO"))
+            .args(["check", "--package", "vee_parse", "--no-default-features"])
+            .args(["--features", features, "--target", TARGET])
+            .env("CARGO_TARGET_DIR", env!("CARGO_TARGET_TMPDIR"))
+            .output()
+            .expect("cargo should run");
+
+        assert!(
+            output.status.success(),
+            "vee_parse doesn't build for {TARGET} with features {features:?}:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
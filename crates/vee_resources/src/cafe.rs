@@ -0,0 +1,796 @@
+//! Parsing Cafe resource files (`FFLResHigh.dat`, `FFLResMiddle.dat`),
+//! and their Arch (Miitomo) derivative.
+//!
+//! Unlike Nx, shapes and textures share one big-endian file. Every part is
+//! compressed separately, and is decoded into the same [`ShapeMesh`] and [`RgbaImage`]
+//! as the Nx resources.
+//!
+//! ```no_run
+//! use vee_resources::cafe::CafeResourceHeader;
+//! use vee_parse::BinRead;
+//! use std::io::Cursor;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let file = std::fs::read("./FFLResHigh.dat")?;
+//! let header = CafeResourceHeader::read(&mut Cursor::new(&file))?;
+//!
+//! let eye = header.texture.eye[0].get_image(&file)?;
+//! let hair = header.shape.hair_normal[0].mesh(&file)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::packing::{Float16, Vec3PackedSnorm};
+use crate::shape::{ResourceShapeFacelineTransform, ResourceShapeHairTransform, Shape, ShapeMesh};
+use crate::tex::{ResourceTextureFormat, TextureError, TexturePart, expand_uncompressed};
+use binrw::BinRead;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use gx2::SurfaceLevel;
+use image::RgbaImage;
+use std::error::Error;
+use std::io::{Cursor, Read};
+
+mod gx2;
+
+/// f_f_li_resource_header
+///
+/// Shape/texture resource format for FFL.
+/// Each buffer is compressed using zlib (see [`CafeResourcePartsInfo::data`]).
+///
+/// Texture formats are in f_f_li_texture_format (R/RG/RGBA),
+/// shape formats are in (comments next to)
+/// f_f_li_resource_shape_element_type (float/(u)int8/10_10_10_2).
+///
+/// Shapes contain bounding boxes and textures contain mipmaps.
+/// Other than shapes/textures, this file also contains
+/// transform vectors for each faceline and hair shape.
+#[allow(unused)]
+#[derive(BinRead, Clone, Copy, Debug)]
+#[br(big, magic = b"FFRA", assert(unknown[11] == 0x0))]
+pub struct CafeResourceHeader {
+    version: u32,
+    uncompress_buffer_size: u32,
+    expanded_buffer_size: u32,
+    is_expand: u32,
+
+    pub texture: CafeResourceTexture,
+    pub shape: CafeResourceShape,
+
+    unknown: [u32; 12],
+}
+
+impl CafeResourceHeader {
+    /// Format version from the header.
+    #[must_use]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+/// Arch (Miitomo) Resources are notably different during parsing
+/// while being derivative of [`CafeResourceHeader`].
+#[allow(unused)]
+#[derive(BinRead, Clone, Copy, Debug)]
+#[br(big, magic = b"FFRA", assert(unknown[11] == 0x0))]
+pub struct ArchResourceHeader {
+    version: u32,
+    uncompress_buffer_size: u32,
+    expanded_buffer_size: u32,
+    is_expand: u32,
+
+    pub texture: ArchResourceTexture,
+    pub shape: CafeResourceShape,
+
+    unknown: [u32; 12],
+}
+
+impl ArchResourceHeader {
+    /// Format version from the header.
+    #[must_use]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+/// Texture half of the Cafe resource header.
+#[allow(unused)]
+#[derive(BinRead, Clone, Copy, Debug)]
+pub struct CafeResourceTexture {
+    max_size: [u32; 11],
+    /// AKA FaceT_beard
+    pub beard: [CafeResourcePartsInfo; 3],
+    pub cap: [CafeResourcePartsInfo; 132],
+    pub eye: [CafeResourcePartsInfo; 62],     // 62/80 FFL/AFL
+    pub eyebrow: [CafeResourcePartsInfo; 24], // 24/28 FFL/AFL
+    /// FaceT_line, "wrinkle"
+    pub face_t_line: [CafeResourcePartsInfo; 12],
+    /// FaceT_make, make, makeup
+    pub face_t_make: [CafeResourcePartsInfo; 12],
+    pub glass: [CafeResourcePartsInfo; 9], // 9/20 FFL/AFL
+    pub mole: [CafeResourcePartsInfo; 2],
+    pub mouth: [CafeResourcePartsInfo; 37], // 37/52 FFL/AFL
+    pub mustache: [CafeResourcePartsInfo; 6],
+    /// Noseline
+    pub nline: [CafeResourcePartsInfo; 18],
+}
+
+/// [`CafeResourceTexture`], with room for the extra Arch parts.
+#[allow(unused)]
+#[derive(BinRead, Clone, Copy, Debug)]
+pub struct ArchResourceTexture {
+    max_size: [u32; 11],
+    /// AKA FaceT_beard
+    pub beard: [CafeResourcePartsInfo; 3],
+    pub cap: [CafeResourcePartsInfo; 132],
+    pub eye: [CafeResourcePartsInfo; 80],     // 62/80 FFL/AFL
+    pub eyebrow: [CafeResourcePartsInfo; 28], // 24/28 FFL/AFL
+    /// FaceT_line, "wrinkle"
+    pub face_t_line: [CafeResourcePartsInfo; 12],
+    /// FaceT_make, make, makeup
+    pub face_t_make: [CafeResourcePartsInfo; 12],
+    pub glass: [CafeResourcePartsInfo; 20], // 9/20 FFL/AFL
+    pub mole: [CafeResourcePartsInfo; 2],
+    pub mouth: [CafeResourcePartsInfo; 52], // 37/52 FFL/AFL
+    pub mustache: [CafeResourcePartsInfo; 6],
+    /// Noseline
+    pub nline: [CafeResourcePartsInfo; 18],
+}
+
+/// Implements `part` for the Cafe and Arch texture headers.
+macro_rules! texture_parts {
+    ($($ty:ty),*) => {$(
+        impl $ty {
+            /// Every texture of `part`.
+            #[must_use]
+            pub fn part(&self, part: TexturePart) -> &[CafeResourcePartsInfo] {
+                match part {
+                    TexturePart::Hat => &self.cap,
+                    TexturePart::Eye => &self.eye,
+                    TexturePart::Eyebrow => &self.eyebrow,
+                    TexturePart::Beard => &self.beard,
+                    TexturePart::Wrinkle => &self.face_t_line,
+                    TexturePart::Makeup => &self.face_t_make,
+                    TexturePart::Glass => &self.glass,
+                    TexturePart::Mole => &self.mole,
+                    TexturePart::Mouth => &self.mouth,
+                    TexturePart::Mustache => &self.mustache,
+                    TexturePart::Noseline => &self.nline,
+                }
+            }
+        }
+    )*};
+}
+
+texture_parts!(CafeResourceTexture, ArchResourceTexture);
+
+/// Shape half of the Cafe resource header.
+#[allow(unused)]
+#[derive(BinRead, Clone, Copy, Debug)]
+pub struct CafeResourceShape {
+    max_size: [u32; 12],
+    pub beard: [CafeResourcePartsInfo; 4],
+    pub cap_normal: [CafeResourcePartsInfo; 132],
+    pub cap_hat: [CafeResourcePartsInfo; 132],
+    pub faceline: [CafeResourcePartsInfo; 12],
+    pub glass: [CafeResourcePartsInfo; 1],
+    pub mask: [CafeResourcePartsInfo; 12],
+    /// Noseline
+    pub nline: [CafeResourcePartsInfo; 18],
+    pub nose: [CafeResourcePartsInfo; 18],
+    pub hair_normal: [CafeResourcePartsInfo; 132],
+    /// The hat/cap/headwear variants (as cap/hair/forehead are loaded together)
+    /// are for FFL_MODEL_TYPE_HAT, and are meant for the
+    /// caller/user to use in conjunction with FFLPartsTransform
+    /// to place custom headwear on top of the head model.
+    pub hair_hat: [CafeResourcePartsInfo; 132],
+    pub forehead_normal: [CafeResourcePartsInfo; 132],
+    pub forehead_hat: [CafeResourcePartsInfo; 132],
+}
+
+impl CafeResourceShape {
+    /// Same as [`crate::shape::ResourceShape::index_by_shape`], for Cafe.
+    /// Transforms aren't stored as elements here; use [`Self::hair_transforms`]
+    /// and [`Self::face_line_transforms`] instead.
+    #[must_use]
+    pub fn index_by_shape(&self, shape: Shape, index: usize) -> Option<CafeResourcePartsInfo> {
+        match shape {
+            Shape::Beard => self.beard.get(index),
+            Shape::FaceLine => self.faceline.get(index),
+            Shape::Mask => self.mask.get(index),
+            Shape::HatNormal => self.cap_normal.get(index),
+            Shape::HatCap => self.cap_hat.get(index),
+            Shape::ForeheadNormal => self.forehead_normal.get(index),
+            Shape::ForeheadCap => self.forehead_hat.get(index),
+            Shape::HairNormal => self.hair_normal.get(index),
+            Shape::HairCap => self.hair_hat.get(index),
+            Shape::Glasses => self.glass.get(index),
+            Shape::Nose => self.nose.get(index),
+            Shape::NoseLine => self.nline.get(index),
+            Shape::HairTransform | Shape::FaceLineTransform => None,
+        }
+        .copied()
+    }
+
+    /// Reads the transform of every hair shape. Empty hair shapes have no transform.
+    ///
+    /// # Errors
+    /// - A hair shape can't be decompressed or read
+    pub fn hair_transforms(
+        &self,
+        file: &[u8],
+    ) -> Result<Vec<Option<ResourceShapeHairTransform>>, Box<dyn Error>> {
+        self.hair_normal
+            .iter()
+            .map(|part| part.hair_transform(file))
+            .collect()
+    }
+
+    /// Reads the transform of every faceline shape. Empty faceline shapes have no transform.
+    ///
+    /// # Errors
+    /// - A faceline shape can't be decompressed or read
+    pub fn face_line_transforms(
+        &self,
+        file: &[u8],
+    ) -> Result<Vec<Option<ResourceShapeFacelineTransform>>, Box<dyn Error>> {
+        self.faceline
+            .iter()
+            .map(|part| part.face_line_transform(file))
+            .collect()
+    }
+}
+
+///  f_f_li_resource_parts_info:
+//         doc: |
+//           For verification, see: nn::mii::detail::ResourceCommonAttribute::IsValid()
+//         seq:
+//
+//
+//         instances:
+//           shape_data_header:
+//             io: _root._io
+//             if: size > 0
+//             pos: offset
+//             size: compressed_size # Compressed size.
+//             type: f_f_li_resource_shape_data_header
+//             process: zlib # Deflate will work too.
+//           # Texture footer: Offset is inside DECOMPRESSED block.
+//           # Offset = (decompressed size) - 0x10
+//
+#[derive(BinRead, Clone, Copy, Debug)]
+pub struct CafeResourcePartsInfo {
+    pub offset: u32,
+    /// Decompressed size.
+    pub size: u32,
+    pub compressed_size: u32,
+    pub compress_level: u8,
+    /// `FFLiResourceWindowBits`. Doubles as the choice of zlib or gzip headers.
+    pub window_bits: u8,
+    pub memory_level: u8,
+    /// `FFLiResourceStrategy`. Parts with [`Self::STRATEGY_UNCOMPRESSED`] are stored as-is.
+    pub strategy: u8,
+}
+
+/// Header format of a compressed part, from [`CafeResourcePartsInfo::window_bits`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StreamHeader {
+    Zlib,
+    Gzip,
+    /// Either, like zlib's automatic header detection.
+    Any,
+}
+
+/// Texture footer, at the end of the decompressed texture data.
+#[derive(BinRead, Clone, Copy, Debug)]
+#[br(big)]
+pub struct CafeResourceTextureFooter {
+    /// Where mips 1 onwards start, in the decompressed data.
+    pub mip_offset: u32,
+    pub width: u16,
+    pub height: u16,
+    pub mip_count: u8,
+    /// Only `R`, `Rg` and `Rgba` are used on Cafe.
+    pub format: ResourceTextureFormat,
+    _pad: [u8; 6],
+}
+
+impl CafeResourceTextureFooter {
+    pub const SIZE: usize = 0x10;
+}
+
+/// Element kinds in `FFLiResourceShapeDataHeader`, in order.
+#[allow(unused)]
+#[derive(Clone, Copy)]
+enum ElementType {
+    Position = 0, // 32_32_32(_32)_Float
+    Normal = 1,   // 10_10_10_2_Snorm
+    Uv = 2,       // 32_32_Float
+    Tangent = 3,  // 8_8_8_8_Snorm
+    Color = 4,    // 8_8_8_8_Unorm
+    Index = 5,    // u16
+}
+
+/// Start of the decompressed shape data. Element positions are relative to this.
+/// The faceline or hair transform follows.
+#[derive(BinRead, Clone, Copy, Debug)]
+#[br(big)]
+struct CafeResourceShapeDataHeader {
+    element_pos: [u32; 6],
+    element_size: [u32; 6],
+    /// `[min, max]`
+    bounding_box: [[f32; 3]; 2],
+}
+
+/// Decompressed shape data, with its header read.
+type ShapeData = (CafeResourceShapeDataHeader, Vec<u8>);
+
+impl CafeResourceShapeDataHeader {
+    fn element<'a>(&self, data: &'a [u8], element: ElementType) -> Result<&'a [u8], String> {
+        let start = self.element_pos[element as usize] as usize;
+        let end = start + self.element_size[element as usize] as usize;
+
+        data.get(start..end)
+            .ok_or_else(|| format!("Shape element {start:#x}..{end:#x} is out of bounds."))
+    }
+}
+
+impl CafeResourcePartsInfo {
+    pub const STRATEGY_UNCOMPRESSED: u8 = 5;
+
+    /// Whether this part has no data, as with unused slots.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    fn stream_header(&self) -> Result<StreamHeader, String> {
+        // Zlib window bits are 8 to 15, and each header kind takes 8 values.
+        match self.window_bits / 8 {
+            0 => Ok(StreamHeader::Zlib),
+            1 => Ok(StreamHeader::Gzip),
+            2 => Ok(StreamHeader::Any),
+            _ => Err(format!("Invalid window bits {}.", self.window_bits)),
+        }
+    }
+
+    /// Decompresses this part out of the resource file.
+    ///
+    /// # Errors
+    /// - The part is out of the bounds of `file`
+    /// - The window bits are invalid
+    /// - The part isn't valid zlib/gzip data, or decompresses to the wrong size
+    pub fn data(&self, file: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let start = self.offset as usize;
+
+        if self.strategy == Self::STRATEGY_UNCOMPRESSED {
+            let end = start + self.size as usize;
+            return Ok(file
+                .get(start..end)
+                .ok_or("Part is out of bounds.")?
+                .to_vec());
+        }
+
+        let end = start + self.compressed_size as usize;
+        let compressed = file.get(start..end).ok_or("Part is out of bounds.")?;
+
+        let header = match self.stream_header()? {
+            StreamHeader::Any if compressed.starts_with(&[0x1f, 0x8b]) => StreamHeader::Gzip,
+            StreamHeader::Any => StreamHeader::Zlib,
+            header => header,
+        };
+
+        let mut data = Vec::with_capacity(self.size as usize);
+        match header {
+            StreamHeader::Gzip => GzDecoder::new(compressed).read_to_end(&mut data)?,
+            _ => ZlibDecoder::new(compressed).read_to_end(&mut data)?,
+        };
+
+        if data.len() != self.size as usize {
+            return Err(format!(
+                "Part decompressed to {} bytes, expected {}.",
+                data.len(),
+                self.size
+            )
+            .into());
+        }
+
+        Ok(data)
+    }
+
+    /// Decodes every mip level of a texture, largest first.
+    /// Pixels are in the same order as [`crate::tex::TextureElement::get_image`].
+    /// Returns `None` for an empty part.
+    ///
+    /// # Errors
+    /// - The part can't be decompressed
+    /// - The footer is malformed, or has a compressed format
+    /// - The texture data is too small for its dimensions
+    pub fn get_images(&self, file: &[u8]) -> Result<Option<Vec<RgbaImage>>, Box<dyn Error>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        let data = self.data(file)?;
+        let footer_start = data
+            .len()
+            .checked_sub(CafeResourceTextureFooter::SIZE)
+            .ok_or("Texture is too small for its footer.")?;
+
+        let footer = CafeResourceTextureFooter::read(&mut Cursor::new(&data[footer_start..]))?;
+
+        let bpp = match footer.format {
+            ResourceTextureFormat::R => 8,
+            ResourceTextureFormat::Rg => 16,
+            ResourceTextureFormat::Rgba => 32,
+            format => return Err(TextureError::Unsupported(format).into()),
+        };
+
+        let (width, height) = (u32::from(footer.width), u32::from(footer.height));
+        let mip_count = u32::from(footer.mip_count.max(1));
+
+        let image_end = if mip_count > 1 {
+            footer.mip_offset as usize
+        } else {
+            footer_start
+        };
+        let image_data = data
+            .get(..image_end)
+            .ok_or("Mip offset is out of bounds.")?;
+        let mip_data = data
+            .get(image_end..footer_start)
+            .ok_or("Mip offset is out of bounds.")?;
+
+        let mut images = Vec::with_capacity(mip_count as usize);
+        let mut mip_end: usize = 0;
+
+        for level in 0..mip_count {
+            let surface = SurfaceLevel::new(width, height, bpp, level);
+
+            let tiled = if level == 0 {
+                image_data
+            } else {
+                // Mips are packed one after another, each at its own alignment.
+                let start = mip_end.next_multiple_of(surface.align as usize);
+                mip_end = start + surface.size;
+
+                mip_data.get(start..).unwrap_or_default()
+            };
+
+            let linear = surface.untile(tiled, bpp)?;
+            let pixels: Vec<u8> = expand_uncompressed(footer.format, &linear)
+                .ok_or(TextureError::Unsupported(footer.format))?
+                .into_iter()
+                .flat_map(u32::to_le_bytes)
+                .collect();
+
+            images.push(
+                RgbaImage::from_raw(surface.width, surface.height, pixels)
+                    .ok_or("Texture is the wrong size for its dimensions.")?,
+            );
+        }
+
+        Ok(Some(images))
+    }
+
+    /// Decodes the largest mip level of a texture. Returns `None` for an empty part.
+    ///
+    /// # Errors
+    /// See [`Self::get_images`].
+    pub fn get_image(&self, file: &[u8]) -> Result<Option<RgbaImage>, Box<dyn Error>> {
+        Ok(self
+            .get_images(file)?
+            .and_then(|images| images.into_iter().next()))
+    }
+
+    fn shape_data(&self, file: &[u8]) -> Result<Option<ShapeData>, Box<dyn Error>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        let data = self.data(file)?;
+        let header = CafeResourceShapeDataHeader::read(&mut Cursor::new(&data))?;
+
+        Ok(Some((header, data)))
+    }
+
+    /// Reads a shape's bounding box, as `[min, max]`. Returns `None` for an empty part.
+    ///
+    /// The box is stored ahead of the mesh, so this only decompresses the part.
+    ///
+    /// # Errors
+    /// - The part can't be decompressed or read
+    pub fn bounding_box(&self, file: &[u8]) -> Result<Option<[[f32; 3]; 2]>, Box<dyn Error>> {
+        Ok(self
+            .shape_data(file)?
+            .map(|(header, _)| header.bounding_box))
+    }
+
+    /// Reads a shape's mesh. Returns `None` for an empty part.
+    ///
+    /// Positions and UVs are stored as 32-bit floats, and are narrowed to match Nx.
+    /// Normals are repacked into the Nx bit order.
+    ///
+    /// # Errors
+    /// - The part can't be decompressed
+    /// - An element is out of bounds of the shape data
+    pub fn mesh(&self, file: &[u8]) -> Result<Option<ShapeMesh>, Box<dyn Error>> {
+        let Some((header, data)) = self.shape_data(file)? else {
+            return Ok(None);
+        };
+
+        let f32_be = |bytes: &[u8]| f32::from_be_bytes(bytes.try_into().unwrap());
+
+        let normals: Vec<_> = header
+            .element(&data, ElementType::Normal)?
+            .chunks_exact(4)
+            .map(|bytes| cafe_normal(u32::from_be_bytes(bytes.try_into().unwrap())))
+            .collect();
+
+        let positions = header.element(&data, ElementType::Position)?;
+        // Positions may or may not be padded to four components.
+        let position_stride = positions
+            .len()
+            .checked_div(normals.len())
+            .filter(|stride| matches!(stride, 12 | 16))
+            .unwrap_or(16);
+        let positions = positions
+            .chunks_exact(position_stride)
+            .map(|bytes| {
+                let [x, y, z] = [0, 4, 8].map(|i| Float16::from_f32(f32_be(&bytes[i..i + 4])));
+                [x, y, z, Float16::from_f32(1.0)]
+            })
+            .collect();
+
+        let uvs: Vec<_> = header
+            .element(&data, ElementType::Uv)?
+            .chunks_exact(8)
+            .map(|bytes| [0, 4].map(|i| Float16::from_f32(f32_be(&bytes[i..i + 4]))))
+            .collect();
+
+        let tangents: Vec<_> = header
+            .element(&data, ElementType::Tangent)?
+            .chunks_exact(4)
+            .map(|bytes| [bytes[0], bytes[1], bytes[2], bytes[3]].map(|x| x.cast_signed()))
+            .collect();
+
+        let color_params: Vec<_> = header
+            .element(&data, ElementType::Color)?
+            .chunks_exact(4)
+            .map(|bytes| [bytes[0], bytes[1], bytes[2], bytes[3]])
+            .collect();
+
+        let indices = header
+            .element(&data, ElementType::Index)?
+            .chunks_exact(2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+            .collect();
+
+        Ok(Some(ShapeMesh {
+            positions,
+            indices,
+            normals: (!normals.is_empty()).then_some(normals),
+            uvs: (!uvs.is_empty()).then_some(uvs),
+            tangents: (!tangents.is_empty()).then_some(tangents),
+            color_params: (!color_params.is_empty()).then_some(color_params),
+        }))
+    }
+
+    fn transform<T>(&self, file: &[u8]) -> Result<Option<T>, Box<dyn Error>>
+    where
+        T: for<'a> BinRead<Args<'a> = ()>,
+    {
+        let Some((_, data)) = self.shape_data(file)? else {
+            return Ok(None);
+        };
+
+        let mut reader = Cursor::new(&data);
+        reader.set_position(0x48);
+
+        Ok(Some(T::read_be(&mut reader)?))
+    }
+
+    /// Reads the transform stored with a hair shape. Returns `None` for an empty part.
+    ///
+    /// # Errors
+    /// - The part can't be decompressed or read
+    pub fn hair_transform(
+        &self,
+        file: &[u8],
+    ) -> Result<Option<ResourceShapeHairTransform>, Box<dyn Error>> {
+        self.transform(file)
+    }
+
+    /// Reads the transform stored with a faceline shape. Returns `None` for an empty part.
+    ///
+    /// # Errors
+    /// - The part can't be decompressed or read
+    pub fn face_line_transform(
+        &self,
+        file: &[u8],
+    ) -> Result<Option<ResourceShapeFacelineTransform>, Box<dyn Error>> {
+        self.transform(file)
+    }
+}
+
+/// GX2's `10_10_10_2` has X in the high bits, the opposite of Nx.
+fn cafe_normal(packed: u32) -> Vec3PackedSnorm {
+    let x = (packed >> 22) & 0x3ff;
+    let y = (packed >> 12) & 0x3ff;
+    let z = (packed >> 2) & 0x3ff;
+    let w = packed & 0x3;
+
+    Vec3PackedSnorm(x | (y << 10) | (z << 20) | (w << 30))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use std::collections::HashSet;
+    use std::fs::File;
+    use std::io::{BufReader, Write};
+
+    type R = Result<(), Box<dyn Error>>;
+
+    #[test]
+    fn cafe_resources_read() -> R {
+        let mut bin = BufReader::new(File::open(format!(
+            "{}/resources_here/AFLResHigh_2_3.dat",
+            std::env::var("CARGO_WORKSPACE_DIR").unwrap()
+        ))?);
+
+        let _ = ArchResourceHeader::read(&mut bin)?;
+
+        Ok(())
+    }
+
+    fn part(file: &[u8], size: usize, window_bits: u8) -> CafeResourcePartsInfo {
+        CafeResourcePartsInfo {
+            offset: 0,
+            size: size as u32,
+            compressed_size: file.len() as u32,
+            compress_level: 6,
+            window_bits,
+            memory_level: 8,
+            strategy: 0,
+        }
+    }
+
+    #[test]
+    fn decompress() -> R {
+        let data = b"FFRA FFRA FFRA FFRA".to_vec();
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(&data)?;
+        let zlib = zlib.finish()?;
+
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(&data)?;
+        let gzip = gzip.finish()?;
+
+        assert_eq!(part(&zlib, data.len(), 7).data(&zlib)?, data);
+        assert_eq!(part(&gzip, data.len(), 15).data(&gzip)?, data);
+        assert_eq!(part(&gzip, data.len(), 23).data(&gzip)?, data);
+        assert!(part(&gzip, data.len(), 7).data(&gzip).is_err());
+        assert!(part(&zlib, data.len(), 24).data(&zlib).is_err());
+
+        Ok(())
+    }
+
+    fn uncompressed(file: &[u8]) -> CafeResourcePartsInfo {
+        CafeResourcePartsInfo {
+            offset: 0,
+            size: file.len() as u32,
+            compressed_size: file.len() as u32,
+            compress_level: 0,
+            window_bits: 0,
+            memory_level: 0,
+            strategy: CafeResourcePartsInfo::STRATEGY_UNCOMPRESSED,
+        }
+    }
+
+    /// Tiles a mip level of an `R` texture, with each pixel set by `pixel`.
+    fn tiled_level(width: u32, height: u32, level: u32, pixel: impl Fn(u32, u32) -> u8) -> Vec<u8> {
+        let surface = SurfaceLevel::new(width, height, 8, level);
+        let mut data = vec![0; surface.size];
+
+        for y in 0..surface.height {
+            for x in 0..surface.width {
+                data[surface.address(x, y, 8)] = pixel(x, y);
+            }
+        }
+
+        data
+    }
+
+    #[test]
+    fn texture_mips() -> R {
+        let pixel = |x: u32, y: u32| (x * 3 + y * 5) as u8;
+
+        let mut file = tiled_level(64, 64, 0, pixel);
+        let mip_offset = u32::try_from(file.len())?;
+        file.extend(tiled_level(64, 64, 1, pixel));
+
+        file.extend(mip_offset.to_be_bytes());
+        file.extend(64u16.to_be_bytes());
+        file.extend(64u16.to_be_bytes());
+        file.extend([2, ResourceTextureFormat::R.into()]);
+        file.extend([0; 6]);
+
+        let part = uncompressed(&file);
+        let images = part.get_images(&file)?.ok_or("Texture is empty")?;
+
+        assert_eq!(images.len(), 2);
+        for (level, image) in (0..).zip(&images) {
+            assert_eq!(image.dimensions(), (64 >> level, 64 >> level));
+
+            for (x, y, color) in image.enumerate_pixels() {
+                let value = pixel(x, y);
+                assert_eq!(color.0, [0, 0, value, 255], "({x}, {y}) of mip {level}");
+            }
+        }
+        assert_eq!(part.get_image(&file)?.as_ref(), images.first());
+
+        let empty = CafeResourcePartsInfo { size: 0, ..part };
+        assert!(empty.get_images(&file)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn oversized_footer() {
+        // A 65535x65535 Rgba texture with 255 mips, and no data for any of it.
+        let mut file = vec![0; 0x100];
+        file.extend(0u32.to_be_bytes());
+        file.extend(u16::MAX.to_be_bytes());
+        file.extend(u16::MAX.to_be_bytes());
+        file.extend([u8::MAX, ResourceTextureFormat::Rgba.into()]);
+        file.extend([0; 6]);
+
+        assert!(uncompressed(&file).get_images(&file).is_err());
+    }
+
+    #[test]
+    fn bounding_box() -> R {
+        // Every element is empty.
+        let mut file = vec![0; 12 * 4];
+        for value in [-1.0f32, -2.0, -3.0, 1.0, 2.0, 3.0] {
+            file.extend(value.to_be_bytes());
+        }
+
+        assert_eq!(
+            uncompressed(&file).bounding_box(&file)?,
+            Some([[-1.0, -2.0, -3.0], [1.0, 2.0, 3.0]])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn normals() {
+        // X = 511, Y = -511, Z = 0, big end first.
+        let packed = (0x1ff << 22) | (0x201 << 12);
+
+        assert_eq!(cafe_normal(packed).unpack(), [1.0, -1.0, 0.0]);
+    }
+
+    #[test]
+    fn untile_is_a_permutation() {
+        for (width, height, bpp) in [(128, 64, 8), (64, 64, 16), (256, 256, 32)] {
+            for level in 0..4 {
+                let surface = SurfaceLevel::new(width, height, bpp, level);
+                let mut addresses = HashSet::new();
+
+                for y in 0..surface.height {
+                    for x in 0..surface.width {
+                        let address = surface.address(x, y, bpp);
+
+                        assert!(address + (bpp / 8) as usize <= surface.size);
+                        assert!(addresses.insert(address));
+                    }
+                }
+            }
+        }
+    }
+}
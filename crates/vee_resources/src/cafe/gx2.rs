@@ -0,0 +1,212 @@
+//! Untiling GX2 surfaces, following AMD's R600 address library as used on Cafe.
+//!
+//! Only covers what FFL textures need: uncompressed 2D surfaces with the default tile mode,
+//! no multisampling and no bank/pipe swizzle. The default is `2D_TILED_THIN1`,
+//! which degrades to `1D_TILED_THIN1` for mips smaller than a macro tile.
+
+use crate::tex::mip_size;
+use std::error::Error;
+
+const PIPE_INTERLEAVE_BYTES: u32 = 256;
+const NUM_PIPES: u32 = 2;
+const NUM_BANKS: u32 = 4;
+const NUM_GROUP_BITS: u32 = 8;
+const NUM_PIPE_BITS: u32 = 1;
+const NUM_BANK_BITS: u32 = 2;
+
+const MICRO_TILE_SIZE: u32 = 8;
+const MACRO_TILE_WIDTH: u32 = MICRO_TILE_SIZE * NUM_BANKS;
+const MACRO_TILE_HEIGHT: u32 = MICRO_TILE_SIZE * NUM_PIPES;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum TileMode {
+    /// `ADDR_TM_1D_TILED_THIN1`
+    Micro,
+    /// `ADDR_TM_2D_TILED_THIN1`
+    Macro,
+}
+
+/// Layout of one mip level of a surface.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct SurfaceLevel {
+    pub width: u32,
+    pub height: u32,
+    pub tile_mode: TileMode,
+    pub pitch: u32,
+    pub size: usize,
+    pub align: u32,
+}
+
+impl SurfaceLevel {
+    /// `bpp` is in bits.
+    pub fn new(width: u32, height: u32, bpp: u32, level: u32) -> SurfaceLevel {
+        let (width, height) = mip_size(width, height, level);
+
+        // Mips are padded to powers of two before alignment.
+        let (padded_width, padded_height) = if level > 0 {
+            (width.next_power_of_two(), height.next_power_of_two())
+        } else {
+            (width, height)
+        };
+
+        let micro_tile_bytes = bpp * MICRO_TILE_SIZE * MICRO_TILE_SIZE / 8;
+        let width_align_factor = (PIPE_INTERLEAVE_BYTES / micro_tile_bytes).max(1);
+
+        // The base level is never degraded.
+        let tile_mode = if level > 0
+            && (padded_width < width_align_factor * MACRO_TILE_WIDTH
+                || padded_height < MACRO_TILE_HEIGHT)
+        {
+            TileMode::Micro
+        } else {
+            TileMode::Macro
+        };
+
+        let (pitch_align, height_align, align) = match tile_mode {
+            TileMode::Micro => (
+                (PIPE_INTERLEAVE_BYTES / bpp).max(MICRO_TILE_SIZE),
+                MICRO_TILE_SIZE,
+                PIPE_INTERLEAVE_BYTES,
+            ),
+            TileMode::Macro => {
+                let pitch_align =
+                    MACRO_TILE_WIDTH * (PIPE_INTERLEAVE_BYTES / bpp / MICRO_TILE_SIZE).max(1);
+                let macro_tile_bytes = bpp * MACRO_TILE_WIDTH * MACRO_TILE_HEIGHT / 8;
+
+                (
+                    pitch_align,
+                    MACRO_TILE_HEIGHT,
+                    macro_tile_bytes.max(MACRO_TILE_HEIGHT * bpp * pitch_align / 8),
+                )
+            }
+        };
+
+        let pitch = padded_width.next_multiple_of(pitch_align);
+        let padded_height = padded_height.next_multiple_of(height_align);
+
+        SurfaceLevel {
+            width,
+            height,
+            tile_mode,
+            pitch,
+            // Widened, as the largest surfaces don't fit in a `u32`.
+            size: (u64::from(pitch) * u64::from(padded_height) * u64::from(bpp) / 8) as usize,
+            align,
+        }
+    }
+
+    /// Byte offset of the pixel at `x`, `y` in the tiled data.
+    pub fn address(&self, x: u32, y: u32, bpp: u32) -> usize {
+        match self.tile_mode {
+            TileMode::Micro => micro_tiled_address(x, y, bpp, self.pitch),
+            TileMode::Macro => macro_tiled_address(x, y, bpp, self.pitch),
+        }
+    }
+
+    /// Copies the level out of `tiled` into rows of `bpp / 8` byte pixels.
+    ///
+    /// # Errors
+    /// - `tiled` is too small for the level
+    pub fn untile(&self, tiled: &[u8], bpp: u32) -> Result<Vec<u8>, Box<dyn Error>> {
+        let bytes_per_pixel = (bpp / 8) as usize;
+
+        if tiled.len() < self.size {
+            return Err(format!(
+                "Tiled surface is {} bytes, expected {}.",
+                tiled.len(),
+                self.size
+            )
+            .into());
+        }
+
+        let mut linear = Vec::with_capacity(self.width as usize * self.height as usize * 4);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let address = self.address(x, y, bpp);
+                linear.extend_from_slice(&tiled[address..address + bytes_per_pixel]);
+            }
+        }
+
+        Ok(linear)
+    }
+}
+
+/// Index of a pixel inside its 8x8 micro tile.
+fn pixel_index(x: u32, y: u32, bpp: u32) -> u32 {
+    let bit = |value: u32, n: u32| (value >> n) & 1;
+
+    let bits = match bpp {
+        8 => [
+            bit(x, 0),
+            bit(x, 1),
+            bit(x, 2),
+            bit(y, 1),
+            bit(y, 0),
+            bit(y, 2),
+        ],
+        16 => [
+            bit(x, 0),
+            bit(x, 1),
+            bit(x, 2),
+            bit(y, 0),
+            bit(y, 1),
+            bit(y, 2),
+        ],
+        64 => [
+            bit(x, 0),
+            bit(y, 0),
+            bit(x, 1),
+            bit(x, 2),
+            bit(y, 1),
+            bit(y, 2),
+        ],
+        _ => [
+            bit(x, 0),
+            bit(x, 1),
+            bit(y, 0),
+            bit(x, 2),
+            bit(y, 1),
+            bit(y, 2),
+        ],
+    };
+
+    bits.iter()
+        .enumerate()
+        .fold(0, |index, (n, bit)| index | (bit << n))
+}
+
+fn micro_tiled_address(x: u32, y: u32, bpp: u32, pitch: u32) -> usize {
+    let micro_tile_bytes = bpp * MICRO_TILE_SIZE * MICRO_TILE_SIZE / 8;
+    let micro_tiles_per_row = pitch / MICRO_TILE_SIZE;
+
+    let micro_tile_offset =
+        micro_tile_bytes * (x / MICRO_TILE_SIZE + y / MICRO_TILE_SIZE * micro_tiles_per_row);
+    let pixel_offset = bpp * pixel_index(x, y, bpp) / 8;
+
+    (micro_tile_offset + pixel_offset) as usize
+}
+
+fn macro_tiled_address(x: u32, y: u32, bpp: u32, pitch: u32) -> usize {
+    let element_offset = u64::from(bpp * pixel_index(x, y, bpp) / 8);
+
+    let pipe = ((y >> 3) ^ (x >> 3)) & 1;
+    let bank_bit_0 = ((y / (16 * NUM_PIPES)) ^ (x >> 3)) & 1;
+    let bank_bit_1 = ((y / (8 * NUM_PIPES)) ^ (x >> 4)) & 1;
+    let bank = bank_bit_0 | (bank_bit_1 << 1);
+
+    let macro_tiles_per_row = u64::from(pitch / MACRO_TILE_WIDTH);
+    let macro_tile_bytes = u64::from(bpp * MACRO_TILE_WIDTH * MACRO_TILE_HEIGHT / 8);
+    let macro_tile_offset = (u64::from(x / MACRO_TILE_WIDTH)
+        + macro_tiles_per_row * u64::from(y / MACRO_TILE_HEIGHT))
+        * macro_tile_bytes;
+
+    let group_mask = (1 << NUM_GROUP_BITS) - 1;
+    let total_offset = element_offset + (macro_tile_offset >> (NUM_BANK_BITS + NUM_PIPE_BITS));
+
+    let offset_high = (total_offset & !group_mask) << (NUM_BANK_BITS + NUM_PIPE_BITS);
+    let offset_low = total_offset & group_mask;
+    let pipe_bits = u64::from(pipe) << NUM_GROUP_BITS;
+    let bank_bits = u64::from(bank) << (NUM_PIPE_BITS + NUM_GROUP_BITS);
+
+    usize::try_from(bank_bits | pipe_bits | offset_low | offset_high).unwrap()
+}
@@ -1,8 +1,463 @@
-//! Contains the color tables for Cafe and Nx. Also implements modulation for Nx, (not Cafe yet!)
-//! Expect modulation to become generic in the future.
+//! Contains the color tables for Rvl, Cafe and Nx, and implements modulation.
+//!
+//! [`ResolveColor`] looks colors up by their [`GenericColor`] index, in either [`ColorSpace`].
+//! [`nx::modulate`] works on `NxCharInfo`, and [`modulate`] on any character,
+//! in the [`Palette`] of any console.
+
+use vee_parse::generic::{CreationData, GenericChar, GenericColor};
 
 type Color = [f32; 4];
 
+/// Which space color values are in, e.g. Nx's `linear` and `srgb` tables,
+/// or the `SRGB` and `Linear` texture files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+impl ColorSpace {
+    /// Color space of an official Nx texture file from its name,
+    /// e.g. `Linear` for `NXTextureMidLinear.dat`. Only for when the header doesn't tell,
+    /// see [`crate::tex::ResourceTexture::color_space`].
+    #[must_use]
+    pub fn from_file_name(name: &str) -> Option<ColorSpace> {
+        let name = name.to_ascii_lowercase();
+
+        if name.contains("srgb") {
+            Some(ColorSpace::Srgb)
+        } else if name.contains("linear") {
+            Some(ColorSpace::Linear)
+        } else {
+            None
+        }
+    }
+}
+
+/// A color with the space its channels are in. Alpha is always linear.
+///
+/// ```
+/// use vee_parse::generic::GenericColor;
+/// use vee_resources::color::{ColorSpace, ResolveColor};
+///
+/// let red = GenericColor::favorite_color(0).resolve(ColorSpace::Linear);
+/// assert_eq!(red.to_hex(), "#d21e14");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rgba {
+    pub color: Color,
+    pub space: ColorSpace,
+}
+
+impl Rgba {
+    #[must_use]
+    pub fn srgb(color: Color) -> Rgba {
+        Rgba {
+            color,
+            space: ColorSpace::Srgb,
+        }
+    }
+
+    #[must_use]
+    pub fn linear(color: Color) -> Rgba {
+        Rgba {
+            color,
+            space: ColorSpace::Linear,
+        }
+    }
+
+    /// Converts the color channels into `space`.
+    #[must_use]
+    pub fn into_space(self, space: ColorSpace) -> Rgba {
+        let convert = match (self.space, space) {
+            (ColorSpace::Srgb, ColorSpace::Linear) => srgb_to_linear,
+            (ColorSpace::Linear, ColorSpace::Srgb) => linear_to_srgb,
+            _ => return self,
+        };
+
+        let [r, g, b, a] = self.color;
+
+        Rgba {
+            color: [convert(r), convert(g), convert(b), a],
+            space,
+        }
+    }
+
+    /// 8-bit sRGB, as image files and most UI toolkits want it.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn to_srgb8(self) -> [u8; 4] {
+        self.into_space(ColorSpace::Srgb)
+            .color
+            .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+
+    /// `#rrggbb` in sRGB, or `#rrggbbaa` if the color isn't opaque.
+    #[must_use]
+    pub fn to_hex(self) -> String {
+        match self.to_srgb8() {
+            [r, g, b, 255] => format!("#{r:02x}{g:02x}{b:02x}"),
+            [r, g, b, a] => format!("#{r:02x}{g:02x}{b:02x}{a:02x}"),
+        }
+    }
+}
+
+impl From<Rgba> for Color {
+    fn from(rgba: Rgba) -> Color {
+        rgba.color
+    }
+}
+
+fn srgb_to_linear(channel: f32) -> f32 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(channel: f32) -> f32 {
+    if channel <= 0.003_130_8 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Looks up the value of a color index in its table.
+pub trait ResolveColor {
+    /// The color in `space`. Cafe tables are sRGB, and converted to get linear colors.
+    /// Out of range indices resolve to the last color of the table.
+    fn resolve(&self, space: ColorSpace) -> Rgba;
+
+    /// The upper lip color that goes with a mouth color.
+    fn resolve_upper_lip(&self, space: ColorSpace) -> Rgba;
+
+    /// The Nx color that looks the most like this one, by distance in Oklab.
+    /// Facelines match Nx facelines, favorite colors stay as they are,
+    /// and everything else matches the common colors.
+    fn closest_nx(&self) -> GenericColor;
+}
+
+impl ResolveColor for GenericColor {
+    fn resolve(&self, space: ColorSpace) -> Rgba {
+        let index = usize::from(self.raw_index());
+        let nx = |linear: &[Color], srgb: &[Color]| match space {
+            ColorSpace::Srgb => Rgba::srgb(last_if_past(srgb, index)),
+            ColorSpace::Linear => Rgba::linear(last_if_past(linear, index)),
+        };
+
+        let rgba = match self {
+            GenericColor::CafeFaceline(_) => Rgba::srgb(last_if_past(&cafe::FACELINE_COLOR, index)),
+            GenericColor::CafeHair(_) => Rgba::srgb(last_if_past(&cafe::HAIR_COLOR, index)),
+            GenericColor::CafeEye(_) => Rgba::srgb(last_if_past(&cafe::EYE_COLOR_B, index)),
+            GenericColor::CafeMouth(_) => Rgba::srgb(last_if_past(&cafe::MOUTH_COLOR_R, index)),
+            GenericColor::CafeGlass(_) => Rgba::srgb(last_if_past(&cafe::GLASS_COLOR_R, index)),
+            GenericColor::RvlFaceline(_) => Rgba::srgb(last_if_past(&rvl::FACELINE_COLOR, index)),
+            GenericColor::RvlHair(_) => Rgba::srgb(last_if_past(&rvl::HAIR_COLOR, index)),
+            GenericColor::RvlEye(_) => Rgba::srgb(last_if_past(&rvl::EYE_COLOR, index)),
+            GenericColor::RvlMouth(_) => Rgba::srgb(last_if_past(&rvl::MOUTH_COLOR, index)),
+            GenericColor::RvlGlass(_) => Rgba::srgb(last_if_past(&rvl::GLASS_COLOR, index)),
+            GenericColor::NxFaceline(_) => {
+                nx(&nx::linear::FACELINE_COLOR, &nx::srgb::FACELINE_COLOR)
+            }
+            GenericColor::NxCommon(_) => nx(&nx::linear::COMMON_COLOR, &nx::srgb::COMMON_COLOR),
+            GenericColor::FavoriteColor(_) => nx(
+                &nx::linear::FAVORITE_COLOR.map(|[r, g, b]| [r, g, b, 1.0]),
+                &nx::srgb::FAVORITE_COLOR.map(|[r, g, b]| [r, g, b, 1.0]),
+            ),
+        };
+
+        rgba.into_space(space)
+    }
+
+    fn resolve_upper_lip(&self, space: ColorSpace) -> Rgba {
+        match self {
+            GenericColor::CafeMouth(i) | GenericColor::RvlMouth(i) => {
+                Rgba::srgb(last_if_past(&cafe::MOUTH_COLOR_G, usize::from(*i))).into_space(space)
+            }
+            color => {
+                let index = usize::from(color.to_nx().raw_index());
+
+                match space {
+                    ColorSpace::Srgb => Rgba::srgb(last_if_past(&nx::srgb::UPPER_LIP_COLOR, index)),
+                    ColorSpace::Linear => {
+                        Rgba::linear(last_if_past(&nx::linear::UPPER_LIP_COLOR, index))
+                    }
+                }
+            }
+        }
+    }
+
+    fn closest_nx(&self) -> GenericColor {
+        let (candidates, make): (u8, fn(u8) -> GenericColor) = match self {
+            GenericColor::FavoriteColor(_) => return *self,
+            GenericColor::CafeFaceline(_)
+            | GenericColor::RvlFaceline(_)
+            | GenericColor::NxFaceline(_) => (10, GenericColor::NxFaceline),
+            _ => (100, GenericColor::NxCommon),
+        };
+
+        let target = oklab(*self);
+        let distance = |color: &GenericColor| {
+            let color = oklab(*color);
+
+            (0..3).map(|i| (color[i] - target[i]).powi(2)).sum::<f32>()
+        };
+
+        (0..candidates)
+            .map(make)
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .expect("Nx tables aren't empty")
+    }
+}
+
+/// Converts a color to Oklab, where distances roughly match how different colors look.
+fn oklab(color: GenericColor) -> [f32; 3] {
+    let [r, g, b, _] = color.resolve(ColorSpace::Linear).color;
+
+    let lms = [
+        0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b,
+        0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b,
+        0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b,
+    ]
+    .map(f32::cbrt);
+
+    [
+        0.210_454_26 * lms[0] + 0.793_617_8 * lms[1] - 0.004_072_047 * lms[2],
+        1.977_998_5 * lms[0] - 2.428_592_2 * lms[1] + 0.450_593_7 * lms[2],
+        0.025_904_037 * lms[0] + 0.782_771_77 * lms[1] - 0.808_675_77 * lms[2],
+    ]
+}
+
+fn last_if_past(table: &[Color], index: usize) -> Color {
+    table[index.min(table.len() - 1)]
+}
+
+/// A console's set of colors. Characters keep their look best in the palette they were made in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Palette {
+    /// Wii and DS colors, from RFL.
+    Rvl,
+    Cafe,
+    Nx,
+}
+
+impl Palette {
+    /// The palette of the format `char` was read from. Nx if it isn't known.
+    #[must_use]
+    pub fn native(char: &GenericChar) -> Palette {
+        match char.creation_data {
+            CreationData::Rvl(_) => Palette::Rvl,
+            CreationData::Ctr(_) => Palette::Cafe,
+            CreationData::Nx(_) | CreationData::None => Palette::Nx,
+        }
+    }
+
+    /// `color` as this palette has it, for a part that uses the `part` table.
+    /// Colors the palette doesn't have are kept as they are.
+    fn convert(self, color: GenericColor, part: PalettePart) -> GenericColor {
+        let cafe = match part {
+            PalettePart::Faceline => color.to_cafe_faceline(),
+            PalettePart::Hair => color.to_cafe_hair(),
+            PalettePart::Eye => color.to_cafe_eye(),
+            PalettePart::Mouth => color.to_cafe_mouth(),
+            PalettePart::Glass => color.to_cafe_glass(),
+        };
+
+        match self {
+            Palette::Nx => color.to_nx(),
+            Palette::Cafe => cafe.unwrap_or(color),
+            Palette::Rvl => match cafe {
+                Some(GenericColor::CafeFaceline(i)) => GenericColor::RvlFaceline(i),
+                Some(GenericColor::CafeHair(i)) => GenericColor::RvlHair(i),
+                Some(GenericColor::CafeEye(i)) => GenericColor::RvlEye(i),
+                Some(GenericColor::CafeMouth(i)) if i < 3 => GenericColor::RvlMouth(i),
+                Some(GenericColor::CafeGlass(i)) => GenericColor::RvlGlass(i),
+                _ => color,
+            },
+        }
+    }
+}
+
+/// Which of a palette's tables a color is from.
+#[derive(Clone, Copy)]
+enum PalettePart {
+    Faceline,
+    Hair,
+    Eye,
+    Mouth,
+    Glass,
+}
+
+/// Like [`nx::modulate`], but with the colors of `char` looked up in `palette`.
+///
+/// With [`Palette::Nx`] this gives the same result as [`nx::modulate`] on the converted
+/// `NxCharInfo`. Cafe and Rvl keep colors Nx would round to a nearby one.
+#[must_use]
+pub fn modulate(
+    class: nx::ColorModulated,
+    char: &GenericChar,
+    palette: Palette,
+) -> nx::ModulationIntent {
+    use nx::{
+        BLACK, ColorModulated as C, ModulationIntent, ModulationMode as M, NON_MODULATION, WHITE,
+    };
+
+    let linear = |color, part| {
+        palette
+            .convert(color, part)
+            .resolve(ColorSpace::Linear)
+            .color
+    };
+    let single = |mode, color| ModulationIntent {
+        mode,
+        channels: [color, NON_MODULATION, NON_MODULATION],
+    };
+
+    match class {
+        C::Eye => {
+            let [black, white] = match palette {
+                Palette::Nx => [BLACK, WHITE],
+                Palette::Cafe | Palette::Rvl => [cafe::EYE_COLOR_R[0], cafe::EYE_COLOR_G[0]]
+                    .map(|color| Rgba::srgb(color).into_space(ColorSpace::Linear).color),
+            };
+
+            ModulationIntent {
+                mode: M::LayeredRgbTexture,
+                channels: [black, white, linear(char.eye.color, PalettePart::Eye)],
+            }
+        }
+        C::Eyebrow => single(
+            M::AlphaTexture,
+            linear(char.eyebrow.color, PalettePart::Hair),
+        ),
+        C::Mouth => {
+            let mouth = palette.convert(char.mouth.color, PalettePart::Mouth);
+
+            ModulationIntent {
+                mode: M::LayeredRgbTexture,
+                channels: [
+                    mouth.resolve(ColorSpace::Linear).color,
+                    mouth.resolve_upper_lip(ColorSpace::Linear).color,
+                    WHITE,
+                ],
+            }
+        }
+        C::Glass => single(
+            M::LuminanceAlphaTexture,
+            linear(char.glass.color, PalettePart::Glass),
+        ),
+        C::NoseLineShape => single(M::AlphaTexture, BLACK),
+        C::FacelineBeard | C::Mustache => {
+            single(M::AlphaTexture, linear(char.beard.color, PalettePart::Hair))
+        }
+        C::FacelineWrinkle => {
+            let color = match palette {
+                // Nx looks the faceline index up in the common table.
+                Palette::Nx => {
+                    GenericColor::NxCommon(char.faceline.color.to_nx().raw_index())
+                        .resolve(ColorSpace::Linear)
+                        .color
+                }
+                Palette::Cafe | Palette::Rvl => linear(char.faceline.color, PalettePart::Faceline),
+            };
+
+            single(M::AlphaTexture, color)
+        }
+        C::FacelineMakeup => ModulationIntent {
+            mode: M::DirectTexture,
+            channels: [NON_MODULATION; 3],
+        },
+        C::Mole => ModulationIntent {
+            mode: M::AlphaTexture,
+            channels: [BLACK, BLACK, BLACK],
+        },
+        C::Hat => single(
+            M::LuminanceTexture,
+            char.meta_data
+                .favorite_color
+                .resolve(ColorSpace::Linear)
+                .color,
+        ),
+        C::Body => single(
+            M::SingleColor,
+            char.meta_data
+                .favorite_color
+                .resolve(ColorSpace::Linear)
+                .color,
+        ),
+        C::Pants => single(
+            M::SingleColor,
+            nx::PantsColor::for_special(char.meta_data.special).linear(),
+        ),
+    }
+}
+
+/// A character's colors, resolved up front so parts can be drawn without the format
+/// the character came from. Values are linear.
+#[derive(Clone, Copy, Debug)]
+pub struct CharColors {
+    pub faceline: Color,
+    pub hair: Color,
+    pub beard: Color,
+    pub favorite: Color,
+    modulations: [nx::ModulationIntent; nx::ColorModulated::ALL.len()],
+}
+
+impl CharColors {
+    /// The colors of `char` in `palette`, see [`modulate`].
+    #[must_use]
+    pub fn new(char: &GenericChar, palette: Palette) -> CharColors {
+        let linear = |color, part| {
+            palette
+                .convert(color, part)
+                .resolve(ColorSpace::Linear)
+                .color
+        };
+
+        CharColors {
+            faceline: linear(char.faceline.color, PalettePart::Faceline),
+            hair: linear(char.hair.color, PalettePart::Hair),
+            beard: linear(char.beard.color, PalettePart::Hair),
+            favorite: char
+                .meta_data
+                .favorite_color
+                .resolve(ColorSpace::Linear)
+                .color,
+            modulations: nx::ColorModulated::ALL.map(|class| modulate(class, char, palette)),
+        }
+    }
+
+    /// The colors of `char` in the Nx palette, see [`nx::modulate`].
+    #[must_use]
+    pub fn nx(char: &vee_parse::NxCharInfo) -> CharColors {
+        let common = |index: u8| nx::linear::COMMON_COLOR[usize::from(index)];
+        let [r, g, b] = nx::linear::FAVORITE_COLOR[usize::from(char.favorite_color)];
+
+        CharColors {
+            faceline: nx::linear::FACELINE_COLOR[usize::from(char.faceline_color)],
+            hair: common(char.hair_color),
+            beard: common(char.beard_color),
+            favorite: [r, g, b, 1.0],
+            modulations: nx::ColorModulated::ALL.map(|class| nx::modulate(class, char)),
+        }
+    }
+
+    /// The same colors, with the pants in `pants` instead of the color FFL picks.
+    #[must_use]
+    pub fn with_pants(mut self, pants: nx::PantsColor) -> CharColors {
+        self.modulations[nx::ColorModulated::Pants as usize].channels[0] = pants.linear();
+        self
+    }
+
+    /// How to modulate the texture or shape of `class`.
+    #[must_use]
+    pub fn modulate(&self, class: nx::ColorModulated) -> nx::ModulationIntent {
+        self.modulations[class as usize]
+    }
+}
+
 pub mod cafe {
 
     // No it's not an approximation of 1/pi. You fool. You idiot.
@@ -88,6 +543,57 @@ pub mod cafe {
     ];
 }
 
+/// RFL's palette, used by Rvl and Ntr characters. Values are sRGB.
+///
+/// RFL has a single shade per part, so there are no iris/sclera or upper lip tables;
+/// those come from [`cafe`]. FFL kept RFL's eye, mouth and glass colors, and changed
+/// the faceline and hair ones.
+pub mod rvl {
+    pub const FACELINE_COLOR: [[f32; 4]; 6] = [
+        [0.941, 0.847, 0.769, 1.000],
+        [1.000, 0.737, 0.502, 1.000],
+        [0.847, 0.533, 0.314, 1.000],
+        [1.000, 0.690, 0.565, 1.000],
+        [0.596, 0.314, 0.157, 1.000],
+        [0.322, 0.180, 0.110, 1.000],
+    ];
+
+    pub const HAIR_COLOR: [[f32; 4]; 8] = [
+        [0.118, 0.102, 0.094, 1.000],
+        [0.220, 0.125, 0.082, 1.000],
+        [0.333, 0.149, 0.090, 1.000],
+        [0.439, 0.251, 0.141, 1.000],
+        [0.447, 0.447, 0.471, 1.000],
+        [0.286, 0.212, 0.102, 1.000],
+        [0.478, 0.349, 0.157, 1.000],
+        [0.757, 0.624, 0.392, 1.000],
+    ];
+
+    pub const EYE_COLOR: [[f32; 4]; 6] = [
+        [0.000, 0.000, 0.000, 1.000],
+        [0.424, 0.439, 0.439, 1.000],
+        [0.400, 0.235, 0.173, 1.000],
+        [0.376, 0.369, 0.188, 1.000],
+        [0.275, 0.329, 0.659, 1.000],
+        [0.220, 0.439, 0.345, 1.000],
+    ];
+
+    pub const MOUTH_COLOR: [[f32; 4]; 3] = [
+        [0.847, 0.322, 0.031, 1.000],
+        [0.941, 0.047, 0.031, 1.000],
+        [0.961, 0.282, 0.282, 1.000],
+    ];
+
+    pub const GLASS_COLOR: [[f32; 4]; 6] = [
+        [0.094, 0.094, 0.094, 1.000],
+        [0.376, 0.220, 0.063, 1.000],
+        [0.659, 0.063, 0.031, 1.000],
+        [0.125, 0.188, 0.408, 1.000],
+        [0.659, 0.376, 0.000, 1.000],
+        [0.471, 0.439, 0.408, 1.000],
+    ];
+}
+
 pub mod nx {
     // I'm not doing all of that.
     #![allow(clippy::unreadable_literal, clippy::excessive_precision)]
@@ -113,11 +619,65 @@ pub mod nx {
         Mole,
         Mustache,
         Hat,
+        /// The shirt and arms, in the favorite color.
+        Body,
+        /// In the [`PantsColor`] FFL picks for the character,
+        /// or the one from [`super::CharColors::with_pants`].
+        Pants,
+    }
+
+    impl ColorModulated {
+        /// Every class, in declaration order.
+        pub const ALL: [ColorModulated; 13] = [
+            ColorModulated::Eye,
+            ColorModulated::Eyebrow,
+            ColorModulated::Mouth,
+            ColorModulated::Glass,
+            ColorModulated::FacelineBeard,
+            ColorModulated::NoseLineShape,
+            ColorModulated::FacelineMakeup,
+            ColorModulated::FacelineWrinkle,
+            ColorModulated::Mole,
+            ColorModulated::Mustache,
+            ColorModulated::Hat,
+            ColorModulated::Body,
+            ColorModulated::Pants,
+        ];
+    }
+
+    /// Colors of a character's pants, like FFL's `FFLPantsColor`.
+    /// Games pick blue or red to set characters apart, e.g. by team.
+    #[repr(u8)]
+    #[derive(IntoPrimitive, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub enum PantsColor {
+        #[default]
+        Gray = 0,
+        Blue = 1,
+        Red = 2,
+        Gold = 3,
+    }
+
+    impl PantsColor {
+        /// Gold for special characters, gray for everyone else.
+        #[must_use]
+        pub fn for_special(special: bool) -> PantsColor {
+            if special {
+                PantsColor::Gold
+            } else {
+                PantsColor::Gray
+            }
+        }
+
+        /// The color in the `linear` table.
+        #[must_use]
+        pub fn linear(self) -> Color {
+            linear::PANTS_COLOR[usize::from(u8::from(self))]
+        }
     }
 
     pub const NON_MODULATION: Color = [f32::NAN, f32::NAN, f32::NAN, f32::NAN];
-    const WHITE: Color = linear::COMMON_COLOR[99];
-    const BLACK: Color = linear::COMMON_COLOR[8];
+    pub(super) const WHITE: Color = linear::COMMON_COLOR[99];
+    pub(super) const BLACK: Color = linear::COMMON_COLOR[8];
     // const TRANSPARENT: Color = [0.0, 0.0, 0.0, 0.0];
 
     /// Different ways a texture can be modulated.
@@ -234,6 +794,22 @@ pub mod nx {
                     ]
                 },
             },
+            ColorModulated::Body => ModulationIntent {
+                mode: M::SingleColor,
+                channels: {
+                    let [r, g, b] = linear::FAVORITE_COLOR[usize::from(char.favorite_color)];
+
+                    [[r, g, b, 1.0], NON_MODULATION, NON_MODULATION]
+                },
+            },
+            ColorModulated::Pants => ModulationIntent {
+                mode: M::SingleColor,
+                channels: [
+                    PantsColor::for_special(char.is_special != 0).linear(),
+                    NON_MODULATION,
+                    NON_MODULATION,
+                ],
+            },
         }
     }
 
@@ -457,6 +1033,14 @@ pub mod nx {
             [0.04518623, 0.02624122, 0.0168074, 1.0],
         ];
 
+        /// Indexed by [`PantsColor`](super::PantsColor).
+        pub const PANTS_COLOR: [[f32; 4]; 4] = [
+            [0.0512695, 0.0612461, 0.0761854, 1.0],
+            [0.021219, 0.0512695, 0.1878208, 1.0],
+            [0.2622507, 0.021219, 0.0144438, 1.0],
+            [0.5271151, 0.3515326, 0.1169707, 1.0],
+        ];
+
         pub const FAVORITE_COLOR: [[f32; 3]; 12] = [
             [
                 0.6444798707962036,
@@ -716,6 +1300,14 @@ pub mod nx {
             [0.2352942, 0.1764706, 0.1372550, 1.0],
         ];
 
+        /// Indexed by [`PantsColor`](super::PantsColor).
+        pub const PANTS_COLOR: [[f32; 4]; 4] = [
+            [0.2509804, 0.2745098, 0.3058824, 1.0],
+            [0.1568627, 0.2509804, 0.4705882, 1.0],
+            [0.5490196, 0.1568627, 0.1254902, 1.0],
+            [0.7529412, 0.6274510, 0.3764706, 1.0],
+        ];
+
         pub const FAVORITE_COLOR: [[f32; 3]; 12] = [
             [0.8235294818878174, 0.11764709651470184, 0.0784313976764679],
             [1.0, 0.43137261271476746, 0.09803929924964905],
@@ -732,3 +1324,170 @@ pub mod nx {
         ];
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::iter::zip;
+
+    #[test]
+    fn nx_tables_agree() {
+        for index in 0..100 {
+            let color = GenericColor::nx_common(index);
+            let srgb = color.resolve(ColorSpace::Srgb);
+            let linear = color.resolve(ColorSpace::Linear);
+
+            assert_eq!(srgb.to_srgb8(), linear.to_srgb8(), "common color {index}");
+        }
+
+        for index in 0..12 {
+            let color = GenericColor::favorite_color(index);
+
+            assert_eq!(
+                color.resolve(ColorSpace::Srgb).to_hex(),
+                color.resolve(ColorSpace::Linear).to_hex(),
+            );
+        }
+    }
+
+    #[test]
+    fn rvl_converts_to_closest() {
+        let parts = [
+            (GenericColor::rvl_faceline as fn(u8) -> GenericColor, 6),
+            (GenericColor::rvl_hair, 8),
+            (GenericColor::rvl_eye, 6),
+            (GenericColor::rvl_mouth, 3),
+            (GenericColor::rvl_glass, 6),
+        ];
+
+        for (color, count) in parts {
+            for index in 0..count {
+                let color = color(index);
+
+                assert_eq!(color.to_nx(), color.closest_nx(), "{color:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn rvl_resolves_through_rfl() {
+        assert_eq!(
+            GenericColor::rvl_hair(1).resolve(ColorSpace::Srgb).to_hex(),
+            "#382015"
+        );
+        assert_ne!(
+            GenericColor::rvl_faceline(0)
+                .resolve(ColorSpace::Srgb)
+                .to_hex(),
+            GenericColor::cafe_faceline(0)
+                .resolve(ColorSpace::Srgb)
+                .to_hex()
+        );
+        // RFL's gray hair matches a different Nx color than Cafe's.
+        assert_ne!(
+            GenericColor::rvl_hair(4).to_nx(),
+            GenericColor::cafe_hair(4).to_nx()
+        );
+    }
+
+    fn ctr_char() -> GenericChar {
+        use vee_parse::generic::AsGenericChar;
+        use vee_parse::{BinRead, CtrStoreData};
+
+        let data = include_bytes!("../../../resources_here/j0.ffsd");
+
+        CtrStoreData::read(&mut std::io::Cursor::new(data))
+            .unwrap()
+            .as_generic()
+            .unwrap()
+    }
+
+    #[test]
+    fn modulate_matches_nx() {
+        use vee_parse::NxCharInfo;
+        use vee_parse::generic::FromGenericChar;
+
+        let nx_char = NxCharInfo::from_generic(ctr_char());
+        let char = ctr_char();
+
+        for class in nx::ColorModulated::ALL {
+            let expected = nx::modulate(class, &nx_char).channels;
+            let channels = modulate(class, &char, Palette::Nx).channels;
+
+            // NaN channels don't compare equal, so compare the bits.
+            assert_eq!(
+                channels.map(|c| c.map(f32::to_bits)),
+                expected.map(|c| c.map(f32::to_bits)),
+                "{class:?}"
+            );
+        }
+
+        let colors = CharColors::new(&char, Palette::Nx);
+        let expected = CharColors::nx(&nx_char);
+
+        assert_eq!(
+            [colors.faceline, colors.hair, colors.beard, colors.favorite],
+            [
+                expected.faceline,
+                expected.hair,
+                expected.beard,
+                expected.favorite
+            ],
+        );
+    }
+
+    #[test]
+    fn modulate_keeps_cafe_colors() {
+        let char = ctr_char();
+        let GenericColor::CafeHair(hair) = char.eyebrow.color else {
+            panic!("Expected a Cafe color");
+        };
+
+        assert_eq!(Palette::native(&char), Palette::Cafe);
+
+        let channels = modulate(nx::ColorModulated::Eyebrow, &char, Palette::Cafe).channels;
+        let expected =
+            Rgba::srgb(cafe::HAIR_COLOR[usize::from(hair)]).into_space(ColorSpace::Linear);
+
+        assert_eq!(channels[0], expected.color);
+    }
+
+    #[test]
+    fn chosen_pants() {
+        use nx::{ColorModulated, PantsColor};
+
+        let char = ctr_char();
+        let pants = |colors: CharColors| colors.modulate(ColorModulated::Pants).channels[0];
+
+        assert_eq!(
+            pants(CharColors::new(&char, Palette::Cafe)),
+            PantsColor::Gray.linear()
+        );
+        assert_eq!(
+            pants(CharColors::new(&char, Palette::Cafe).with_pants(PantsColor::Red)),
+            PantsColor::Red.linear()
+        );
+    }
+
+    #[test]
+    fn pants_tables_agree() {
+        for (srgb, linear) in zip(nx::srgb::PANTS_COLOR, nx::linear::PANTS_COLOR) {
+            assert_eq!(Rgba::srgb(srgb).to_srgb8(), Rgba::linear(linear).to_srgb8());
+        }
+    }
+
+    #[test]
+    fn cafe_converts() {
+        let hair = GenericColor::cafe_hair(0);
+
+        assert_eq!(hair.resolve(ColorSpace::Srgb).color, cafe::HAIR_COLOR[0]);
+        assert_eq!(hair.resolve(ColorSpace::Linear).space, ColorSpace::Linear);
+        assert_eq!(hair.resolve(ColorSpace::Linear).to_hex(), "#1e1a18");
+        assert_eq!(
+            GenericColor::cafe_mouth(1)
+                .resolve_upper_lip(ColorSpace::Srgb)
+                .color,
+            cafe::MOUTH_COLOR_G[1]
+        );
+    }
+}
@@ -0,0 +1,660 @@
+//! Identifying and checking resource files, e.g. ones dropped into `resources_here`.
+//!
+//! [`inspect`] reads a file's header and decompresses every part, and [`verify`] goes on to
+//! decode every mesh and texture. Neither stops at a broken part: failures are collected in
+//! the [`Inspection`], so a whole file can be checked before it's handed to a renderer.
+//!
+//! ```no_run
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let report = vee_resources::inspect("./resources_here/ShapeMid.dat")?;
+//! println!("{report}");
+//!
+//! let report = vee_resources::verify("./resources_here/NXTextureMidSRGB.dat")?;
+//! assert!(report.is_ok());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cafe::{
+    ArchResourceHeader, CafeResourceHeader, CafeResourcePartsInfo, CafeResourceShape,
+    CafeResourceTextureFooter,
+};
+use crate::color::ColorSpace;
+use crate::inflate_bytes;
+use crate::shape::{ResourceShape, Shape};
+use crate::source::ResourceTier;
+use crate::tex::{ResourceTexture, ResourceTextureFormat, TexturePart};
+use binrw::BinRead;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Cursor};
+use std::path::Path;
+use thiserror::Error;
+
+/// Format of a resource file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceKind {
+    /// `NFSR`, e.g. `ShapeMid.dat`.
+    NxShape,
+    /// `NFTR`, e.g. `NXTextureMidSRGB.dat`.
+    NxTexture,
+    /// `FFRA`, e.g. `FFLResHigh.dat`.
+    Cafe,
+    /// `FFRA`, with room for Miitomo's extra textures, e.g. `AFLResHigh_2_3.dat`.
+    Arch,
+}
+
+/// Which kind of mesh or texture an element is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResourcePart {
+    Shape(Shape),
+    Texture(TexturePart),
+}
+
+/// Whether an element could be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ElementStatus {
+    /// An unused slot.
+    Empty,
+    Ok,
+    /// The element couldn't be decompressed (or decoded, when verifying), with the reason.
+    Failed(String),
+}
+
+/// One mesh or texture slot of a resource file.
+#[derive(Clone, Debug)]
+pub struct ElementReport {
+    pub part: ResourcePart,
+    pub index: usize,
+    /// Decompressed size in bytes.
+    pub size: u32,
+    pub compressed_size: u32,
+    /// `None` for meshes, empty slots, and Cafe textures that couldn't be decompressed.
+    pub format: Option<ResourceTextureFormat>,
+    pub status: ElementStatus,
+}
+
+/// Everything [`inspect`] or [`verify`] found out about a resource file.
+#[derive(Clone, Debug)]
+pub struct Inspection {
+    pub kind: ResourceKind,
+    /// From the file name, if it's one of the official ones. Headers don't have a field for it.
+    pub tier: Option<ResourceTier>,
+    /// Only known for Nx texture files. Read from the texture formats in the header,
+    /// or the file name if there aren't any textures.
+    pub color_space: Option<ColorSpace>,
+    pub version: u32,
+    /// Size of the whole file in bytes.
+    pub file_size: u64,
+    /// Every slot, in the order they're stored in the header.
+    pub elements: Vec<ElementReport>,
+}
+
+/// Errors that stop a file from being inspected at all.
+/// Broken elements don't; they're reported in [`Inspection::elements`] instead.
+#[derive(Error, Debug)]
+pub enum InspectError {
+    #[error("Resource file can't be read: {0}")]
+    Io(#[from] io::Error),
+    #[error("Unknown resource file magic {0:02x?}.")]
+    UnknownMagic([u8; 4]),
+    #[error("Resource header is malformed: {0}")]
+    Header(#[from] binrw::Error),
+}
+
+/// How far to read each element.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Depth {
+    Decompress,
+    Decode,
+}
+
+/// Identifies the resource file at `path`, and checks that each of its parts decompresses.
+///
+/// # Errors
+/// - The file can't be read
+/// - The file isn't a known resource format, or its header is malformed
+pub fn inspect(path: impl AsRef<Path>) -> Result<Inspection, InspectError> {
+    read_and_check(path.as_ref(), Depth::Decompress)
+}
+
+/// Like [`inspect`], but also decodes every mesh and texture (and every mip level).
+/// Slower, but catches parts that decompress to garbage.
+///
+/// # Errors
+/// See [`inspect`].
+pub fn verify(path: impl AsRef<Path>) -> Result<Inspection, InspectError> {
+    read_and_check(path.as_ref(), Depth::Decode)
+}
+
+/// [`inspect`] for a file that's already in memory. `file_name` is used to guess the tier,
+/// and the color space if the header doesn't tell.
+///
+/// # Errors
+/// - `data` isn't a known resource format, or its header is malformed
+pub fn inspect_bytes(data: &[u8], file_name: Option<&str>) -> Result<Inspection, InspectError> {
+    check(data, file_name, Depth::Decompress)
+}
+
+/// [`verify`] for a file that's already in memory.
+///
+/// # Errors
+/// See [`inspect_bytes`].
+pub fn verify_bytes(data: &[u8], file_name: Option<&str>) -> Result<Inspection, InspectError> {
+    check(data, file_name, Depth::Decode)
+}
+
+fn read_and_check(path: &Path, depth: Depth) -> Result<Inspection, InspectError> {
+    let data = std::fs::read(path)?;
+    let file_name = path.file_name().and_then(|name| name.to_str());
+
+    check(&data, file_name, depth)
+}
+
+fn check(data: &[u8], file_name: Option<&str>, depth: Depth) -> Result<Inspection, InspectError> {
+    let magic: [u8; 4] = data
+        .get(..4)
+        .and_then(|magic| magic.try_into().ok())
+        .ok_or(InspectError::UnknownMagic([0; 4]))?;
+
+    // Only Nx texture files come in more than one color space.
+    let mut color_space = None;
+
+    let (kind, version, elements) = match &magic {
+        b"NFSR" => {
+            let header = ResourceShape::read(&mut Cursor::new(data))?;
+            let elements = nx_shape_elements(&header, data, depth);
+            (ResourceKind::NxShape, header.version(), elements)
+        }
+        b"NFTR" => {
+            let header = ResourceTexture::read(&mut Cursor::new(data))?;
+            let elements = nx_texture_elements(&header, data, depth);
+            color_space = Some(header.color_space().ok());
+            (ResourceKind::NxTexture, header.version(), elements)
+        }
+        b"FFRA" => {
+            if let Some(header) = read_arch_header(data) {
+                let textures = TexturePart::ALL.map(|part| (part, header.texture.part(part)));
+                let elements = cafe_elements(&textures, &header.shape, data, depth);
+                (ResourceKind::Arch, header.version(), elements)
+            } else {
+                let header = CafeResourceHeader::read(&mut Cursor::new(data))?;
+                let textures = TexturePart::ALL.map(|part| (part, header.texture.part(part)));
+                let elements = cafe_elements(&textures, &header.shape, data, depth);
+                (ResourceKind::Cafe, header.version(), elements)
+            }
+        }
+        _ => return Err(InspectError::UnknownMagic(magic)),
+    };
+
+    let file_name = file_name.unwrap_or_default();
+    let tier = ResourceTier::from_file_name(file_name);
+    // The header only tells if there's a texture in it.
+    let color_space =
+        color_space.and_then(|header| header.or_else(|| ColorSpace::from_file_name(file_name)));
+
+    Ok(Inspection {
+        kind,
+        tier,
+        color_space,
+        version,
+        file_size: data.len() as u64,
+        elements,
+    })
+}
+
+/// Cafe and Arch files share a magic, so an Arch header is only taken if none of its parts
+/// start inside it. A Cafe file's parts start right after its (smaller) header.
+fn read_arch_header(data: &[u8]) -> Option<ArchResourceHeader> {
+    let mut reader = Cursor::new(data);
+    let header = ArchResourceHeader::read(&mut reader).ok()?;
+    let header_end = reader.position();
+
+    let texture_parts = TexturePart::ALL
+        .into_iter()
+        .flat_map(|part| header.texture.part(part));
+    let shape_parts = Shape::MESHES
+        .into_iter()
+        .flat_map(|shape| (0..).map_while(move |index| header.shape.index_by_shape(shape, index)));
+
+    texture_parts
+        .copied()
+        .chain(shape_parts)
+        .filter(|part| !part.is_empty())
+        .all(|part| u64::from(part.offset) >= header_end)
+        .then_some(header)
+}
+
+fn nx_shape_elements(header: &ResourceShape, data: &[u8], depth: Depth) -> Vec<ElementReport> {
+    let mut elements = Vec::new();
+
+    for shape in Shape::MESHES {
+        for (index, element) in header.elements(shape).iter().enumerate() {
+            let common = element.common;
+
+            let status = if common.size == 0 {
+                ElementStatus::Empty
+            } else {
+                status(run(|| match depth {
+                    Depth::Decompress => {
+                        inflate_range(data, common.offset, common.size_compressed).map(drop)
+                    }
+                    Depth::Decode => element.clone().mesh(data).map(drop),
+                }))
+            };
+
+            elements.push(ElementReport {
+                part: ResourcePart::Shape(shape),
+                index,
+                size: common.size,
+                compressed_size: common.size_compressed,
+                format: None,
+                status,
+            });
+        }
+    }
+
+    elements
+}
+
+fn nx_texture_elements(header: &ResourceTexture, data: &[u8], depth: Depth) -> Vec<ElementReport> {
+    let mut elements = Vec::new();
+
+    for part in TexturePart::ALL {
+        for (index, element) in header.part(part).iter().enumerate() {
+            let common = element.common;
+            let empty = element.texture.width == 0 || element.texture.height == 0;
+
+            let status = if empty {
+                ElementStatus::Empty
+            } else {
+                status(run(|| match depth {
+                    Depth::Decompress => {
+                        inflate_range(data, common.offset, common.size_compressed).map(drop)
+                    }
+                    Depth::Decode => element.get_mip_chain(data).map(drop),
+                }))
+            };
+
+            elements.push(ElementReport {
+                part: ResourcePart::Texture(part),
+                index,
+                size: common.size,
+                compressed_size: common.size_compressed,
+                format: (!empty).then_some(element.texture.format),
+                status,
+            });
+        }
+    }
+
+    elements
+}
+
+fn cafe_elements(
+    textures: &[(TexturePart, &[CafeResourcePartsInfo])],
+    shapes: &CafeResourceShape,
+    data: &[u8],
+    depth: Depth,
+) -> Vec<ElementReport> {
+    let mut elements = Vec::new();
+
+    let mut push = |part, index, info: &CafeResourcePartsInfo, status, format| {
+        elements.push(ElementReport {
+            part,
+            index,
+            size: info.size,
+            compressed_size: info.compressed_size,
+            format,
+            status,
+        });
+    };
+
+    for &(part, infos) in textures {
+        for (index, info) in infos.iter().enumerate() {
+            if info.is_empty() {
+                push(
+                    ResourcePart::Texture(part),
+                    index,
+                    info,
+                    ElementStatus::Empty,
+                    None,
+                );
+                continue;
+            }
+
+            // The format is in the footer, so it's only known once decompressed.
+            let format = run(|| cafe_texture_format(&info.data(data)?));
+            let status = match (&format, depth) {
+                (Ok(_), Depth::Decode) => status(run(|| info.get_images(data).map(drop))),
+                (result, _) => status(result.as_ref().map(drop).map_err(Clone::clone)),
+            };
+
+            push(
+                ResourcePart::Texture(part),
+                index,
+                info,
+                status,
+                format.ok(),
+            );
+        }
+    }
+
+    for shape in Shape::MESHES {
+        for (index, info) in (0..)
+            .map_while(|index| shapes.index_by_shape(shape, index))
+            .enumerate()
+        {
+            let status = if info.is_empty() {
+                ElementStatus::Empty
+            } else {
+                status(run(|| match depth {
+                    Depth::Decompress => info.data(data).map(drop),
+                    Depth::Decode => info.mesh(data).map(drop),
+                }))
+            };
+
+            push(ResourcePart::Shape(shape), index, &info, status, None);
+        }
+    }
+
+    elements
+}
+
+fn cafe_texture_format(data: &[u8]) -> Result<ResourceTextureFormat, Box<dyn Error>> {
+    let footer_start = data
+        .len()
+        .checked_sub(CafeResourceTextureFooter::SIZE)
+        .ok_or("Texture is too small for its footer.")?;
+
+    Ok(CafeResourceTextureFooter::read(&mut Cursor::new(&data[footer_start..]))?.format)
+}
+
+fn inflate_range(data: &[u8], offset: u32, size: u32) -> Result<Vec<u8>, Box<dyn Error>> {
+    let start = offset as usize;
+    let end = start + size as usize;
+
+    Ok(inflate_bytes(
+        data.get(start..end)
+            .ok_or("Part is out of bounds of the resource file.")?,
+    )?)
+}
+
+/// Runs a check, turning its error into a message.
+fn run<T>(check: impl FnOnce() -> Result<T, Box<dyn Error>>) -> Result<T, String> {
+    check().map_err(|error| error.to_string())
+}
+
+fn status(result: Result<(), String>) -> ElementStatus {
+    match result {
+        Ok(()) => ElementStatus::Ok,
+        Err(reason) => ElementStatus::Failed(reason),
+    }
+}
+
+impl Inspection {
+    /// Every part in the file, in the order they're stored.
+    #[must_use]
+    pub fn parts(&self) -> Vec<ResourcePart> {
+        let mut parts: Vec<_> = self.elements.iter().map(|element| element.part).collect();
+        parts.dedup();
+        parts
+    }
+
+    /// Number of slots of `part`, including empty ones.
+    #[must_use]
+    pub fn slot_count(&self, part: ResourcePart) -> usize {
+        self.elements_of(part).count()
+    }
+
+    /// Number of unused slots of `part`.
+    #[must_use]
+    pub fn empty_count(&self, part: ResourcePart) -> usize {
+        self.elements_of(part)
+            .filter(|element| element.status == ElementStatus::Empty)
+            .count()
+    }
+
+    /// How many textures there are of each format.
+    #[must_use]
+    pub fn format_histogram(&self) -> BTreeMap<ResourceTextureFormat, usize> {
+        let mut histogram = BTreeMap::new();
+        for format in self.elements.iter().filter_map(|element| element.format) {
+            *histogram.entry(format).or_default() += 1;
+        }
+        histogram
+    }
+
+    /// Decompressed size of every element, in bytes.
+    #[must_use]
+    pub fn total_size(&self) -> u64 {
+        self.elements.iter().map(|e| u64::from(e.size)).sum()
+    }
+
+    /// Compressed size of every element, in bytes.
+    #[must_use]
+    pub fn total_compressed_size(&self) -> u64 {
+        self.elements
+            .iter()
+            .map(|e| u64::from(e.compressed_size))
+            .sum()
+    }
+
+    /// Elements that couldn't be read.
+    pub fn failures(&self) -> impl Iterator<Item = &ElementReport> {
+        self.elements
+            .iter()
+            .filter(|element| matches!(element.status, ElementStatus::Failed(_)))
+    }
+
+    /// Whether every element could be read.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    fn elements_of(&self, part: ResourcePart) -> impl Iterator<Item = &ElementReport> {
+        self.elements
+            .iter()
+            .filter(move |element| element.part == part)
+    }
+}
+
+impl fmt::Display for ResourcePart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourcePart::Shape(shape) => write!(f, "{shape:?} shape"),
+            ResourcePart::Texture(part) => write!(f, "{part:?} texture"),
+        }
+    }
+}
+
+impl fmt::Display for Inspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} resource file", self.kind)?;
+        if let Some(tier) = self.tier {
+            write!(f, ", {tier:?} tier")?;
+        }
+        if let Some(color_space) = self.color_space {
+            write!(f, ", {color_space:?}")?;
+        }
+        writeln!(f, ", version {}, {} bytes", self.version, self.file_size)?;
+
+        for part in self.parts() {
+            writeln!(
+                f,
+                "  {part}: {} slots, {} empty",
+                self.slot_count(part),
+                self.empty_count(part)
+            )?;
+        }
+
+        let histogram = self.format_histogram();
+        if !histogram.is_empty() {
+            let formats: Vec<_> = histogram
+                .iter()
+                .map(|(format, count)| format!("{format:?} x{count}"))
+                .collect();
+            writeln!(f, "Texture formats: {}", formats.join(", "))?;
+        }
+
+        writeln!(
+            f,
+            "Total size: {} bytes, {} compressed",
+            self.total_size(),
+            self.total_compressed_size()
+        )?;
+
+        write!(f, "Failures: {}", self.failures().count())?;
+        for element in self.failures() {
+            if let ElementStatus::Failed(reason) = &element.status {
+                write!(f, "\n  {} {}: {reason}", element.part, element.index)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::ShapeMesh;
+    use crate::write::{EncodedTexture, ShapeFileBuilder, TextureFileBuilder};
+    use image::RgbaImage;
+
+    type R = Result<(), Box<dyn Error>>;
+
+    fn triangle() -> ShapeMesh {
+        ShapeMesh::from_f32(
+            &[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            Some(&[[0.0, 0.0, 1.0]; 3]),
+            None,
+            vec![0, 1, 2],
+        )
+    }
+
+    #[test]
+    fn nx_shape() -> R {
+        let mut builder = ShapeFileBuilder::new();
+        builder.set_mesh(Shape::Nose, 3, &triangle())?;
+        builder.set_mesh(Shape::HairNormal, 0, &triangle())?;
+        let mut data = builder.build()?;
+
+        let report = inspect_bytes(&data, Some("ShapeHigh.dat"))?;
+        assert_eq!(report.kind, ResourceKind::NxShape);
+        assert_eq!(report.tier, Some(ResourceTier::High));
+        assert_eq!(report.color_space, None);
+        assert_eq!(report.file_size, data.len() as u64);
+
+        let nose = ResourcePart::Shape(Shape::Nose);
+        assert_eq!(report.slot_count(nose), 18);
+        assert_eq!(report.empty_count(nose), 17);
+        assert_eq!(report.parts().len(), 12);
+        assert!(report.format_histogram().is_empty());
+        assert!(report.is_ok());
+
+        // Break the hair mesh; the nose should still be fine.
+        let header = ResourceShape::read(&mut Cursor::new(&data))?;
+        let hair = header.hair_normal[0].common;
+        data[hair.offset as usize..][..hair.size_compressed as usize].fill(0xff);
+
+        let report = verify_bytes(&data, None)?;
+        let failures: Vec<_> = report.failures().collect();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].part, ResourcePart::Shape(Shape::HairNormal));
+        assert_eq!(failures[0].index, 0);
+        assert!(report.to_string().contains("HairNormal shape 0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn nx_texture() -> R {
+        let image = RgbaImage::from_pixel(8, 8, image::Rgba([0, 0, 255, 255]));
+        let mut builder = TextureFileBuilder::new();
+        builder.set_texture(
+            TexturePart::Mole,
+            1,
+            &EncodedTexture::encode(&image, ResourceTextureFormat::R)?,
+        )?;
+        builder.set_texture(
+            TexturePart::Eye,
+            5,
+            &EncodedTexture::encode(&image, ResourceTextureFormat::Rgba)?,
+        )?;
+        let data = builder.build()?;
+
+        let report = verify_bytes(&data, Some("NXTextureMidLinear.dat"))?;
+        assert_eq!(report.kind, ResourceKind::NxTexture);
+        assert_eq!(report.tier, Some(ResourceTier::Mid));
+        assert_eq!(report.color_space, Some(ColorSpace::Linear));
+        assert_eq!(
+            report.empty_count(ResourcePart::Texture(TexturePart::Mole)),
+            1
+        );
+        assert_eq!(
+            report.format_histogram(),
+            BTreeMap::from([
+                (ResourceTextureFormat::R, 1),
+                (ResourceTextureFormat::Rgba, 1)
+            ])
+        );
+        let header = ResourceTexture::read(&mut Cursor::new(&data))?;
+        let (mole, eye) = (header.mole[1].common, header.eye[5].common);
+        assert_eq!(report.total_size(), u64::from(mole.size + eye.size));
+        assert_eq!(
+            report.total_compressed_size(),
+            u64::from(mole.size_compressed + eye.size_compressed)
+        );
+        assert!(report.is_ok());
+
+        // The header wins over the name, which is only used without any textures.
+        let report = inspect_bytes(&data, Some("NXTextureMidSRGB.dat"))?;
+        assert_eq!(report.color_space, Some(ColorSpace::Linear));
+        let empty = TextureFileBuilder::new().build()?;
+        let report = inspect_bytes(&empty, Some("NXTextureMidSRGB.dat"))?;
+        assert_eq!(report.color_space, Some(ColorSpace::Srgb));
+
+        Ok(())
+    }
+
+    #[test]
+    fn cafe_and_arch() -> R {
+        // Headers without any parts. Arch headers have 48 more textures.
+        const CAFE_HEADER_SIZE: usize = 18944;
+        const ARCH_HEADER_SIZE: usize = CAFE_HEADER_SIZE + 48 * 16;
+
+        for (size, kind) in [
+            (CAFE_HEADER_SIZE, ResourceKind::Cafe),
+            (ARCH_HEADER_SIZE, ResourceKind::Arch),
+        ] {
+            let mut data = vec![0; size];
+            data[..4].copy_from_slice(b"FFRA");
+
+            let report = inspect_bytes(&data, Some("FFLResMiddle.dat"))?;
+            assert_eq!(report.kind, kind);
+            assert_eq!(report.tier, Some(ResourceTier::Mid));
+            assert!(
+                report
+                    .elements
+                    .iter()
+                    .all(|element| element.status == ElementStatus::Empty)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn unknown() {
+        assert!(matches!(
+            inspect_bytes(b"FFSD....", None),
+            Err(InspectError::UnknownMagic(magic)) if &magic == b"FFSD"
+        ));
+        assert!(matches!(
+            inspect_bytes(b"NF", None),
+            Err(InspectError::UnknownMagic(_))
+        ));
+    }
+}
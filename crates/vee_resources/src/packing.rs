@@ -3,10 +3,8 @@
 use binrw::{BinRead, BinWrite};
 use half::f16 as half_f16;
 
-// TODO: unpack on GPU?
-// Wgsl doesn't have a [10,10,10,2] unpacking method
-
-/// WebGPU does not suport Snorm_10_10_10_2 so we have to convert this on the CPU.
+/// WebGPU does not suport Snorm_10_10_10_2, so this is either converted on the CPU with
+/// [`Vec3PackedSnorm::unpack`], or uploaded as a `u32` and unpacked in a shader.
 /// This format contains 4 signed, normalised floats packed into 32 bits.
 ///
 /// Format: `[10; 10; 10; 2]`
@@ -26,6 +24,15 @@ impl Vec3PackedSnorm {
 
         [nx as f32 / 511.0, ny as f32 / 511.0, nz as f32 / 511.0]
     }
+
+    /// Packs a vector, clamping each component to `-1.0..=1.0`. The w component is `0`.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn pack(vector: [f32; 3]) -> Self {
+        let [nx, ny, nz] =
+            vector.map(|n| (n.clamp(-1.0, 1.0) * 511.0).round() as i32 as u32 & 0x3ff);
+
+        Vec3PackedSnorm(nx | ny << 10 | nz << 20)
+    }
 }
 
 /// Wrapper type for 16-bit floats 'cos Rust doesn't have stable support yet.
@@ -71,4 +78,13 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn vec3_packed_snorm_round_trip() {
+        for test in [0x067a_67ca, 0x067a_6436, 0x0675_9fca, 0x0675_9c36] {
+            let packed = Vec3PackedSnorm(test);
+
+            assert_eq!(Vec3PackedSnorm::pack(packed.unpack()).0, test);
+        }
+    }
 }
@@ -1,7 +1,7 @@
 //! Parsing mesh data
 use crate::inflate_bytes;
 use crate::packing::{Float16, Vec3PackedSnorm};
-use binrw::{BinRead, Endian};
+use binrw::{BinRead, BinWrite, Endian};
 use num_enum::TryFromPrimitive;
 use std::{
     error::Error,
@@ -27,7 +27,10 @@ pub struct ShapeMesh {
     pub indices: Vec<u16>,
     pub normals: Option<Vec<Vec3PackedSnorm>>,
     pub uvs: Option<Vec<[Float16; 2]>>,
-    pub color_params: Option<Vec<u8>>,
+    /// Snorm, for anisotropic hair specular.
+    pub tangents: Option<Vec<[i8; 4]>>,
+    /// Unorm, for masking hair and faceline colors.
+    pub color_params: Option<Vec<[u8; 4]>>,
 }
 // TODO: add gltf?
 // impl ShapeData {
@@ -143,17 +146,31 @@ impl BinRead for ShapeMesh {
             None
         };
 
+        // Read tangents
+        let tangents = if args.is_valid_attribute(AttributeType::Tangent) {
+            reader.seek(SeekFrom::Start(u64::from(
+                args.attr_offset[AttributeType::Tangent as usize],
+            )))?;
+
+            let mut tangents = Vec::with_capacity(usize::try_from(vertex_count).unwrap());
+            for _vertex in 0..vertex_count {
+                tangents.push(<[i8; 4]>::read_options(reader, endian, ())?);
+            }
+
+            Some(tangents)
+        } else {
+            None
+        };
+
         // Read Params (Colors)
         let color_params = if args.is_valid_attribute(AttributeType::Param) {
             reader.seek(SeekFrom::Start(u64::from(
                 args.attr_offset[AttributeType::Param as usize],
             )))?;
 
-            let color_count = args.attr_size[AttributeType::Param as usize] / PER_VERTEX_SIZE;
-
-            let mut color_params = Vec::with_capacity(usize::try_from(color_count).unwrap());
-            for _color in 0..color_count {
-                color_params.push(<u8>::read_options(reader, endian, ())?);
+            let mut color_params = Vec::with_capacity(usize::try_from(vertex_count).unwrap());
+            for _vertex in 0..vertex_count {
+                color_params.push(<[u8; 4]>::read_options(reader, endian, ())?);
             }
 
             Some(color_params)
@@ -172,25 +189,145 @@ impl BinRead for ShapeMesh {
             indices,
             normals,
             uvs,
+            tangents,
             color_params,
         })
     }
 }
 
+impl ShapeMesh {
+    /// Packs a mesh from full precision vertices, e.g. one exported from a modelling program.
+    /// Positions become `f16` with a w of `1.0`, and normals become `10_10_10_2` Snorm.
+    #[must_use]
+    pub fn from_f32(
+        positions: &[[f32; 3]],
+        normals: Option<&[[f32; 3]]>,
+        uvs: Option<&[[f32; 2]]>,
+        indices: Vec<u16>,
+    ) -> ShapeMesh {
+        ShapeMesh {
+            positions: positions
+                .iter()
+                .map(|&[x, y, z]| [x, y, z, 1.0].map(Float16::from_f32))
+                .collect(),
+            indices,
+            normals: normals
+                .map(|normals| normals.iter().copied().map(Vec3PackedSnorm::pack).collect()),
+            uvs: uvs.map(|uvs| uvs.iter().map(|uv| uv.map(Float16::from_f32)).collect()),
+            tangents: None,
+            color_params: None,
+        }
+    }
+
+    /// Smallest and largest corners of the box around every position.
+    /// Both are zero for a mesh without positions.
+    #[must_use]
+    pub fn bounding_box(&self) -> [[f32; 3]; 2] {
+        let mut positions = self
+            .positions
+            .iter()
+            .map(|&[x, y, z, _]| [x, y, z].map(Float16::as_f32));
+
+        let Some(first) = positions.next() else {
+            return [[0.0; 3]; 2];
+        };
+
+        positions.fold([first, first], |[min, max], position| {
+            [
+                std::array::from_fn(|i| min[i].min(position[i])),
+                std::array::from_fn(|i| max[i].max(position[i])),
+            ]
+        })
+    }
+
+    /// Encodes the mesh the way it's stored in a shape file, before compression.
+    /// The inverse of reading it with the returned [`ResourceShapeAttribute`].
+    ///
+    /// Vertex attributes are laid out one after another, followed by the indices.
+    ///
+    /// # Errors
+    /// - An attribute doesn't have one value per position
+    pub fn encode(&self) -> Result<(Vec<u8>, ResourceShapeAttribute), Box<dyn Error>> {
+        fn write_attribute<T: for<'a> BinWrite<Args<'a> = ()>>(
+            writer: &mut Cursor<Vec<u8>>,
+            values: &[T],
+        ) -> binrw::BinResult<(u32, u32)> {
+            let start = writer.position();
+            values.write_le(writer)?;
+
+            Ok((start as u32, (writer.position() - start) as u32))
+        }
+
+        let vertex_count = self.positions.len();
+        let lengths = [
+            self.normals.as_ref().map(Vec::len),
+            self.uvs.as_ref().map(Vec::len),
+            self.tangents.as_ref().map(Vec::len),
+            self.color_params.as_ref().map(Vec::len),
+        ];
+        if lengths.into_iter().flatten().any(|len| len != vertex_count) {
+            return Err("Every vertex attribute needs one value per position.".into());
+        }
+
+        let mut writer = Cursor::new(Vec::new());
+        let mut attribute = ResourceShapeAttribute {
+            bounding_box: self.bounding_box(),
+            ..Default::default()
+        };
+
+        let mut set = |attr_type: AttributeType, (offset, size): (u32, u32)| {
+            let index = attr_type as usize;
+            attribute.attr_offset[index] = offset;
+            attribute.attr_size[index] = size;
+        };
+
+        set(
+            AttributeType::Position,
+            write_attribute(&mut writer, &self.positions)?,
+        );
+        if let Some(normals) = &self.normals {
+            set(
+                AttributeType::Normal,
+                write_attribute(&mut writer, normals)?,
+            );
+        }
+        if let Some(uvs) = &self.uvs {
+            set(AttributeType::Uv, write_attribute(&mut writer, uvs)?);
+        }
+        if let Some(tangents) = &self.tangents {
+            set(
+                AttributeType::Tangent,
+                write_attribute(&mut writer, tangents)?,
+            );
+        }
+        if let Some(color_params) = &self.color_params {
+            set(
+                AttributeType::Param,
+                write_attribute(&mut writer, color_params)?,
+            );
+        }
+
+        (attribute.index_offset, attribute.index_size) =
+            write_attribute(&mut writer, &self.indices)?;
+
+        Ok((writer.into_inner(), attribute))
+    }
+}
+
 /// Specifies where data is, now big it is, and how compressed it is.
 /// Used for both textures and shapes.
-#[derive(BinRead, Debug, Clone, Copy)]
+#[derive(BinRead, BinWrite, Default, Debug, Clone, Copy)]
 pub struct ResourceCommonAttribute {
     pub offset: u32,
     pub size: u32,
     pub size_compressed: u32,
     pub compression_level: u8,
     pub memory_level: u8,
-    _pad: u16,
+    pub(crate) _pad: u16,
 }
 
 /// Specifies where {vertex,index} buffers are, and how big they are.
-#[derive(BinRead, Default, Debug, Clone, Copy)]
+#[derive(BinRead, BinWrite, Default, Debug, Clone, Copy)]
 pub struct ResourceShapeAttribute {
     pub attr_offset: [u32; 5],
     pub attr_size: [u32; 5],
@@ -207,7 +344,7 @@ impl ResourceShapeAttribute {
 
 /// All the data required to read a mesh from the shape file.
 /// Essentially a 'pointer' to the data.
-#[derive(BinRead, Debug, Clone, Copy)]
+#[derive(BinRead, BinWrite, Default, Debug, Clone, Copy)]
 pub struct ShapeElement {
     pub common: ResourceCommonAttribute,
     pub shape: ResourceShapeAttribute,
@@ -229,7 +366,10 @@ impl ShapeElement {
 
         let range = start..end;
 
-        let shape_data = inflate_bytes(&file[range])?;
+        let shape_data = inflate_bytes(
+            file.get(range)
+                .ok_or("Shape is out of bounds of the resource file.")?,
+        )?;
 
         // if !cfg!(target_family = "wasm") {
         //     std::fs::write("./shape.dat", shape_data.clone())?;
@@ -244,20 +384,26 @@ impl ShapeElement {
 
 /// Contains positional data for any headwear that
 /// may be placed on the `CharModel` post-render.
-#[expect(unused)]
-#[derive(BinRead, Debug, Clone, Copy)]
+///
+/// Each point is relative to the hair mesh, before it's moved by
+/// [`ResourceShapeFacelineTransform::hair_translate`] or flipped.
+/// Rotations are Euler angles in degrees.
+#[derive(BinRead, BinWrite, Default, Debug, Clone, Copy)]
 pub struct ResourceShapeHairTransform {
-    front_translate: [f32; 3],
-    front_rotate: [f32; 3],
-    side_translate: [f32; 3],
-    side_rotate: [f32; 3],
-    top_translate: [f32; 3],
-    top_rotate: [f32; 3],
+    /// On the forehead, e.g. for a badge or goggles.
+    pub front_translate: [f32; 3],
+    pub front_rotate: [f32; 3],
+    /// On the side of the head, e.g. for headphones.
+    pub side_translate: [f32; 3],
+    pub side_rotate: [f32; 3],
+    /// On the top of the head, e.g. for a hat.
+    pub top_translate: [f32; 3],
+    pub top_rotate: [f32; 3],
 }
 
 /// Contains positional data used to move face parts
 /// like the beard, nose, hair, glasses, etc.
-#[derive(BinRead, Debug, Clone, Copy)]
+#[derive(BinRead, BinWrite, Default, Debug, Clone, Copy)]
 pub struct ResourceShapeFacelineTransform {
     pub hair_translate: [f32; 3],
     pub nose_translate: [f32; 3],
@@ -265,7 +411,7 @@ pub struct ResourceShapeFacelineTransform {
 }
 
 /// Every type of shape mesh stored in the resource data.
-#[derive(Clone, Copy, Debug, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, TryFromPrimitive)]
 #[repr(u8)]
 pub enum Shape {
     Beard = 0,
@@ -284,6 +430,24 @@ pub enum Shape {
     FaceLineTransform,
 }
 
+impl Shape {
+    /// Every shape that has meshes, in the order they're stored in a [`ResourceShape`].
+    pub const MESHES: [Shape; 12] = [
+        Shape::Beard,
+        Shape::FaceLine,
+        Shape::Mask,
+        Shape::HatNormal,
+        Shape::HatCap,
+        Shape::ForeheadNormal,
+        Shape::ForeheadCap,
+        Shape::HairNormal,
+        Shape::HairCap,
+        Shape::Glasses,
+        Shape::Nose,
+        Shape::NoseLine,
+    ];
+}
+
 /// Generic 'pointer' in the resource data.
 #[derive(Clone, Copy)]
 pub enum GenericResourceShape {
@@ -294,8 +458,8 @@ pub enum GenericResourceShape {
 
 /// Header of the `Shape` resource file. Contains model data for `CharModel`s.
 #[allow(unused)]
-#[derive(BinRead, Debug, Clone, Copy)]
-#[br(little, magic = b"NFSR")]
+#[derive(BinRead, BinWrite, Debug, Clone, Copy)]
+#[brw(little, magic = b"NFSR")]
 pub struct ResourceShape {
     ver: u32,
     file_size: u32,
@@ -320,7 +484,101 @@ pub struct ResourceShape {
     pub face_line_transform: [ResourceShapeFacelineTransform; 12],
 }
 
+impl Default for ResourceShape {
+    /// A header without any meshes or transforms.
+    fn default() -> Self {
+        use std::array::from_fn;
+
+        ResourceShape {
+            // Not checked by readers.
+            ver: 0,
+            file_size: 0,
+            max_size: [0; 12],
+            max_alignment: [0; 12],
+            beard: from_fn(|_| ShapeElement::default()),
+            face_line: from_fn(|_| ShapeElement::default()),
+            mask: from_fn(|_| ShapeElement::default()),
+            hat_normal: from_fn(|_| ShapeElement::default()),
+            hat_cap: from_fn(|_| ShapeElement::default()),
+            forehead_normal: from_fn(|_| ShapeElement::default()),
+            forehead_cap: from_fn(|_| ShapeElement::default()),
+            hair_normal: from_fn(|_| ShapeElement::default()),
+            hair_cap: from_fn(|_| ShapeElement::default()),
+            glasses: from_fn(|_| ShapeElement::default()),
+            nose: from_fn(|_| ShapeElement::default()),
+            nose_line: from_fn(|_| ShapeElement::default()),
+            hair_transform: from_fn(|_| ResourceShapeHairTransform::default()),
+            face_line_transform: from_fn(|_| ResourceShapeFacelineTransform::default()),
+        }
+    }
+}
+
 impl ResourceShape {
+    /// Format version from the header.
+    #[allow(clippy::must_use_candidate)]
+    pub fn version(&self) -> u32 {
+        self.ver
+    }
+
+    /// Every mesh element of `shape`.
+    /// Empty for [`Shape::HairTransform`] and [`Shape::FaceLineTransform`].
+    #[allow(clippy::must_use_candidate)]
+    pub fn elements(&self, shape: Shape) -> &[ShapeElement] {
+        match shape {
+            Shape::Beard => &self.beard,
+            Shape::FaceLine => &self.face_line,
+            Shape::Mask => &self.mask,
+            Shape::HatNormal => &self.hat_normal,
+            Shape::HatCap => &self.hat_cap,
+            Shape::ForeheadNormal => &self.forehead_normal,
+            Shape::ForeheadCap => &self.forehead_cap,
+            Shape::HairNormal => &self.hair_normal,
+            Shape::HairCap => &self.hair_cap,
+            Shape::Glasses => &self.glasses,
+            Shape::Nose => &self.nose,
+            Shape::NoseLine => &self.nose_line,
+            Shape::HairTransform | Shape::FaceLineTransform => &[],
+        }
+    }
+
+    pub(crate) fn elements_mut(&mut self, shape: Shape) -> &mut [ShapeElement] {
+        match shape {
+            Shape::Beard => &mut self.beard,
+            Shape::FaceLine => &mut self.face_line,
+            Shape::Mask => &mut self.mask,
+            Shape::HatNormal => &mut self.hat_normal,
+            Shape::HatCap => &mut self.hat_cap,
+            Shape::ForeheadNormal => &mut self.forehead_normal,
+            Shape::ForeheadCap => &mut self.forehead_cap,
+            Shape::HairNormal => &mut self.hair_normal,
+            Shape::HairCap => &mut self.hair_cap,
+            Shape::Glasses => &mut self.glasses,
+            Shape::Nose => &mut self.nose,
+            Shape::NoseLine => &mut self.nose_line,
+            Shape::HairTransform | Shape::FaceLineTransform => &mut [],
+        }
+    }
+
+    /// Sets the file size and recomputes the largest mesh of each shape,
+    /// which a reader allocates its buffers from.
+    pub(crate) fn set_sizes(&mut self, file_size: u32) {
+        self.file_size = file_size;
+        for shape in Shape::MESHES {
+            self.max_size[shape as usize] = self
+                .elements(shape)
+                .iter()
+                .map(|element| element.common.size)
+                .max()
+                .unwrap_or(0);
+        }
+    }
+
+    /// Raises the buffer alignment a reader needs for meshes of `shape` to at least `alignment`.
+    pub(crate) fn raise_alignment(&mut self, shape: Shape, alignment: u32) {
+        let max_alignment = &mut self.max_alignment[shape as usize];
+        *max_alignment = (*max_alignment).max(alignment);
+    }
+
     #[allow(clippy::must_use_candidate)]
     pub fn index_by_shape(&self, shape: Shape, index: usize) -> Option<GenericResourceShape> {
         let shape_el = |x: &ShapeElement| GenericResourceShape::Element(*x);
@@ -0,0 +1,467 @@
+//! Where textures and shapes come from, independent of the resource format.
+//!
+//! Renderers look parts up through [`TextureSource`] and [`ShapeSource`] instead of indexing
+//! a header directly, so that any resource file can be drawn from. Indices that a file doesn't
+//! have (e.g. Miitomo's extra eyes, when reading Nx resources) come back as `None`.
+//!
+//! ```no_run
+//! use vee_resources::source::{ArchResourceFile, TextureSource};
+//! use vee_resources::tex::TexturePart;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let resources = ArchResourceFile::new(std::fs::read("./AFLResHigh_2_3.dat")?)?;
+//!
+//! // Eyes past 62 only exist in Miitomo resources.
+//! assert_eq!(resources.texture_count(TexturePart::Eye), 80);
+//! let eye = resources.texture(TexturePart::Eye, 70)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cafe::{ArchResourceHeader, CafeResourceHeader};
+use crate::color::ColorSpace;
+use crate::shape::{
+    GenericResourceShape, ResourceShape, ResourceShapeFacelineTransform,
+    ResourceShapeHairTransform, Shape, ShapeMesh,
+};
+use crate::tex::{CompressedTexture, ResourceTexture, TexturePart};
+use binrw::BinRead;
+use image::RgbaImage;
+use std::error::Error;
+use std::io::Cursor;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Something textures can be read from.
+pub trait TextureSource {
+    /// Number of textures of `part`. Some of these may be empty.
+    fn texture_count(&self, part: TexturePart) -> usize;
+
+    /// Decodes a texture. Returns `None` for an empty texture, or an index past
+    /// [`Self::texture_count`].
+    ///
+    /// # Errors
+    /// - The texture data is malformed
+    fn texture(&self, part: TexturePart, index: usize)
+    -> Result<Option<RgbaImage>, Box<dyn Error>>;
+
+    /// Like [`Self::texture`], but lets a caching source share its copy.
+    /// Wraps a freshly decoded texture by default.
+    ///
+    /// # Errors
+    /// - The texture data is malformed
+    fn texture_shared(
+        &self,
+        part: TexturePart,
+        index: usize,
+    ) -> Result<Option<Arc<RgbaImage>>, Box<dyn Error>> {
+        Ok(self.texture(part, index)?.map(Arc::new))
+    }
+
+    /// Reads a texture without decoding it, so a GPU can sample it directly.
+    /// Returns `None` for an empty texture, an index past [`Self::texture_count`],
+    /// an uncompressed texture, or a source that only has decoded textures (the default).
+    ///
+    /// # Errors
+    /// - The texture data is malformed
+    fn compressed_texture(
+        &self,
+        _part: TexturePart,
+        _index: usize,
+    ) -> Result<Option<CompressedTexture>, Box<dyn Error>> {
+        Ok(None)
+    }
+
+    /// Which space the texels are stored in. Renderers sample [`ColorSpace::Srgb`] textures
+    /// as sRGB, and [`ColorSpace::Linear`] ones as they are. Defaults to sRGB.
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Srgb
+    }
+}
+
+/// Something shapes can be read from.
+pub trait ShapeSource {
+    /// Number of meshes of `shape`. Some of these may be empty.
+    /// Always `0` for [`Shape::HairTransform`] and [`Shape::FaceLineTransform`].
+    fn shape_count(&self, shape: Shape) -> usize;
+
+    /// Reads a mesh. Returns `None` for an empty mesh, or an index past [`Self::shape_count`].
+    ///
+    /// # Errors
+    /// - The shape data is malformed
+    fn mesh(&self, shape: Shape, index: usize) -> Result<Option<ShapeMesh>, Box<dyn Error>>;
+
+    /// Like [`Self::mesh`], but lets a caching source share its copy.
+    /// Wraps a freshly read mesh by default.
+    ///
+    /// # Errors
+    /// - The shape data is malformed
+    fn mesh_shared(
+        &self,
+        shape: Shape,
+        index: usize,
+    ) -> Result<Option<Arc<ShapeMesh>>, Box<dyn Error>> {
+        Ok(self.mesh(shape, index)?.map(Arc::new))
+    }
+
+    /// Smallest and largest corners of a mesh, as `[min, max]`.
+    /// Returns `None` where [`Self::mesh`] would.
+    ///
+    /// Decodes the mesh by default. Sources that keep bounds in their header don't need to.
+    ///
+    /// # Errors
+    /// - The shape data is malformed
+    fn bounding_box(
+        &self,
+        shape: Shape,
+        index: usize,
+    ) -> Result<Option<[[f32; 3]; 2]>, Box<dyn Error>> {
+        Ok(self.mesh(shape, index)?.map(|mesh| mesh.bounding_box()))
+    }
+
+    /// Reads the transform for a hair type. Returns `None` if there isn't one.
+    ///
+    /// # Errors
+    /// - The shape data is malformed
+    fn hair_transform(
+        &self,
+        index: usize,
+    ) -> Result<Option<ResourceShapeHairTransform>, Box<dyn Error>>;
+
+    /// Reads the transform for a faceline type. Returns `None` if there isn't one.
+    ///
+    /// # Errors
+    /// - The shape data is malformed
+    fn face_line_transform(
+        &self,
+        index: usize,
+    ) -> Result<Option<ResourceShapeFacelineTransform>, Box<dyn Error>>;
+}
+
+/// Quality tier of a Nx resource file. Higher tiers have denser meshes and bigger textures.
+///
+/// ```
+/// use vee_resources::color::ColorSpace;
+/// use vee_resources::source::ResourceTier;
+///
+/// let tier: ResourceTier = "high".parse().unwrap();
+/// assert_eq!(tier.shape_file_name(), "ShapeHigh.dat");
+/// assert_eq!(tier.texture_file_name(ColorSpace::Linear), "NXTextureHighLinear.dat");
+/// assert_eq!(ResourceTier::from_file_name("ShapeLow.dat"), Some(ResourceTier::Low));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ResourceTier {
+    Low,
+    #[default]
+    Mid,
+    High,
+}
+
+impl ResourceTier {
+    pub const ALL: [ResourceTier; 3] = [ResourceTier::Low, ResourceTier::Mid, ResourceTier::High];
+
+    /// Name of the official shape file of this tier.
+    #[must_use]
+    pub fn shape_file_name(self) -> &'static str {
+        match self {
+            ResourceTier::Low => "ShapeLow.dat",
+            ResourceTier::Mid => "ShapeMid.dat",
+            ResourceTier::High => "ShapeHigh.dat",
+        }
+    }
+
+    /// Name of the official texture file of this tier, in `color_space`.
+    #[must_use]
+    pub fn texture_file_name(self, color_space: ColorSpace) -> &'static str {
+        match (self, color_space) {
+            (ResourceTier::Low, ColorSpace::Srgb) => "NXTextureLowSRGB.dat",
+            (ResourceTier::Low, ColorSpace::Linear) => "NXTextureLowLinear.dat",
+            (ResourceTier::Mid, ColorSpace::Srgb) => "NXTextureMidSRGB.dat",
+            (ResourceTier::Mid, ColorSpace::Linear) => "NXTextureMidLinear.dat",
+            (ResourceTier::High, ColorSpace::Srgb) => "NXTextureHighSRGB.dat",
+            (ResourceTier::High, ColorSpace::Linear) => "NXTextureHighLinear.dat",
+        }
+    }
+
+    /// Tier of an official resource file (Nx, Cafe or Arch) from its name, e.g. `High` for
+    /// `NXTextureHighLinear.dat` or `Mid` for `FFLResMiddle.dat`.
+    ///
+    /// Neither the NFSR nor the NFTR header has a field for the tier, and they're laid out the
+    /// same in every tier, so the name is all there is to go by. Returns `None` for any other
+    /// name.
+    #[must_use]
+    pub fn from_file_name(name: &str) -> Option<ResourceTier> {
+        let name = name.to_ascii_lowercase();
+
+        if name.contains("high") {
+            Some(ResourceTier::High)
+        } else if name.contains("mid") {
+            Some(ResourceTier::Mid)
+        } else if name.contains("low") {
+            Some(ResourceTier::Low)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for ResourceTier {
+    type Err = String;
+
+    /// Parses `low`, `mid`, `middle` or `high`, in any case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "low" => Ok(ResourceTier::Low),
+            "mid" | "middle" => Ok(ResourceTier::Mid),
+            "high" => Ok(ResourceTier::High),
+            _ => Err(format!(
+                "Unknown resource tier {s:?}, expected low, mid or high."
+            )),
+        }
+    }
+}
+
+/// A Nx texture resource file (e.g. `NXTextureMidSRGB.dat`), with its header read.
+///
+/// `data` is usually a `Vec<u8>`, but can be anything that derefs to bytes,
+/// such as a memory-mapped file.
+#[derive(Clone)]
+pub struct NxTextureFile<D = Vec<u8>> {
+    pub header: ResourceTexture,
+    pub data: D,
+    /// Whether this is an `SRGB` or a `Linear` texture file. Read from the header's formats
+    /// (see [`ResourceTexture::color_space`]), or given to [`NxTextureFile::new_in`].
+    pub color_space: ColorSpace,
+}
+
+impl<D: AsRef<[u8]>> NxTextureFile<D> {
+    /// # Errors
+    /// - `data` isn't a texture resource file
+    /// - The header doesn't tell the color space, see [`ResourceTexture::color_space`].
+    ///   [`NxTextureFile::new_in`] reads such files.
+    pub fn new(data: D) -> Result<NxTextureFile<D>, Box<dyn Error>> {
+        let header = ResourceTexture::read(&mut Cursor::new(data.as_ref()))?;
+
+        Ok(NxTextureFile {
+            color_space: header.color_space()?,
+            header,
+            data,
+        })
+    }
+
+    /// Reads a texture file that's known to be in `color_space` (e.g. from its name, see
+    /// [`ColorSpace::from_file_name`]), whatever its header says.
+    ///
+    /// # Errors
+    /// - `data` isn't a texture resource file
+    pub fn new_in(data: D, color_space: ColorSpace) -> binrw::BinResult<NxTextureFile<D>> {
+        let header = ResourceTexture::read(&mut Cursor::new(data.as_ref()))?;
+
+        Ok(NxTextureFile {
+            header,
+            data,
+            color_space,
+        })
+    }
+}
+
+impl<D: AsRef<[u8]>> TextureSource for NxTextureFile<D> {
+    fn texture_count(&self, part: TexturePart) -> usize {
+        self.header.part(part).len()
+    }
+
+    fn texture(
+        &self,
+        part: TexturePart,
+        index: usize,
+    ) -> Result<Option<RgbaImage>, Box<dyn Error>> {
+        match self.header.part(part).get(index) {
+            Some(element) => element.get_image(self.data.as_ref()),
+            None => Ok(None),
+        }
+    }
+
+    fn compressed_texture(
+        &self,
+        part: TexturePart,
+        index: usize,
+    ) -> Result<Option<CompressedTexture>, Box<dyn Error>> {
+        match self.header.part(part).get(index) {
+            Some(element) => element.get_compressed(self.data.as_ref()),
+            None => Ok(None),
+        }
+    }
+
+    fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+}
+
+/// A Nx shape resource file (e.g. `ShapeMid.dat`), with its header read.
+///
+/// `data` is usually a `Vec<u8>`, but can be anything that derefs to bytes,
+/// such as a memory-mapped file.
+#[derive(Clone)]
+pub struct NxShapeFile<D = Vec<u8>> {
+    pub header: ResourceShape,
+    pub data: D,
+}
+
+impl<D: AsRef<[u8]>> NxShapeFile<D> {
+    /// # Errors
+    /// - `data` isn't a shape resource file
+    pub fn new(data: D) -> binrw::BinResult<NxShapeFile<D>> {
+        let header = ResourceShape::read(&mut Cursor::new(data.as_ref()))?;
+
+        Ok(NxShapeFile { header, data })
+    }
+}
+
+impl<D: AsRef<[u8]>> ShapeSource for NxShapeFile<D> {
+    fn shape_count(&self, shape: Shape) -> usize {
+        match shape {
+            Shape::HairTransform | Shape::FaceLineTransform => 0,
+            shape => (0..)
+                .take_while(|&index| self.header.index_by_shape(shape, index).is_some())
+                .count(),
+        }
+    }
+
+    fn mesh(&self, shape: Shape, index: usize) -> Result<Option<ShapeMesh>, Box<dyn Error>> {
+        match self.header.index_by_shape(shape, index) {
+            // For some reason there are just empty gaps in the shape data.
+            Some(GenericResourceShape::Element(mut element)) if element.common.size != 0 => {
+                element.mesh(self.data.as_ref()).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn bounding_box(
+        &self,
+        shape: Shape,
+        index: usize,
+    ) -> Result<Option<[[f32; 3]; 2]>, Box<dyn Error>> {
+        match self.header.index_by_shape(shape, index) {
+            Some(GenericResourceShape::Element(element)) if element.common.size != 0 => {
+                Ok(Some(element.shape.bounding_box))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn hair_transform(
+        &self,
+        index: usize,
+    ) -> Result<Option<ResourceShapeHairTransform>, Box<dyn Error>> {
+        Ok(self.header.hair_transform.get(index).copied())
+    }
+
+    fn face_line_transform(
+        &self,
+        index: usize,
+    ) -> Result<Option<ResourceShapeFacelineTransform>, Box<dyn Error>> {
+        Ok(self.header.face_line_transform.get(index).copied())
+    }
+}
+
+/// Implements the sources for a combined Cafe-style resource file.
+macro_rules! cafe_resource_file {
+    ($(#[$attr:meta])* $name:ident($header:ty)) => {
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name<D = Vec<u8>> {
+            pub header: $header,
+            pub data: D,
+        }
+
+        impl<D: AsRef<[u8]>> $name<D> {
+            /// # Errors
+            /// - `data` isn't a resource file of this kind
+            pub fn new(data: D) -> binrw::BinResult<$name<D>> {
+                let header = <$header>::read(&mut Cursor::new(data.as_ref()))?;
+
+                Ok($name { header, data })
+            }
+        }
+
+        impl<D: AsRef<[u8]>> TextureSource for $name<D> {
+            fn texture_count(&self, part: TexturePart) -> usize {
+                self.header.texture.part(part).len()
+            }
+
+            fn texture(
+                &self,
+                part: TexturePart,
+                index: usize,
+            ) -> Result<Option<RgbaImage>, Box<dyn Error>> {
+                match self.header.texture.part(part).get(index) {
+                    Some(info) => info.get_image(self.data.as_ref()),
+                    None => Ok(None),
+                }
+            }
+        }
+
+        impl<D: AsRef<[u8]>> ShapeSource for $name<D> {
+            fn shape_count(&self, shape: Shape) -> usize {
+                (0..)
+                    .take_while(|&index| self.header.shape.index_by_shape(shape, index).is_some())
+                    .count()
+            }
+
+            fn mesh(
+                &self,
+                shape: Shape,
+                index: usize,
+            ) -> Result<Option<ShapeMesh>, Box<dyn Error>> {
+                match self.header.shape.index_by_shape(shape, index) {
+                    Some(info) => info.mesh(self.data.as_ref()),
+                    None => Ok(None),
+                }
+            }
+
+            fn bounding_box(
+                &self,
+                shape: Shape,
+                index: usize,
+            ) -> Result<Option<[[f32; 3]; 2]>, Box<dyn Error>> {
+                match self.header.shape.index_by_shape(shape, index) {
+                    Some(info) => info.bounding_box(self.data.as_ref()),
+                    None => Ok(None),
+                }
+            }
+
+            fn hair_transform(
+                &self,
+                index: usize,
+            ) -> Result<Option<ResourceShapeHairTransform>, Box<dyn Error>> {
+                match self.header.shape.hair_normal.get(index) {
+                    Some(info) => info.hair_transform(self.data.as_ref()),
+                    None => Ok(None),
+                }
+            }
+
+            fn face_line_transform(
+                &self,
+                index: usize,
+            ) -> Result<Option<ResourceShapeFacelineTransform>, Box<dyn Error>> {
+                match self.header.shape.faceline.get(index) {
+                    Some(info) => info.face_line_transform(self.data.as_ref()),
+                    None => Ok(None),
+                }
+            }
+        }
+
+    };
+}
+
+cafe_resource_file!(
+    /// A Cafe resource file (`FFLResHigh.dat`, `FFLResMiddle.dat`), with its header read.
+    CafeResourceFile(CafeResourceHeader)
+);
+
+cafe_resource_file!(
+    /// A Miitomo resource file (e.g. `AFLResHigh_2_3.dat`), with its header read.
+    /// Has more eyes, eyebrows, glasses and mouths than the other formats.
+    ArchResourceFile(ArchResourceHeader)
+);
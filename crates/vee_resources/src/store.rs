@@ -0,0 +1,556 @@
+//! Shared, cached access to resource files.
+//!
+//! [`ResourceStore`] memory-maps resource files instead of reading them into memory whole,
+//! and keeps the most recently decoded textures and meshes around, so drawing the same part
+//! twice (e.g. both eyes) only inflates it once. Without the `mmap` feature (e.g. on wasm),
+//! files are read whole when opened, and parts are still only decoded when asked for. It's `Send + Sync`, so one store can be
+//! shared between rendering threads with an [`Arc`].
+//!
+//! ```no_run
+//! use vee_resources::ResourceStore;
+//! use vee_resources::source::TextureSource;
+//! use vee_resources::tex::TexturePart;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let store = ResourceStore::open_nx("./ShapeMid.dat", "./NXTextureMidSRGB.dat")?;
+//!
+//! // The second lookup comes from the cache.
+//! let eye = store.texture_shared(TexturePart::Eye, 2)?;
+//! let same_eye = store.texture_shared(TexturePart::Eye, 2)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::color::ColorSpace;
+use crate::shape::{ResourceShapeFacelineTransform, ResourceShapeHairTransform, Shape, ShapeMesh};
+use crate::source::{
+    ArchResourceFile, CafeResourceFile, NxShapeFile, NxTextureFile, ResourceTier, ShapeSource,
+    TextureSource,
+};
+use crate::tex::{CompressedTexture, ResourceTexture, TexturePart};
+use binrw::BinRead;
+use image::RgbaImage;
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Number of decoded parts a [`ResourceStore`] keeps by default.
+/// Enough for every part of a couple of characters.
+pub const DEFAULT_CACHE_CAPACITY: usize = 64;
+
+/// Resources shared between threads, with a bounded cache of decoded parts.
+///
+/// Implements [`TextureSource`] and [`ShapeSource`], so it can be used anywhere a resource
+/// file can. [`TextureSource::texture_shared`] and [`ShapeSource::mesh_shared`] skip copying
+/// out of the cache.
+pub struct ResourceStore {
+    textures: Arc<dyn TextureSource + Send + Sync>,
+    shapes: Arc<dyn ShapeSource + Send + Sync>,
+    cache: Mutex<LruCache>,
+}
+
+impl ResourceStore {
+    /// Wraps already opened sources, caching up to [`DEFAULT_CACHE_CAPACITY`] parts.
+    pub fn new(
+        textures: Arc<dyn TextureSource + Send + Sync>,
+        shapes: Arc<dyn ShapeSource + Send + Sync>,
+    ) -> ResourceStore {
+        ResourceStore::with_capacity(textures, shapes, DEFAULT_CACHE_CAPACITY)
+    }
+
+    /// Wraps already opened sources, caching up to `capacity` parts.
+    /// A `capacity` of `0` turns caching off.
+    pub fn with_capacity(
+        textures: Arc<dyn TextureSource + Send + Sync>,
+        shapes: Arc<dyn ShapeSource + Send + Sync>,
+        capacity: usize,
+    ) -> ResourceStore {
+        ResourceStore {
+            textures,
+            shapes,
+            cache: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Opens a Nx shape file (e.g. `ShapeMid.dat`) and texture file
+    /// (e.g. `NXTextureMidSRGB.dat`). The color space of the textures is read from the
+    /// header (see [`crate::tex::ResourceTexture::color_space`]). If it doesn't tell, the
+    /// name has to be an official one (e.g. `NXTextureMidLinear.dat`).
+    ///
+    /// # Errors
+    /// - Either file can't be opened or read
+    /// - Either file isn't a resource file of its kind
+    /// - Neither the texture header nor the texture file name tells the color space
+    pub fn open_nx(
+        shape_path: impl AsRef<Path>,
+        texture_path: impl AsRef<Path>,
+    ) -> Result<ResourceStore, Box<dyn Error>> {
+        let texture_path = texture_path.as_ref();
+
+        let shapes = NxShapeFile::new(open_file(shape_path.as_ref())?)?;
+        let data = open_file(texture_path)?;
+        let header = ResourceTexture::read(&mut Cursor::new(data.as_ref()))?;
+
+        let color_space = match header.color_space() {
+            Ok(color_space) => color_space,
+            Err(error) => texture_path
+                .file_name()
+                .and_then(|name| ColorSpace::from_file_name(&name.to_string_lossy()))
+                .ok_or(error)?,
+        };
+        let textures = NxTextureFile {
+            header,
+            data,
+            color_space,
+        };
+
+        Ok(ResourceStore::new(Arc::new(textures), Arc::new(shapes)))
+    }
+
+    /// Opens the official Nx files of `tier` in `dir`, e.g. `ShapeHigh.dat` and
+    /// `NXTextureHighSRGB.dat`. The `SRGB` textures are used if both color spaces are there.
+    ///
+    /// # Errors
+    /// - Neither texture file of `tier` is in `dir`
+    /// - See [`Self::open_nx`]
+    pub fn open_nx_tier(
+        dir: impl AsRef<Path>,
+        tier: ResourceTier,
+    ) -> Result<ResourceStore, Box<dyn Error>> {
+        let dir = dir.as_ref();
+
+        let texture_path = [ColorSpace::Srgb, ColorSpace::Linear]
+            .map(|color_space| dir.join(tier.texture_file_name(color_space)))
+            .into_iter()
+            .find(|path| path.exists())
+            .ok_or_else(|| {
+                format!(
+                    "Neither {} nor {} is in {}.",
+                    tier.texture_file_name(ColorSpace::Srgb),
+                    tier.texture_file_name(ColorSpace::Linear),
+                    dir.display()
+                )
+            })?;
+
+        ResourceStore::open_nx(dir.join(tier.shape_file_name()), texture_path)
+    }
+
+    /// Opens a Cafe resource file (`FFLResHigh.dat`, `FFLResMiddle.dat`).
+    ///
+    /// # Errors
+    /// - The file can't be opened or read
+    /// - The file isn't a Cafe resource file
+    pub fn open_cafe(path: impl AsRef<Path>) -> Result<ResourceStore, Box<dyn Error>> {
+        let resources = Arc::new(CafeResourceFile::new(open_file(path.as_ref())?)?);
+
+        Ok(ResourceStore::new(resources.clone(), resources))
+    }
+
+    /// Opens a Miitomo resource file (e.g. `AFLResHigh_2_3.dat`).
+    ///
+    /// # Errors
+    /// - The file can't be opened or read
+    /// - The file isn't a Miitomo resource file
+    pub fn open_arch(path: impl AsRef<Path>) -> Result<ResourceStore, Box<dyn Error>> {
+        let resources = Arc::new(ArchResourceFile::new(open_file(path.as_ref())?)?);
+
+        Ok(ResourceStore::new(resources.clone(), resources))
+    }
+
+    /// Maximum number of decoded parts kept around.
+    pub fn capacity(&self) -> usize {
+        self.lock_cache().capacity
+    }
+
+    /// Drops every cached part.
+    pub fn clear_cache(&self) {
+        self.lock_cache().entries.clear();
+    }
+
+    fn lock_cache(&self) -> std::sync::MutexGuard<'_, LruCache> {
+        // The cache is never left half-updated, so a panic elsewhere doesn't invalidate it.
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl TextureSource for ResourceStore {
+    fn texture_count(&self, part: TexturePart) -> usize {
+        self.textures.texture_count(part)
+    }
+
+    fn texture(
+        &self,
+        part: TexturePart,
+        index: usize,
+    ) -> Result<Option<RgbaImage>, Box<dyn Error>> {
+        // Owned textures have to be copied out of the cache.
+        Ok(self.texture_shared(part, index)?.map(Arc::unwrap_or_clone))
+    }
+
+    fn texture_shared(
+        &self,
+        part: TexturePart,
+        index: usize,
+    ) -> Result<Option<Arc<RgbaImage>>, Box<dyn Error>> {
+        let key = CacheKey::Texture(part, index);
+        if let Some(CacheEntry::Texture(texture)) = self.lock_cache().get(key) {
+            return Ok(texture);
+        }
+
+        // Decoded without holding the lock, so other threads aren't held up by the inflate.
+        let texture = self.textures.texture(part, index)?.map(Arc::new);
+        self.lock_cache()
+            .insert(key, CacheEntry::Texture(texture.clone()));
+
+        Ok(texture)
+    }
+
+    fn compressed_texture(
+        &self,
+        part: TexturePart,
+        index: usize,
+    ) -> Result<Option<CompressedTexture>, Box<dyn Error>> {
+        // Not cached: compressed textures are copied straight to the GPU, usually only once.
+        self.textures.compressed_texture(part, index)
+    }
+
+    fn color_space(&self) -> ColorSpace {
+        self.textures.color_space()
+    }
+}
+
+impl ShapeSource for ResourceStore {
+    fn shape_count(&self, shape: Shape) -> usize {
+        self.shapes.shape_count(shape)
+    }
+
+    fn mesh(&self, shape: Shape, index: usize) -> Result<Option<ShapeMesh>, Box<dyn Error>> {
+        Ok(self.mesh_shared(shape, index)?.map(Arc::unwrap_or_clone))
+    }
+
+    fn mesh_shared(
+        &self,
+        shape: Shape,
+        index: usize,
+    ) -> Result<Option<Arc<ShapeMesh>>, Box<dyn Error>> {
+        let key = CacheKey::Mesh(shape, index);
+        if let Some(CacheEntry::Mesh(mesh)) = self.lock_cache().get(key) {
+            return Ok(mesh);
+        }
+
+        let mesh = self.shapes.mesh(shape, index)?.map(Arc::new);
+        self.lock_cache()
+            .insert(key, CacheEntry::Mesh(mesh.clone()));
+
+        Ok(mesh)
+    }
+
+    fn bounding_box(
+        &self,
+        shape: Shape,
+        index: usize,
+    ) -> Result<Option<[[f32; 3]; 2]>, Box<dyn Error>> {
+        self.shapes.bounding_box(shape, index)
+    }
+
+    fn hair_transform(
+        &self,
+        index: usize,
+    ) -> Result<Option<ResourceShapeHairTransform>, Box<dyn Error>> {
+        self.shapes.hair_transform(index)
+    }
+
+    fn face_line_transform(
+        &self,
+        index: usize,
+    ) -> Result<Option<ResourceShapeFacelineTransform>, Box<dyn Error>> {
+        self.shapes.face_line_transform(index)
+    }
+}
+
+/// A file's contents, mapped if the `mmap` feature is on.
+#[cfg(feature = "mmap")]
+type FileData = memmap2::Mmap;
+#[cfg(not(feature = "mmap"))]
+type FileData = Vec<u8>;
+
+#[cfg(feature = "mmap")]
+fn open_file(path: &Path) -> std::io::Result<FileData> {
+    let file = std::fs::File::open(path)?;
+
+    // SAFETY: Resource files are read-only assets. If one is truncated or rewritten while
+    // mapped, reads from it are undefined, as with any memory-mapped file.
+    unsafe { memmap2::Mmap::map(&file) }
+}
+
+#[cfg(not(feature = "mmap"))]
+fn open_file(path: &Path) -> std::io::Result<FileData> {
+    std::fs::read(path)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum CacheKey {
+    Texture(TexturePart, usize),
+    Mesh(Shape, usize),
+}
+
+/// Empty parts are cached too, so they aren't looked up again.
+#[derive(Clone)]
+enum CacheEntry {
+    Texture(Option<Arc<RgbaImage>>),
+    Mesh(Option<Arc<ShapeMesh>>),
+}
+
+/// Least recently used cache. Eviction is a linear scan, which is fine at this size.
+struct LruCache {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<CacheKey, (u64, CacheEntry)>,
+}
+
+impl LruCache {
+    fn new(capacity: usize) -> LruCache {
+        LruCache {
+            capacity,
+            tick: 0,
+            entries: HashMap::with_capacity(capacity),
+        }
+    }
+
+    fn get(&mut self, key: CacheKey) -> Option<CacheEntry> {
+        self.tick += 1;
+        let (last_used, entry) = self.entries.get_mut(&key)?;
+        *last_used = self.tick;
+
+        Some(entry.clone())
+    }
+
+    fn insert(&mut self, key: CacheKey, entry: CacheEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (last_used, _))| *last_used)
+                .map(|(&key, _)| key);
+
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.tick += 1;
+        self.entries.insert(key, (self.tick, entry));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    type R = Result<(), Box<dyn Error>>;
+
+    /// Counts how many textures and meshes it has been asked to decode.
+    #[derive(Default)]
+    struct CountingSource {
+        decodes: AtomicUsize,
+    }
+
+    impl TextureSource for CountingSource {
+        fn texture_count(&self, _part: TexturePart) -> usize {
+            4
+        }
+
+        fn texture(
+            &self,
+            _part: TexturePart,
+            index: usize,
+        ) -> Result<Option<RgbaImage>, Box<dyn Error>> {
+            self.decodes.fetch_add(1, Ordering::Relaxed);
+            Ok((index < 4).then(|| RgbaImage::new(1, 1)))
+        }
+    }
+
+    impl ShapeSource for CountingSource {
+        fn shape_count(&self, _shape: Shape) -> usize {
+            0
+        }
+
+        fn mesh(&self, _shape: Shape, _index: usize) -> Result<Option<ShapeMesh>, Box<dyn Error>> {
+            self.decodes.fetch_add(1, Ordering::Relaxed);
+            Ok(None)
+        }
+
+        fn hair_transform(
+            &self,
+            _index: usize,
+        ) -> Result<Option<ResourceShapeHairTransform>, Box<dyn Error>> {
+            Ok(None)
+        }
+
+        fn face_line_transform(
+            &self,
+            _index: usize,
+        ) -> Result<Option<ResourceShapeFacelineTransform>, Box<dyn Error>> {
+            Ok(None)
+        }
+    }
+
+    fn store(capacity: usize) -> (ResourceStore, Arc<CountingSource>) {
+        let source = Arc::new(CountingSource::default());
+        let store = ResourceStore::with_capacity(source.clone(), source.clone(), capacity);
+
+        (store, source)
+    }
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ResourceStore>();
+    }
+
+    #[test]
+    fn caches_decodes() -> R {
+        let (store, source) = store(DEFAULT_CACHE_CAPACITY);
+
+        let first = store.texture_shared(TexturePart::Eye, 1)?.unwrap();
+        let second = store.texture_shared(TexturePart::Eye, 1)?.unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // Renderers only see a `dyn TextureSource`, and still get the cached copy.
+        let textures: &dyn TextureSource = &store;
+        let third = textures.texture_shared(TexturePart::Eye, 1)?.unwrap();
+        assert!(Arc::ptr_eq(&first, &third));
+
+        // Empty parts are remembered as well.
+        assert!(store.texture(TexturePart::Eye, 10)?.is_none());
+        assert!(store.texture(TexturePart::Eye, 10)?.is_none());
+        assert!(store.mesh(Shape::Nose, 3)?.is_none());
+        assert!(store.mesh(Shape::Nose, 3)?.is_none());
+
+        assert_eq!(source.decodes.load(Ordering::Relaxed), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn evicts_least_recently_used() -> R {
+        let (store, source) = store(2);
+
+        store.texture(TexturePart::Eye, 0)?;
+        store.texture(TexturePart::Eye, 1)?;
+        // Touch 0, so 1 is the oldest when 2 comes in.
+        store.texture(TexturePart::Eye, 0)?;
+        store.texture(TexturePart::Eye, 2)?;
+        assert_eq!(source.decodes.load(Ordering::Relaxed), 3);
+
+        store.texture(TexturePart::Eye, 0)?;
+        assert_eq!(source.decodes.load(Ordering::Relaxed), 3);
+        store.texture(TexturePart::Eye, 1)?;
+        assert_eq!(source.decodes.load(Ordering::Relaxed), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn zero_capacity_disables_cache() -> R {
+        let (store, source) = store(0);
+
+        store.texture(TexturePart::Mouth, 0)?;
+        store.texture(TexturePart::Mouth, 0)?;
+        assert_eq!(source.decodes.load(Ordering::Relaxed), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_nx_tier() -> R {
+        use crate::write::{ShapeFileBuilder, TextureFileBuilder};
+
+        let dir = std::env::temp_dir().join(format!("vee_store_tier_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+
+        let tier = ResourceTier::High;
+        std::fs::write(
+            dir.join(tier.shape_file_name()),
+            ShapeFileBuilder::new().build()?,
+        )?;
+        std::fs::write(
+            dir.join(tier.texture_file_name(ColorSpace::Linear)),
+            TextureFileBuilder::new().build()?,
+        )?;
+
+        let store = ResourceStore::open_nx_tier(&dir, tier);
+        let missing = ResourceStore::open_nx_tier(&dir, ResourceTier::Mid);
+        std::fs::remove_dir_all(&dir)?;
+
+        assert_eq!(store?.color_space(), ColorSpace::Linear);
+        assert!(missing.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_nx_color_space() -> R {
+        use crate::tex::ResourceTextureFormat;
+        use crate::write::{EncodedTexture, ShapeFileBuilder, TextureFileBuilder};
+
+        let dir = std::env::temp_dir().join(format!("vee_store_color_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let shape_path = dir.join(ResourceTier::Mid.shape_file_name());
+        std::fs::write(&shape_path, ShapeFileBuilder::new().build()?)?;
+
+        // The formats in the header win over the name.
+        let mut color_spaces = Vec::new();
+        for (format, name) in [
+            (ResourceTextureFormat::Rgba, "NXTextureMidSRGB.dat"),
+            (ResourceTextureFormat::Bc4, "NXTextureMidLinear.dat"),
+        ] {
+            let mut builder = TextureFileBuilder::new();
+            let texture = EncodedTexture::encode(&RgbaImage::new(4, 4), format)?;
+            builder.set_texture(TexturePart::Mole, 0, &texture)?;
+            std::fs::write(dir.join(name), builder.build()?)?;
+
+            color_spaces.push(ResourceStore::open_nx(&shape_path, dir.join(name))?.color_space());
+        }
+        std::fs::remove_dir_all(&dir)?;
+
+        assert_eq!(color_spaces, [ColorSpace::Linear, ColorSpace::Srgb]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_nx_unknown_color_space() -> R {
+        use crate::source::NxTextureFile;
+        use crate::write::{ShapeFileBuilder, TextureFileBuilder};
+
+        let dir = std::env::temp_dir().join(format!("vee_store_unknown_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let shape_path = dir.join(ResourceTier::Mid.shape_file_name());
+        std::fs::write(&shape_path, ShapeFileBuilder::new().build()?)?;
+
+        // Nothing in the header or the name tells.
+        let texture_path = dir.join("textures.dat");
+        let data = TextureFileBuilder::new().build()?;
+        std::fs::write(&texture_path, &data)?;
+
+        let store = ResourceStore::open_nx(&shape_path, &texture_path);
+        std::fs::remove_dir_all(&dir)?;
+
+        assert!(store.is_err());
+        assert!(NxTextureFile::new(data.as_slice()).is_err());
+        assert_eq!(
+            NxTextureFile::new_in(data.as_slice(), ColorSpace::Linear)?.color_space,
+            ColorSpace::Linear
+        );
+
+        Ok(())
+    }
+}
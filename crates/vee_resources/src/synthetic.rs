@@ -0,0 +1,326 @@
+//! Placeholder resource files, for testing without dumped (copyrighted) resources.
+//!
+//! [`shape_file`] and [`texture_file`] build Nx files with a procedural mesh or texture in
+//! every slot. They're structurally valid, so they go through the same parsing, model
+//! building and rendering paths as the real files, but look nothing like a character.
+//!
+//! ```
+//! use vee_resources::shape::Shape;
+//! use vee_resources::source::{ShapeSource, TextureSource};
+//! use vee_resources::synthetic;
+//! use vee_resources::tex::TexturePart;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let shapes = synthetic::shape_file();
+//! let textures = synthetic::texture_file();
+//!
+//! assert!(shapes.mesh(Shape::HairNormal, 131)?.is_some());
+//! assert!(textures.texture(TexturePart::Eye, 61)?.is_some());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::color::ColorSpace;
+use crate::shape::{
+    ResourceShape, ResourceShapeFacelineTransform, ResourceShapeHairTransform, Shape, ShapeMesh,
+};
+use crate::source::{NxShapeFile, NxTextureFile, ResourceTier};
+use crate::tex::{ResourceTexture, ResourceTextureFormat, TexturePart};
+use crate::write::{EncodedTexture, ShapeFileBuilder, TextureFileBuilder};
+use image::{Rgba, RgbaImage};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Placeholders are built often and compress well anyway.
+const FAST_COMPRESSION_LEVEL: u8 = 1;
+
+/// Builds a shape file with a box in every mesh slot.
+///
+/// # Panics
+/// - Never, the placeholders are always valid
+#[must_use]
+pub fn shape_data() -> Vec<u8> {
+    let mut builder = ShapeFileBuilder::new();
+    builder.set_compression_level(FAST_COMPRESSION_LEVEL);
+    let header = ResourceShape::default();
+
+    for shape in Shape::MESHES {
+        let (center, size) = shape_bounds(shape);
+
+        for index in 0..header.elements(shape).len() {
+            // Types of a part get slightly bigger, so they can be told apart.
+            let scale = 1.0 + (index % 5) as f32 * 0.05;
+            let mesh = cuboid(center, size.map(|x| x * scale));
+
+            builder
+                .set_mesh(shape, index, &mesh)
+                .expect("Placeholder mesh should be valid");
+        }
+    }
+
+    for index in 0..header.hair_transform.len() {
+        builder
+            .set_hair_transform(
+                index,
+                ResourceShapeHairTransform {
+                    front_translate: [0.0, 4.0, 18.0],
+                    side_translate: [20.0, 0.0, 0.0],
+                    side_rotate: [0.0, 0.0, -90.0],
+                    top_translate: [0.0, 12.0, 0.0],
+                    ..Default::default()
+                },
+            )
+            .expect("Hair transform should be in range");
+    }
+
+    for index in 0..12 {
+        builder
+            .set_face_line_transform(
+                index,
+                ResourceShapeFacelineTransform {
+                    hair_translate: [0.0, 14.0, 0.0],
+                    nose_translate: [0.0, 6.0, 16.0],
+                    beard_translate: [0.0, -8.0, 4.0],
+                },
+            )
+            .expect("Faceline transform should be in range");
+    }
+
+    builder.build().expect("Placeholder shape file should fit")
+}
+
+/// Builds a texture file with a pattern in every texture slot.
+///
+/// # Panics
+/// - Never, the placeholders are always valid
+#[must_use]
+pub fn texture_data() -> Vec<u8> {
+    let mut builder = TextureFileBuilder::new();
+    builder.set_compression_level(FAST_COMPRESSION_LEVEL);
+    let header = ResourceTexture::default();
+
+    for part in TexturePart::ALL {
+        let (format, width, height) = texture_layout(part);
+
+        for index in 0..header.part(part).len() {
+            let texture = EncodedTexture::encode_mip_chain(
+                &pattern_mip_chain(format, width, height, index),
+                format,
+            )
+            .expect("Placeholder texture should encode");
+
+            builder
+                .set_texture(part, index, &texture)
+                .expect("Placeholder texture should be valid");
+        }
+    }
+
+    builder
+        .build()
+        .expect("Placeholder texture file should fit")
+}
+
+/// Reads [`shape_data`].
+///
+/// # Panics
+/// - Never, the placeholders are always valid
+#[must_use]
+pub fn shape_file() -> NxShapeFile {
+    NxShapeFile::new(shape_data()).expect("Placeholder shape file should be readable")
+}
+
+/// Reads [`texture_data`].
+///
+/// # Panics
+/// - Never, the placeholders are always valid
+#[must_use]
+pub fn texture_file() -> NxTextureFile {
+    NxTextureFile::new(texture_data()).expect("Placeholder texture file should be readable")
+}
+
+/// Writes placeholders as `ShapeMid.dat` and `NXTextureMidLinear.dat` in `dir`, for code that
+/// opens resources by path. Returns the shape and texture file paths.
+///
+/// The textures are uncompressed, like those of the official `Linear` files.
+///
+/// # Errors
+/// - Either file can't be written
+pub fn write_files(dir: impl AsRef<Path>) -> io::Result<(PathBuf, PathBuf)> {
+    let shape_path = dir.as_ref().join(ResourceTier::Mid.shape_file_name());
+    let texture_path = dir
+        .as_ref()
+        .join(ResourceTier::Mid.texture_file_name(ColorSpace::Linear));
+
+    std::fs::write(&shape_path, shape_data())?;
+    std::fs::write(&texture_path, texture_data())?;
+
+    Ok((shape_path, texture_path))
+}
+
+/// Rough center and size of a part, in the same space as the real meshes.
+fn shape_bounds(shape: Shape) -> ([f32; 3], [f32; 3]) {
+    match shape {
+        Shape::Beard => ([0.0, -10.0, 4.0], [24.0, 10.0, 14.0]),
+        Shape::FaceLine => ([0.0, 8.0, 0.0], [36.0, 40.0, 32.0]),
+        Shape::Mask => ([0.0, 10.0, 16.5], [30.0, 30.0, 0.5]),
+        Shape::HatNormal | Shape::HatCap => ([0.0, 28.0, 0.0], [40.0, 16.0, 36.0]),
+        Shape::ForeheadNormal | Shape::ForeheadCap => ([0.0, 22.0, 8.0], [34.0, 10.0, 20.0]),
+        Shape::HairNormal | Shape::HairCap => ([0.0, 24.0, -2.0], [40.0, 20.0, 38.0]),
+        Shape::Glasses => ([0.0, 12.0, 18.0], [32.0, 8.0, 1.0]),
+        Shape::Nose => ([0.0, 6.0, 18.0], [4.0, 6.0, 4.0]),
+        Shape::NoseLine => ([0.0, 6.0, 19.0], [5.0, 7.0, 1.0]),
+        Shape::HairTransform | Shape::FaceLineTransform => ([0.0; 3], [0.0; 3]),
+    }
+}
+
+/// A box with flat normals, each face mapped to the whole texture.
+fn cuboid(center: [f32; 3], size: [f32; 3]) -> ShapeMesh {
+    // Normal, then the two axes across the face.
+    const FACES: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+        ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ];
+    const CORNERS: [[f32; 2]; 4] = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+
+    let mut positions = Vec::with_capacity(24);
+    let mut normals = Vec::with_capacity(24);
+    let mut uvs = Vec::with_capacity(24);
+    let mut indices = Vec::with_capacity(36);
+
+    for (face, (normal, u, v)) in (0..).zip(FACES) {
+        for [s, t] in CORNERS {
+            positions.push(std::array::from_fn(|i| {
+                let offset = normal[i] + u[i] * (s * 2.0 - 1.0) + v[i] * (t * 2.0 - 1.0);
+                center[i] + size[i] / 2.0 * offset
+            }));
+            normals.push(normal);
+            uvs.push([s, 1.0 - t]);
+        }
+
+        let first = face * 4;
+        indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+
+    let tangents = normals
+        .iter()
+        .map(|&[x, y, z]| [z, x, y, 1.0].map(|n| (n * 127.0) as i8))
+        .collect();
+
+    ShapeMesh {
+        tangents: Some(tangents),
+        // Everything in the first color, like most of a hair mesh.
+        color_params: Some(vec![[255, 0, 0, 0]; positions.len()]),
+        ..ShapeMesh::from_f32(&positions, Some(&normals), Some(&uvs), indices)
+    }
+}
+
+/// Format and size of the textures of `part`.
+fn texture_layout(part: TexturePart) -> (ResourceTextureFormat, u32, u32) {
+    use ResourceTextureFormat as Rtf;
+
+    match part {
+        TexturePart::Hat | TexturePart::Eye | TexturePart::Mouth => (Rtf::Rgba, 64, 64),
+        TexturePart::Glass => (Rtf::Rgba, 64, 32),
+        TexturePart::Eyebrow | TexturePart::Beard => (Rtf::R, 64, 64),
+        TexturePart::Wrinkle | TexturePart::Makeup => (Rtf::R, 64, 64),
+        TexturePart::Mustache | TexturePart::Noseline => (Rtf::R, 64, 32),
+        TexturePart::Mole => (Rtf::R, 32, 32),
+    }
+}
+
+/// An ellipse that shrinks with `index`, down to the last mip level.
+/// `Rgba` textures get a ring in each of the red, green and blue channels.
+fn pattern_mip_chain(
+    format: ResourceTextureFormat,
+    width: u32,
+    height: u32,
+    index: usize,
+) -> Vec<RgbaImage> {
+    let radius = 0.9 - (index % 8) as f32 * 0.05;
+    let levels = width.max(height).ilog2() + 1;
+
+    (0..levels)
+        .map(|level| {
+            let (width, height) = ((width >> level).max(1), (height >> level).max(1));
+
+            RgbaImage::from_fn(width, height, |x, y| {
+                // Distance from the center, where the edges are at `1.0`.
+                let dx = (x as f32 + 0.5) / width as f32 * 2.0 - 1.0;
+                let dy = (y as f32 + 0.5) / height as f32 * 2.0 - 1.0;
+                let distance = (dx * dx + dy * dy).sqrt() / radius;
+
+                let inside = |from: f32, to: f32| {
+                    if (from..to).contains(&distance) {
+                        255
+                    } else {
+                        0
+                    }
+                };
+
+                match format {
+                    // Laid out like `TextureElement::get_image` returns them, red third.
+                    ResourceTextureFormat::Rgba => Rgba([
+                        inside(0.0, 0.3),
+                        inside(0.3, 0.6),
+                        inside(0.6, 1.0),
+                        inside(0.0, 1.0),
+                    ]),
+                    _ => {
+                        let value = inside(0.0, 1.0);
+                        Rgba([value, value, value, 255])
+                    }
+                }
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{ShapeSource, TextureSource};
+    use std::error::Error;
+
+    type R = Result<(), Box<dyn Error>>;
+
+    #[test]
+    fn every_slot() -> R {
+        let shapes = shape_file();
+        let textures = texture_file();
+
+        for shape in Shape::MESHES {
+            assert!(shapes.shape_count(shape) > 0);
+
+            for index in 0..shapes.shape_count(shape) {
+                let mesh = shapes.mesh(shape, index)?.ok_or("Mesh is empty")?;
+                assert_eq!(mesh.positions.len(), 24);
+                assert_eq!(mesh.indices.len(), 36);
+            }
+        }
+
+        for part in TexturePart::ALL {
+            for index in 0..textures.texture_count(part) {
+                let (_, width, height) = texture_layout(part);
+                let chain = textures.header.part(part)[index]
+                    .get_mip_chain(&textures.data)?
+                    .ok_or("Texture is empty")?;
+
+                assert_eq!(chain[0].dimensions(), (width, height));
+                assert_eq!(chain.last().map(RgbaImage::dimensions), Some((1, 1)));
+            }
+        }
+
+        assert!(shapes.face_line_transform(11)?.is_some());
+        assert!(
+            shapes
+                .hair_transform(131)?
+                .is_some_and(|t| t.top_translate[1] > 0.0)
+        );
+
+        Ok(())
+    }
+}
@@ -1,29 +1,34 @@
 //! Parsing texture data
 
+use crate::color::ColorSpace;
 use crate::inflate_bytes;
 use crate::shape::ResourceCommonAttribute;
-use binrw::BinRead;
-use image::{ImageBuffer, Rgba, RgbaImage};
+use binrw::{BinRead, BinWrite};
+use image::RgbaImage;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use std::error::Error;
-use tegra_swizzle::{block_height_mip0, div_round_up, swizzle::deswizzle_block_linear};
+use std::str::FromStr;
+use tegra_swizzle::surface::{BlockDim, deswizzle_surface};
+use tegra_swizzle::swizzle::{deswizzle_block_linear, deswizzled_mip_size, swizzled_mip_size};
+use tegra_swizzle::{block_height_mip0, div_round_up};
+use thiserror::Error;
 
 /// Specifies information about the texture.
 #[allow(unused)]
-#[derive(BinRead, Debug, Clone, Copy)]
+#[derive(BinRead, BinWrite, Default, Debug, Clone, Copy)]
 pub struct ResourceTextureAttribute {
-    alignment: u32,
+    pub(crate) alignment: u32,
     pub width: u16,
     pub height: u16,
     pub format: ResourceTextureFormat,
-    mip_count: u8,
-    tile_mode: u8,
-    pad: [u8; 1],
+    pub mip_count: u8,
+    pub(crate) tile_mode: u8,
+    pub(crate) pad: [u8; 1],
 }
 
 /// All the data required to read a texture from the shape file.
 /// Essentially a 'pointer' to the data.
-#[derive(BinRead, Debug, Clone, Copy)]
+#[derive(BinRead, BinWrite, Default, Debug, Clone, Copy)]
 pub struct TextureElement {
     pub common: ResourceCommonAttribute,
     pub texture: ResourceTextureAttribute,
@@ -38,11 +43,26 @@ pub struct TextureElement {
 ///
 /// Normally, these are decompressed _on the GPU_, but CPU implementations are here for convenience.
 /// Especially, `Astc` is a poorly supported format on desktops, so a CPU "polyfill" is required.
-#[derive(IntoPrimitive, TryFromPrimitive, Debug, Clone, Copy, BinRead)]
-#[br(repr = u8)]
+#[derive(
+    IntoPrimitive,
+    TryFromPrimitive,
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BinRead,
+    BinWrite,
+)]
+#[brw(repr = u8)]
 #[repr(u8)]
 pub enum ResourceTextureFormat {
-    R = 0,       // R8Unorm (Ffl Name)
+    #[default]
+    R = 0, // R8Unorm (Ffl Name)
     Rg = 1,      // R8B8Unorm
     Rgba = 2,    // R8B8G8A8Unorm
     Bc4 = 3,     // Bc4Unorm (Compressed R)
@@ -51,7 +71,147 @@ pub enum ResourceTextureFormat {
     Astc4x4 = 6, // Astc4x4Unorm (Compressed Rgba)
 }
 
+/// Expands uncompressed `R`, `Rg` or `Rgba` pixels into the same packed layout
+/// the compressed formats decode to. Returns `None` for compressed formats.
+pub(crate) fn expand_uncompressed(format: ResourceTextureFormat, data: &[u8]) -> Option<Vec<u32>> {
+    use ResourceTextureFormat as Rtf;
+
+    Some(match format {
+        Rtf::R => data
+            .iter()
+            .map(|x| u32::from_le_bytes([0, 0, *x, 255]))
+            .collect(),
+        Rtf::Rg => data
+            .chunks_exact(2)
+            .map(|rg| u32::from_le_bytes([0, rg[1], rg[0], 255]))
+            .collect(),
+        Rtf::Rgba => data
+            .chunks_exact(4)
+            .map(|rgba| u32::from_le_bytes([rgba[2], rgba[1], rgba[0], rgba[3]]))
+            .collect(),
+        Rtf::Bc4 | Rtf::Bc5 | Rtf::Bc7 | Rtf::Astc4x4 => return None,
+    })
+}
+
+/// Errors from decoding a texture.
+#[derive(Error, Debug)]
+pub enum TextureError {
+    #[error("{0:?} textures can't be decoded here.")]
+    Unsupported(ResourceTextureFormat),
+    #[error("Texture data is empty.")]
+    Empty,
+    #[error("Texture data is {actual} bytes, but its dimensions need {expected}.")]
+    WrongSize { expected: usize, actual: usize },
+    #[error("Decoding {format:?} texture failed: {reason}")]
+    Decode {
+        format: ResourceTextureFormat,
+        reason: &'static str,
+    },
+    #[error("The header has no color textures to tell the color space by.")]
+    UnknownColorSpace,
+}
+
+/// Options for decoding a texture on the CPU.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextureDecodeOptions {
+    /// Spreads one and two channel textures (`R`, `Rg`, `Bc4`, `Bc5`) over every channel,
+    /// so that they look right in an image viewer. Renderers want this off, as
+    /// the modulation modes read the raw channels.
+    pub normalize: bool,
+}
+
+/// Spreads the channels of one and two channel textures out, see
+/// [`TextureDecodeOptions::normalize`].
+fn normalize_pixels(format: ResourceTextureFormat, pixels: &mut [u32]) {
+    use ResourceTextureFormat as Rtf;
+
+    match format {
+        // R to Rgba
+        Rtf::R | Rtf::Bc4 => {
+            for x in pixels {
+                let [_, _, w, _] = x.to_le_bytes();
+                *x = u32::from_le_bytes([w, w, w, w]);
+            }
+        }
+        // Rg to Rgba
+        Rtf::Rg | Rtf::Bc5 => {
+            for x in pixels {
+                let [_, a, w, _] = x.to_le_bytes();
+                *x = u32::from_le_bytes([w, w, w, a]);
+            }
+        }
+        Rtf::Rgba | Rtf::Bc7 | Rtf::Astc4x4 => {}
+    }
+}
+
+impl ResourceTextureFormat {
+    /// Whether the format is block compressed.
+    #[must_use]
+    pub const fn is_compressed(self) -> bool {
+        matches!(self, Self::Bc4 | Self::Bc5 | Self::Bc7 | Self::Astc4x4)
+    }
+
+    /// Width (and height) of a block in pixels, and its size in bytes.
+    #[must_use]
+    pub const fn block_layout(self) -> (u32, u32) {
+        match self {
+            Self::R => (1, 1),
+            Self::Rg => (1, 2),
+            Self::Rgba => (1, 4),
+            Self::Bc4 => (4, 8),
+            Self::Bc5 | Self::Bc7 | Self::Astc4x4 => (4, 16),
+        }
+    }
+}
+
+/// A texture still in its block compressed format, de-swizzled and ready for
+/// a GPU that can sample it directly.
+#[derive(Debug, Clone)]
+pub struct CompressedTexture {
+    pub format: ResourceTextureFormat,
+    pub width: u32,
+    pub height: u32,
+    /// Blocks of the largest mip level, row by row.
+    pub data: Vec<u8>,
+}
+
+impl CompressedTexture {
+    /// Number of blocks across and down the texture.
+    #[must_use]
+    pub fn blocks(&self) -> (u32, u32) {
+        let (block_size, _) = self.format.block_layout();
+
+        (
+            div_round_up(self.width, block_size),
+            div_round_up(self.height, block_size),
+        )
+    }
+
+    /// Decodes the texture on the CPU, for when the GPU can't.
+    /// Pixels are in the same order as [`TextureElement::get_image`].
+    ///
+    /// # Errors
+    /// - Texture decompression fails, or the data is the wrong size
+    pub fn decode(&self) -> Result<RgbaImage, TextureError> {
+        let pixels = decode_level(
+            self.format,
+            &self.data,
+            self.width,
+            self.height,
+            TextureDecodeOptions::default(),
+        )?;
+
+        image_from_pixels(self.width, self.height, pixels)
+    }
+}
+
 impl TextureElement {
+    /// Number of mip levels, including the full size one.
+    #[must_use]
+    pub fn mip_count(&self) -> u32 {
+        u32::from(self.texture.mip_count.max(1))
+    }
+
     /// Gets the raw bytes of a texture. Takes an argument of the texture resource file.
     /// On Nx, the textures are pre-"swizzled" for cache locality reasons,
     /// so they need to be de-swizzled.
@@ -60,35 +220,31 @@ impl TextureElement {
     /// - Encounters texture data that isn't Zlib deflated
     /// - Deswizzling texture data fails
     pub fn get_texture_bytes(&self, texture_bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
-        use ResourceTextureFormat as Rtf;
-
-        let start: usize = self.common.offset as usize;
-        let end: usize = self.common.offset as usize + self.common.size_compressed as usize;
-
-        let range = start..end;
-
-        let tex_data = inflate_bytes(&texture_bytes[range])?;
+        let tex_data = self.inflate(texture_bytes)?;
 
         let needs_swizzling = self.texture.tile_mode == 0;
 
         let tex_data = if needs_swizzling {
-            let block_size = match self.texture.format {
-                Rtf::R | Rtf::Rg | Rtf::Rgba => 1,
-                Rtf::Bc4 | Rtf::Bc5 | Rtf::Bc7 | Rtf::Astc4x4 => 4,
-            };
+            let (block_size, bytes_per_pixel) = self.texture.format.block_layout();
 
-            let bytes_per_pixel = match self.texture.format {
-                Rtf::R | Rtf::Rg | Rtf::Rgba => 1,
-                Rtf::Bc4 => 8,
-                Rtf::Bc5 | Rtf::Bc7 | Rtf::Astc4x4 => 16,
-            };
+            let blocks_x = div_round_up(self.texture.width.into(), block_size);
+            let blocks_y = div_round_up(self.texture.height.into(), block_size);
+            let block_height = block_height_mip0(blocks_y);
 
-            let height = self.texture.height.into();
-            let block_height = block_height_mip0(div_round_up(height, block_size));
+            // Checked here, as the output is allocated from the header's size before
+            // the input is looked at.
+            let expected = swizzled_mip_size(blocks_x, blocks_y, 1, block_height, bytes_per_pixel);
+            if tex_data.len() < expected {
+                return Err(TextureError::WrongSize {
+                    expected,
+                    actual: tex_data.len(),
+                }
+                .into());
+            }
 
             deswizzle_block_linear(
-                div_round_up(self.texture.width.into(), block_size),
-                div_round_up(self.texture.height.into(), block_size),
+                blocks_x,
+                blocks_y,
                 1,
                 &tex_data,
                 block_height,
@@ -101,140 +257,485 @@ impl TextureElement {
         Ok(tex_data)
     }
 
-    /// Gets the raw bytes of a texture. Takes an argument of the resource file.
-    /// This function will decompress the image on the CPU.
+    /// Gets a block compressed texture without decoding it.
+    /// Takes an argument of the texture resource file.
+    /// Returns `None` for an empty texture, or one in an uncompressed format.
+    ///
+    /// # Errors
+    /// - Encounters texture data that isn't Zlib deflated
+    /// - Deswizzling texture data fails
+    /// - The texture data is too small for its dimensions
+    pub fn get_compressed(
+        &self,
+        texture_bytes: &[u8],
+    ) -> Result<Option<CompressedTexture>, Box<dyn Error>> {
+        if self.texture.width == 0
+            || self.texture.height == 0
+            || !self.texture.format.is_compressed()
+        {
+            return Ok(None);
+        }
+
+        let mut texture = CompressedTexture {
+            format: self.texture.format,
+            width: self.texture.width.into(),
+            height: self.texture.height.into(),
+            data: self.get_texture_bytes(texture_bytes)?,
+        };
+
+        // Unswizzled textures may still have their mips on the end.
+        let (blocks_x, blocks_y) = texture.blocks();
+        let (_, bytes_per_block) = texture.format.block_layout();
+        let size = blocks_x as usize * blocks_y as usize * bytes_per_block as usize;
+
+        if texture.data.len() < size {
+            return Err(TextureError::WrongSize {
+                expected: size,
+                actual: texture.data.len(),
+            }
+            .into());
+        }
+        texture.data.truncate(size);
+
+        Ok(Some(texture))
+    }
+
+    /// Gets the raw bytes of every mip level of a texture, largest first.
+    /// Takes an argument of the texture resource file.
+    /// Like [`Self::get_texture_bytes`], each level is de-swizzled.
     ///
     /// # Errors
     /// - Encounters texture data that isn't Zlib deflated
     /// - Deswizzling texture data fails
-    /// - Texture decompression fails
-    /// # Panics
-    /// - If texture data is empty
+    /// - The texture data is too small for its mip levels
+    pub fn get_mip_texture_bytes(
+        &self,
+        texture_bytes: &[u8],
+    ) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        let tex_data = self.inflate(texture_bytes)?;
+
+        let (block_size, bytes_per_pixel) = self.texture.format.block_layout();
+        let (width, height) = (self.texture.width.into(), self.texture.height.into());
+        let mip_count = self.mip_count();
+
+        let needs_swizzling = self.texture.tile_mode == 0;
+
+        let tex_data = if needs_swizzling {
+            let block_dim = if block_size == 1 {
+                BlockDim::uncompressed()
+            } else {
+                BlockDim::block_4x4()
+            };
+
+            // Levels come out one after another, each tightly packed.
+            deswizzle_surface(
+                width,
+                height,
+                1,
+                &tex_data,
+                block_dim,
+                None,
+                bytes_per_pixel,
+                mip_count,
+                1,
+            )?
+        } else {
+            tex_data
+        };
+
+        let mut levels = Vec::with_capacity(mip_count as usize);
+        let mut start = 0;
+
+        for level in 0..mip_count {
+            let (mip_width, mip_height) = mip_size(width, height, level);
+            let end = start
+                + deswizzled_mip_size(
+                    div_round_up(mip_width, block_size),
+                    div_round_up(mip_height, block_size),
+                    1,
+                    bytes_per_pixel,
+                );
+
+            let level_data = tex_data.get(start..end).ok_or(TextureError::WrongSize {
+                expected: end,
+                actual: tex_data.len(),
+            })?;
+            levels.push(level_data.to_vec());
+
+            start = end;
+        }
+
+        Ok(levels)
+    }
+
+    fn inflate(&self, texture_bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let start: usize = self.common.offset as usize;
+        let end: usize = self.common.offset as usize + self.common.size_compressed as usize;
+
+        let range = start..end;
+
+        Ok(inflate_bytes(texture_bytes.get(range).ok_or(
+            "Texture is out of bounds of the resource file.",
+        )?)?)
+    }
+
+    /// Gets the raw bytes of a texture. Takes an argument of the resource file.
+    /// This function will decompress the image on the CPU.
+    ///
+    /// # Errors
+    /// See [`Self::get_uncompressed_bytes_with`].
     pub fn get_uncompressed_bytes(&self, file: &[u8]) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
-        let normalize_textures = false;
+        self.get_uncompressed_bytes_with(file, TextureDecodeOptions::default())
+    }
 
+    /// Gets the raw bytes of a texture, decoded with `options`.
+    /// Takes an argument of the resource file.
+    /// This function will decompress the image on the CPU.
+    ///
+    /// # Errors
+    /// - Encounters texture data that isn't Zlib deflated
+    /// - Deswizzling texture data fails
+    /// - Texture decompression fails, or the data is empty ([`TextureError`])
+    pub fn get_uncompressed_bytes_with(
+        &self,
+        file: &[u8],
+        options: TextureDecodeOptions,
+    ) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
         if self.texture.width == 0 || self.texture.height == 0 {
             return Ok(None);
         }
 
         let tex_data = self.get_texture_bytes(file)?;
-        assert!(!tex_data.is_empty());
-
-        let mut tex_data_decoded =
-            vec![0; (u32::from(self.texture.width) * u32::from(self.texture.height)) as usize];
-        match self.texture.format {
-            ResourceTextureFormat::Bc7 => {
-                texture2ddecoder::decode_bc7(
-                    &tex_data,
-                    self.texture.width.into(),
-                    self.texture.height.into(),
-                    &mut tex_data_decoded,
-                )?;
-            }
-            ResourceTextureFormat::Bc4 => {
-                texture2ddecoder::decode_bc4(
-                    &tex_data,
-                    self.texture.width.into(),
-                    self.texture.height.into(),
-                    &mut tex_data_decoded,
-                )?;
-
-                // Convert R to Rgba
-                if normalize_textures {
-                    tex_data_decoded = tex_data_decoded
-                        .iter()
-                        .map(|x| {
-                            let [_, _, w, _] = x.to_le_bytes();
-                            u32::from_le_bytes([w, w, w, w])
-                        })
-                        .collect();
-                }
-            }
-            ResourceTextureFormat::Bc5 => {
-                texture2ddecoder::decode_bc5(
-                    &tex_data,
-                    self.texture.width.into(),
-                    self.texture.height.into(),
-                    &mut tex_data_decoded,
-                )?;
-
-                // Convert Rb to Rgba
-                if normalize_textures {
-                    tex_data_decoded = tex_data_decoded
-                        .iter()
-                        .map(|x| {
-                            let [w, a, _, _] = x.to_le_bytes();
-                            u32::from_le_bytes([w, w, w, a])
-                        })
-                        .collect();
-                }
-            }
-            ResourceTextureFormat::R => {
-                tex_data_decoded = tex_data
-                    .iter()
-                    .map(|x| u32::from_le_bytes([*x, *x, *x, 255]))
-                    .collect();
-            }
-            ResourceTextureFormat::Astc4x4 => {
-                texture2ddecoder::decode_astc_4_4(
-                    &tex_data,
-                    self.texture.width.into(),
-                    self.texture.height.into(),
-                    &mut tex_data_decoded,
-                )?;
-            }
-            e => unimplemented!("{e:?} is not decoded yet"),
-        }
-
-        // U32 to 4x U8
-        let tex_data_decoded: Vec<u8> = tex_data_decoded
-            .into_iter()
-            .flat_map(<u32>::to_le_bytes)
-            // .flat_map(|x| {
-            //     let [b, g, r, a] = x.to_le_bytes();
-            //     [r, g, b, a]
-            // })
-            .collect();
 
-        Ok(Some(tex_data_decoded))
+        Ok(Some(decode_level(
+            self.texture.format,
+            &tex_data,
+            self.texture.width.into(),
+            self.texture.height.into(),
+            options,
+        )?))
     }
 
     /// Creates an Image. Takes an argument of the resource file.
+    ///
+    /// # Errors
+    /// See [`Self::get_image_with`].
+    pub fn get_image(&self, bytes: &[u8]) -> Result<Option<RgbaImage>, Box<dyn Error>> {
+        self.get_image_with(bytes, TextureDecodeOptions::default())
+    }
+
+    /// Creates an Image, decoded with `options`. Takes an argument of the resource file.
+    ///
     /// # Errors
     /// - Encounters texture data that isn't Zlib deflated
     /// - Deswizzling texture data fails
-    /// - Texture decompression fails
-    /// # Panics
-    /// - If texture data is empty
-    /// - If calculated image container is too small
-    pub fn get_image(&self, bytes: &[u8]) -> Result<Option<RgbaImage>, Box<dyn Error>> {
-        let bytes = match self.get_uncompressed_bytes(bytes) {
-            Ok(Some(bytes)) => bytes,
-            Ok(None) => return Ok(None),
-            Err(e) => return Err(e),
+    /// - Texture decompression fails, or the data is the wrong size ([`TextureError`])
+    pub fn get_image_with(
+        &self,
+        bytes: &[u8],
+        options: TextureDecodeOptions,
+    ) -> Result<Option<RgbaImage>, Box<dyn Error>> {
+        let Some(bytes) = self.get_uncompressed_bytes_with(bytes, options)? else {
+            return Ok(None);
         };
 
         // Something in this process is making `Bgra` images. Wah.
-        // let bytes = bytes
-        //     .chunks_exact(4)
-        //     .flat_map(|v| {
-        //         let [b, g, r, a] = v else { panic!() }; // Should be fine.
-        //         [*r, *g, *b, *a]
-        //     })
-        //     .collect();
+        Ok(Some(image_from_pixels(
+            self.texture.width.into(),
+            self.texture.height.into(),
+            bytes,
+        )?))
+    }
+
+    /// Creates an Image with its channels in RGBA order, for handing to code outside the
+    /// renderer. Takes an argument of the resource file. Returns `None` for an empty texture.
+    ///
+    /// # Errors
+    /// See [`Self::get_image_with`].
+    pub fn get_rgba_image(&self, bytes: &[u8]) -> Result<Option<RgbaImage>, Box<dyn Error>> {
+        let Some(mut image) = self.get_image(bytes)? else {
+            return Ok(None);
+        };
+
+        // `get_image` gives BGRA pixels, which is what the renderer uploads.
+        for pixel in image.pixels_mut() {
+            pixel.0.swap(0, 2);
+        }
+
+        Ok(Some(image))
+    }
+
+    /// Creates an Image for every mip level of the texture, largest first.
+    /// Takes an argument of the resource file. Returns `None` for an empty texture.
+    ///
+    /// # Errors
+    /// See [`Self::get_mip_chain_with`].
+    pub fn get_mip_chain(&self, bytes: &[u8]) -> Result<Option<Vec<RgbaImage>>, Box<dyn Error>> {
+        self.get_mip_chain_with(bytes, TextureDecodeOptions::default())
+    }
+
+    /// Creates an Image for every mip level of the texture, decoded with `options`.
+    /// Takes an argument of the resource file. Returns `None` for an empty texture.
+    ///
+    /// # Errors
+    /// - Encounters texture data that isn't Zlib deflated
+    /// - Deswizzling texture data fails
+    /// - Texture decompression fails, or the data is the wrong size ([`TextureError`])
+    pub fn get_mip_chain_with(
+        &self,
+        bytes: &[u8],
+        options: TextureDecodeOptions,
+    ) -> Result<Option<Vec<RgbaImage>>, Box<dyn Error>> {
+        if self.texture.width == 0 || self.texture.height == 0 {
+            return Ok(None);
+        }
+
+        let (width, height) = (self.texture.width.into(), self.texture.height.into());
 
-        let img: ImageBuffer<Rgba<u8>, Vec<u8>> =
-            RgbaImage::from_raw(self.texture.width.into(), self.texture.height.into(), bytes)
-                .unwrap();
+        let images = self
+            .get_mip_texture_bytes(bytes)?
+            .iter()
+            .zip(0..)
+            .map(|(tex_data, level)| {
+                let (width, height) = mip_size(width, height, level);
+                let pixels = decode_level(self.texture.format, tex_data, width, height, options)?;
 
-        Ok(Some(img))
+                image_from_pixels(width, height, pixels)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Some(images))
+    }
+}
+
+/// Decodes one level of de-swizzled texture data into packed pixels.
+fn decode_level(
+    format: ResourceTextureFormat,
+    tex_data: &[u8],
+    width: u32,
+    height: u32,
+    options: TextureDecodeOptions,
+) -> Result<Vec<u8>, TextureError> {
+    use ResourceTextureFormat as Rtf;
+
+    if tex_data.is_empty() {
+        return Err(TextureError::Empty);
+    }
+
+    let pixel_count = (width * height) as usize;
+
+    let mut tex_data_decoded = match expand_uncompressed(format, tex_data) {
+        Some(pixels) if pixels.len() < pixel_count => {
+            let (_, bytes_per_pixel) = format.block_layout();
+
+            return Err(TextureError::WrongSize {
+                expected: pixel_count * bytes_per_pixel as usize,
+                actual: tex_data.len(),
+            });
+        }
+        Some(mut pixels) => {
+            pixels.truncate(pixel_count);
+            pixels
+        }
+        None => {
+            let decode = match format {
+                Rtf::Bc4 => texture2ddecoder::decode_bc4,
+                Rtf::Bc5 => texture2ddecoder::decode_bc5,
+                Rtf::Bc7 => texture2ddecoder::decode_bc7,
+                Rtf::Astc4x4 => texture2ddecoder::decode_astc_4_4,
+                format => return Err(TextureError::Unsupported(format)),
+            };
+
+            // Checked before allocating, as the dimensions come from the header.
+            let (block_size, bytes_per_block) = format.block_layout();
+            let expected = (div_round_up(width, block_size) * div_round_up(height, block_size))
+                as usize
+                * bytes_per_block as usize;
+            if tex_data.len() < expected {
+                return Err(TextureError::WrongSize {
+                    expected,
+                    actual: tex_data.len(),
+                });
+            }
+
+            let mut pixels = vec![0; pixel_count];
+            decode(tex_data, width as usize, height as usize, &mut pixels)
+                .map_err(|reason| TextureError::Decode { format, reason })?;
+            pixels
+        }
+    };
+
+    if options.normalize {
+        normalize_pixels(format, &mut tex_data_decoded);
+    }
+
+    // U32 to 4x U8
+    Ok(tex_data_decoded
+        .into_iter()
+        .flat_map(<u32>::to_le_bytes)
+        .collect())
+}
+
+/// Encodes an image as one level of de-swizzled texture data, the inverse of [`decode_level`].
+/// `image` is laid out like [`TextureElement::get_image`] returns it, with red in the third
+/// channel.
+///
+/// `Bc4` and `Bc5` are encoded from the block's lowest and highest values.
+/// `Bc7` and `Astc4x4` can't be encoded yet.
+pub(crate) fn encode_level(
+    format: ResourceTextureFormat,
+    image: &RgbaImage,
+) -> Result<Vec<u8>, TextureError> {
+    use ResourceTextureFormat as Rtf;
+
+    let pixels = image.pixels().map(|pixel| pixel.0);
+
+    Ok(match format {
+        Rtf::R => pixels.map(|[_, _, r, _]| r).collect(),
+        Rtf::Rg => pixels.flat_map(|[_, g, r, _]| [r, g]).collect(),
+        Rtf::Rgba => pixels.flat_map(|[b, g, r, a]| [r, g, b, a]).collect(),
+        Rtf::Bc4 => encode_bc4_blocks(image, &[2]),
+        Rtf::Bc5 => encode_bc4_blocks(image, &[2, 1]),
+        Rtf::Bc7 | Rtf::Astc4x4 => return Err(TextureError::Unsupported(format)),
+    })
+}
+
+/// Encodes every 4x4 block of `image`, with a `Bc4` block for each of `channels`.
+fn encode_bc4_blocks(image: &RgbaImage, channels: &[usize]) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let mut data = Vec::new();
+
+    for block_y in 0..div_round_up(height, 4) {
+        for block_x in 0..div_round_up(width, 4) {
+            for &channel in channels {
+                // Blocks past the edge of the image repeat its last row and column.
+                let values = std::array::from_fn(|i| {
+                    let x = (block_x * 4 + i as u32 % 4).min(width - 1);
+                    let y = (block_y * 4 + i as u32 / 4).min(height - 1);
+
+                    image.get_pixel(x, y).0[channel]
+                });
+
+                data.extend(encode_bc4_block(values));
+            }
+        }
+    }
+
+    data
+}
+
+/// Encodes 16 values as a `Bc4` block, with the highest and lowest values as endpoints.
+fn encode_bc4_block(values: [u8; 16]) -> [u8; 8] {
+    let max = values.into_iter().max().unwrap_or(0);
+    let min = values.into_iter().min().unwrap_or(0);
+
+    let mut indices = 0u64;
+    if max > min {
+        let range = u32::from(max - min);
+
+        for (i, value) in values.into_iter().enumerate() {
+            // Steps from `max` to `min`. Index 0 is `max`, 1 is `min`, and 2..=7 are in between.
+            let step = (u32::from(max - value) * 7 + range / 2) / range;
+            let index = match step {
+                0 => 0,
+                7 => 1,
+                step => step + 1,
+            };
+
+            indices |= u64::from(index) << (3 * i);
+        }
+    }
+
+    let mut block = [0; 8];
+    block[0] = max;
+    block[1] = min;
+    block[2..].copy_from_slice(&indices.to_le_bytes()[..6]);
+    block
+}
+
+/// Size of mip `level` of a `width` x `height` texture.
+pub(crate) fn mip_size(width: u32, height: u32, level: u32) -> (u32, u32) {
+    // Levels past the 32nd are all 1x1, rather than a shift overflow.
+    let shift = |size: u32| size.checked_shr(level).unwrap_or(0).max(1);
+
+    (shift(width), shift(height))
+}
+
+fn image_from_pixels(width: u32, height: u32, pixels: Vec<u8>) -> Result<RgbaImage, TextureError> {
+    let actual = pixels.len();
+
+    RgbaImage::from_raw(width, height, pixels).ok_or(TextureError::WrongSize {
+        expected: (width * height * 4) as usize,
+        actual,
+    })
+}
+
+/// Every kind of texture stored in the resource data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TexturePart {
+    Hat,
+    Eye,
+    Eyebrow,
+    Beard,
+    Wrinkle,
+    Makeup,
+    Glass,
+    Mole,
+    Mouth,
+    Mustache,
+    Noseline,
+}
+
+impl TexturePart {
+    pub const ALL: [TexturePart; 11] = [
+        TexturePart::Hat,
+        TexturePart::Eye,
+        TexturePart::Eyebrow,
+        TexturePart::Beard,
+        TexturePart::Wrinkle,
+        TexturePart::Makeup,
+        TexturePart::Glass,
+        TexturePart::Mole,
+        TexturePart::Mouth,
+        TexturePart::Mustache,
+        TexturePart::Noseline,
+    ];
+
+    /// Lowercase name of the part, as parsed by [`FromStr`].
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            TexturePart::Hat => "hat",
+            TexturePart::Eye => "eye",
+            TexturePart::Eyebrow => "eyebrow",
+            TexturePart::Beard => "beard",
+            TexturePart::Wrinkle => "wrinkle",
+            TexturePart::Makeup => "makeup",
+            TexturePart::Glass => "glass",
+            TexturePart::Mole => "mole",
+            TexturePart::Mouth => "mouth",
+            TexturePart::Mustache => "mustache",
+            TexturePart::Noseline => "noseline",
+        }
+    }
+}
+
+impl FromStr for TexturePart {
+    type Err = String;
+
+    /// Parses a part's [`TexturePart::name`], in any case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TexturePart::ALL
+            .into_iter()
+            .find(|part| part.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown texture part `{s}`."))
     }
 }
 
 /// Header of the `Texture` resource file. Contains texture data for `CharModel`s.
 #[allow(unused)]
-#[derive(BinRead, Clone, Copy)]
-#[br(little, magic = b"NFTR")]
+#[derive(BinRead, BinWrite, Clone, Copy)]
+#[brw(little, magic = b"NFTR")]
 pub struct ResourceTexture {
     ver: u32,
     file_size: u32,
@@ -253,6 +754,115 @@ pub struct ResourceTexture {
     pub noseline: [TextureElement; 18],
 }
 
+impl Default for ResourceTexture {
+    /// A header without any textures.
+    fn default() -> Self {
+        use std::array::from_fn;
+
+        ResourceTexture {
+            // Not checked by readers.
+            ver: 0,
+            file_size: 0,
+            max_size: [0; 11],
+            max_alignment: [0; 11],
+            hat: from_fn(|_| TextureElement::default()),
+            eye: from_fn(|_| TextureElement::default()),
+            eyebrow: from_fn(|_| TextureElement::default()),
+            beard: from_fn(|_| TextureElement::default()),
+            wrinkle: from_fn(|_| TextureElement::default()),
+            makeup: from_fn(|_| TextureElement::default()),
+            glass: from_fn(|_| TextureElement::default()),
+            mole: from_fn(|_| TextureElement::default()),
+            mouth: from_fn(|_| TextureElement::default()),
+            mustache: from_fn(|_| TextureElement::default()),
+            noseline: from_fn(|_| TextureElement::default()),
+        }
+    }
+}
+
+impl ResourceTexture {
+    /// Format version from the header.
+    #[must_use]
+    pub fn version(&self) -> u32 {
+        self.ver
+    }
+
+    /// Color space of the file. NFTR headers have no field for it (nor for the tier, see
+    /// [`crate::source::ResourceTier::from_file_name`]), so it's read from the `format`
+    /// field of each [`TextureElement`]: `Linear` files keep their color textures
+    /// uncompressed (`Rg`, `Rgba`), and `SRGB` files block compress them.
+    ///
+    /// # Errors
+    /// - [`TextureError::UnknownColorSpace`] if no non-empty texture is in one of those formats
+    pub fn color_space(&self) -> Result<ColorSpace, TextureError> {
+        use ResourceTextureFormat as Rtf;
+
+        let formats = TexturePart::ALL
+            .into_iter()
+            .flat_map(|part| self.part(part))
+            .filter(|element| element.texture.width != 0 && element.texture.height != 0)
+            .map(|element| element.texture.format);
+
+        let mut color_space = None;
+        for format in formats {
+            match format {
+                Rtf::Rg | Rtf::Rgba => return Ok(ColorSpace::Linear),
+                format if format.is_compressed() => color_space = Some(ColorSpace::Srgb),
+                _ => {}
+            }
+        }
+
+        color_space.ok_or(TextureError::UnknownColorSpace)
+    }
+
+    /// Every texture of `part`.
+    #[must_use]
+    pub fn part(&self, part: TexturePart) -> &[TextureElement] {
+        match part {
+            TexturePart::Hat => &self.hat,
+            TexturePart::Eye => &self.eye,
+            TexturePart::Eyebrow => &self.eyebrow,
+            TexturePart::Beard => &self.beard,
+            TexturePart::Wrinkle => &self.wrinkle,
+            TexturePart::Makeup => &self.makeup,
+            TexturePart::Glass => &self.glass,
+            TexturePart::Mole => &self.mole,
+            TexturePart::Mouth => &self.mouth,
+            TexturePart::Mustache => &self.mustache,
+            TexturePart::Noseline => &self.noseline,
+        }
+    }
+
+    pub(crate) fn part_mut(&mut self, part: TexturePart) -> &mut [TextureElement] {
+        match part {
+            TexturePart::Hat => &mut self.hat,
+            TexturePart::Eye => &mut self.eye,
+            TexturePart::Eyebrow => &mut self.eyebrow,
+            TexturePart::Beard => &mut self.beard,
+            TexturePart::Wrinkle => &mut self.wrinkle,
+            TexturePart::Makeup => &mut self.makeup,
+            TexturePart::Glass => &mut self.glass,
+            TexturePart::Mole => &mut self.mole,
+            TexturePart::Mouth => &mut self.mouth,
+            TexturePart::Mustache => &mut self.mustache,
+            TexturePart::Noseline => &mut self.noseline,
+        }
+    }
+
+    /// Sets the file size and per-part maximums, which a reader allocates its buffers from.
+    pub(crate) fn set_sizes(&mut self, file_size: u32) {
+        self.file_size = file_size;
+        for (index, part) in TexturePart::ALL.into_iter().enumerate() {
+            let elements = self.part(part);
+            let max_size = elements.iter().map(|e| e.common.size).max();
+            let max_alignment = elements.iter().map(|e| e.texture.alignment).max();
+
+            self.max_size[index] = max_size.unwrap_or(0);
+            self.max_alignment[index] = max_alignment.unwrap_or(0);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -272,6 +882,180 @@ mod tests {
         Ok(())
     }
 
+    /// Packs `pixels` into a fake resource file with a linear (unswizzled) texture.
+    fn linear_texture(
+        format: ResourceTextureFormat,
+        width: u16,
+        height: u16,
+        pixels: &[u8],
+    ) -> Result<(TextureElement, Vec<u8>), Box<dyn Error>> {
+        texture(format, width, height, 1, 1, pixels)
+    }
+
+    /// Packs `pixels` into a fake resource file.
+    fn texture(
+        format: ResourceTextureFormat,
+        width: u16,
+        height: u16,
+        mip_count: u8,
+        tile_mode: u8,
+        pixels: &[u8],
+    ) -> Result<(TextureElement, Vec<u8>), Box<dyn Error>> {
+        use flate2::{Compression, write::ZlibEncoder};
+        use std::io::{Cursor, Write};
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(pixels)?;
+        let file = zlib.finish()?;
+
+        let mut header = Vec::new();
+        header.extend(0u32.to_le_bytes());
+        header.extend(u32::try_from(pixels.len())?.to_le_bytes());
+        header.extend(u32::try_from(file.len())?.to_le_bytes());
+        header.extend([6, 8, 0, 0]);
+        header.extend(0u32.to_le_bytes());
+        header.extend(width.to_le_bytes());
+        header.extend(height.to_le_bytes());
+        header.extend([format.into(), mip_count, tile_mode, 0]);
+
+        Ok((TextureElement::read_le(&mut Cursor::new(header))?, file))
+    }
+
+    #[test]
+    fn decode_uncompressed() -> R {
+        let (element, file) = linear_texture(ResourceTextureFormat::Rg, 2, 1, &[10, 20, 30, 40])?;
+        let image = element.get_image(&file)?.ok_or("Texture is empty")?;
+        assert_eq!(image.as_raw(), &[0, 20, 10, 255, 0, 40, 30, 255]);
+
+        let options = TextureDecodeOptions { normalize: true };
+        let image = element
+            .get_image_with(&file, options)?
+            .ok_or("Texture is empty")?;
+        assert_eq!(image.as_raw(), &[10, 10, 10, 20, 30, 30, 30, 40]);
+
+        let (element, file) = linear_texture(ResourceTextureFormat::Rgba, 1, 1, &[10, 20, 30, 40])?;
+        let image = element.get_image(&file)?.ok_or("Texture is empty")?;
+        assert_eq!(image.as_raw(), &[30, 20, 10, 40]);
+
+        let image = element.get_rgba_image(&file)?.ok_or("Texture is empty")?;
+        assert_eq!(image.as_raw(), &[10, 20, 30, 40]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn part_names() {
+        for part in TexturePart::ALL {
+            assert_eq!(part.name().parse(), Ok(part));
+        }
+        assert_eq!("NoseLine".parse(), Ok(TexturePart::Noseline));
+        assert!("nose".parse::<TexturePart>().is_err());
+    }
+
+    #[test]
+    fn mip_chain() -> R {
+        use tegra_swizzle::surface::swizzle_surface;
+
+        // 8x8, 4x4, 2x2 and 1x1 levels, each filled with its level number.
+        let levels: Vec<u8> = (0..4u8)
+            .flat_map(|level| vec![level; (8usize >> level).pow(2)])
+            .collect();
+        let swizzled = swizzle_surface(8, 8, 1, &levels, BlockDim::uncompressed(), None, 1, 4, 1)?;
+
+        let (element, file) = texture(ResourceTextureFormat::R, 8, 8, 4, 0, &swizzled)?;
+        let chain = element.get_mip_chain(&file)?.ok_or("Texture is empty")?;
+
+        assert_eq!(chain.len(), 4);
+        for (level, image) in (0..).zip(&chain) {
+            assert_eq!(image.dimensions(), (8 >> level, 8 >> level));
+            assert!(image.pixels().all(|pixel| pixel.0 == [0, 0, level, 255]));
+        }
+
+        assert_eq!(element.get_image(&file)?.as_ref(), chain.first());
+
+        Ok(())
+    }
+
+    #[test]
+    fn compressed() -> R {
+        // One Bc4 block where every pixel is the first endpoint.
+        let block = [200, 0, 0, 0, 0, 0, 0, 0];
+        let (element, file) = linear_texture(ResourceTextureFormat::Bc4, 4, 4, &block)?;
+
+        let texture = element.get_compressed(&file)?.ok_or("Texture is empty")?;
+        assert_eq!(texture.blocks(), (1, 1));
+        assert_eq!(texture.data, block);
+        assert_eq!(Some(texture.decode()?), element.get_image(&file)?);
+        assert!(
+            texture
+                .decode()?
+                .pixels()
+                .all(|pixel| pixel.0 == [0, 0, 200, 255])
+        );
+
+        let (element, file) = linear_texture(ResourceTextureFormat::R, 1, 1, &[0])?;
+        assert!(element.get_compressed(&file)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn r_matches_bc4() -> R {
+        // The same 4x4 image of 200s, once uncompressed and once as a Bc4 block.
+        let (r, r_file) = linear_texture(ResourceTextureFormat::R, 4, 4, &[200; 16])?;
+        let (bc4, bc4_file) = linear_texture(
+            ResourceTextureFormat::Bc4,
+            4,
+            4,
+            &[200, 0, 0, 0, 0, 0, 0, 0],
+        )?;
+
+        let r = r.get_image(&r_file)?.ok_or("Texture is empty")?;
+        let bc4 = bc4.get_image(&bc4_file)?.ok_or("Texture is empty")?;
+        assert_eq!(r, bc4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn decode_wrong_size() -> R {
+        let (element, file) = linear_texture(ResourceTextureFormat::Rgba, 2, 2, &[0; 4])?;
+        let error = element.get_image(&file).unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(TextureError::WrongSize {
+                expected: 16,
+                actual: 4
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn bogus_header() -> R {
+        // More mip levels than there are bits to shift by.
+        let (element, file) = texture(ResourceTextureFormat::R, 2, 2, 255, 1, &[0; 4])?;
+        assert!(element.get_mip_chain(&file).is_err());
+
+        // Far bigger than the data, swizzled or not. Neither should be allocated for.
+        for tile_mode in [0, 1] {
+            let (element, file) = texture(
+                ResourceTextureFormat::Bc7,
+                u16::MAX,
+                u16::MAX,
+                1,
+                tile_mode,
+                &[0; 16],
+            )?;
+            assert!(element.get_image(&file).is_err());
+            assert!(element.get_compressed(&file).is_err());
+        }
+
+        Ok(())
+    }
+
     // #[test]
     // #[cfg(feature = "draw")]
     // fn eye_tex() -> R {
@@ -0,0 +1,742 @@
+//! Writing Nx resource files, e.g. to replace a hair mesh or an eye texture.
+//!
+//! [`ShapeFileBuilder`] and [`TextureFileBuilder`] start either empty or from an existing
+//! file, have parts replaced or removed, and then build a new file that
+//! [`NxShapeFile`] and [`NxTextureFile`] read back. Parts that aren't replaced are copied
+//! over as they are.
+//!
+//! ```no_run
+//! use vee_resources::shape::{Shape, ShapeMesh};
+//! use vee_resources::source::NxShapeFile;
+//! use vee_resources::write::ShapeFileBuilder;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let shapes = NxShapeFile::new(std::fs::read("./ShapeMid.dat")?)?;
+//! let mut builder = ShapeFileBuilder::from_file(&shapes)?;
+//!
+//! let positions = [[0.0, 0.0, 0.0], [10.0, 0.0, 0.0], [0.0, 10.0, 0.0]];
+//! let hair = ShapeMesh::from_f32(&positions, None, None, vec![0, 1, 2]);
+//! builder.set_mesh(Shape::HairNormal, 30, &hair)?;
+//!
+//! std::fs::write("./ShapeMidModded.dat", builder.build()?)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::deflate_bytes;
+use crate::shape::{
+    ResourceCommonAttribute, ResourceShape, ResourceShapeFacelineTransform,
+    ResourceShapeHairTransform, Shape, ShapeElement, ShapeMesh,
+};
+use crate::source::{NxShapeFile, NxTextureFile};
+use crate::tex::{
+    ResourceTexture, ResourceTextureAttribute, ResourceTextureFormat, TextureElement, TextureError,
+    TexturePart, encode_level, mip_size,
+};
+use binrw::BinWrite;
+use image::RgbaImage;
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{Cursor, Seek, SeekFrom};
+use tegra_swizzle::surface::{BlockDim, swizzle_surface};
+use tegra_swizzle::swizzle::deswizzled_mip_size;
+use tegra_swizzle::{block_height_mip0, div_round_up};
+
+/// Zlib level parts are compressed with, unless set otherwise.
+pub const DEFAULT_COMPRESSION_LEVEL: u8 = 6;
+
+/// Zlib's default, which is all `flate2` supports.
+const MEMORY_LEVEL: u8 = 8;
+
+/// Alignment of a mesh's attribute buffers, all of which have 4 byte elements except positions.
+const MESH_ALIGNMENT: u32 = 4;
+
+/// Size of a Tegra GOB, which swizzled textures are aligned to a column of.
+const GOB_SIZE: u32 = 512;
+
+/// Builds a Nx shape resource file (e.g. `ShapeMid.dat`).
+#[derive(Clone)]
+pub struct ShapeFileBuilder {
+    header: ResourceShape,
+    /// Compressed data of every mesh that isn't empty.
+    data: HashMap<(Shape, usize), Vec<u8>>,
+    compression_level: u8,
+}
+
+impl Default for ShapeFileBuilder {
+    fn default() -> Self {
+        ShapeFileBuilder::new()
+    }
+}
+
+impl ShapeFileBuilder {
+    /// Starts a file without any meshes or transforms.
+    #[must_use]
+    pub fn new() -> ShapeFileBuilder {
+        ShapeFileBuilder {
+            header: ResourceShape::default(),
+            data: HashMap::new(),
+            compression_level: DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+
+    /// Starts from every mesh and transform of `file`.
+    ///
+    /// # Errors
+    /// - A mesh is out of bounds of the file
+    pub fn from_file<D: AsRef<[u8]>>(
+        file: &NxShapeFile<D>,
+    ) -> Result<ShapeFileBuilder, Box<dyn Error>> {
+        let mut data = HashMap::new();
+
+        for shape in Shape::MESHES {
+            for (index, element) in file.header.elements(shape).iter().enumerate() {
+                // Same as reading: empty meshes are skipped, whatever their offset.
+                if element.common.size == 0 {
+                    continue;
+                }
+
+                let compressed = compressed_range(&element.common, file.data.as_ref())
+                    .ok_or("Mesh is out of bounds of the resource file.")?;
+                data.insert((shape, index), compressed.to_vec());
+            }
+        }
+
+        Ok(ShapeFileBuilder {
+            header: file.header,
+            data,
+            compression_level: DEFAULT_COMPRESSION_LEVEL,
+        })
+    }
+
+    /// Sets the zlib level (`0..=9`) meshes set from now on are compressed with.
+    pub fn set_compression_level(&mut self, level: u8) {
+        self.compression_level = level.min(9);
+    }
+
+    /// Encodes and compresses `mesh`, replacing the mesh at `index`.
+    ///
+    /// # Errors
+    /// - `shape` doesn't have an `index`, or has no meshes at all
+    /// - `mesh` can't be encoded, see [`ShapeMesh::encode`]
+    pub fn set_mesh(
+        &mut self,
+        shape: Shape,
+        index: usize,
+        mesh: &ShapeMesh,
+    ) -> Result<(), Box<dyn Error>> {
+        let (bytes, attribute) = mesh.encode()?;
+        let compressed = deflate_bytes(&bytes, self.compression_level)?;
+
+        let element = self
+            .header
+            .elements_mut(shape)
+            .get_mut(index)
+            .ok_or("Shape index is out of range.")?;
+
+        *element = ShapeElement {
+            common: common_attribute(bytes.len(), compressed.len(), self.compression_level)?,
+            shape: attribute,
+        };
+        self.header.raise_alignment(shape, MESH_ALIGNMENT);
+        self.data.insert((shape, index), compressed);
+
+        Ok(())
+    }
+
+    /// Empties the mesh at `index`.
+    ///
+    /// # Errors
+    /// - `shape` doesn't have an `index`, or has no meshes at all
+    pub fn remove_mesh(&mut self, shape: Shape, index: usize) -> Result<(), Box<dyn Error>> {
+        let element = self
+            .header
+            .elements_mut(shape)
+            .get_mut(index)
+            .ok_or("Shape index is out of range.")?;
+
+        *element = ShapeElement::default();
+        self.data.remove(&(shape, index));
+
+        Ok(())
+    }
+
+    /// Replaces the transform for a hair type.
+    ///
+    /// # Errors
+    /// - There's no hair type `index`
+    pub fn set_hair_transform(
+        &mut self,
+        index: usize,
+        transform: ResourceShapeHairTransform,
+    ) -> Result<(), Box<dyn Error>> {
+        *self
+            .header
+            .hair_transform
+            .get_mut(index)
+            .ok_or("Hair transform index is out of range.")? = transform;
+
+        Ok(())
+    }
+
+    /// Replaces the transform for a faceline type.
+    ///
+    /// # Errors
+    /// - There's no faceline type `index`
+    pub fn set_face_line_transform(
+        &mut self,
+        index: usize,
+        transform: ResourceShapeFacelineTransform,
+    ) -> Result<(), Box<dyn Error>> {
+        *self
+            .header
+            .face_line_transform
+            .get_mut(index)
+            .ok_or("Faceline transform index is out of range.")? = transform;
+
+        Ok(())
+    }
+
+    /// Lays out every mesh after the header and writes the file.
+    ///
+    /// # Errors
+    /// - The file would be larger than 4 GiB
+    pub fn build(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut header = self.header;
+        let mut writer = Cursor::new(Vec::new());
+
+        // The header is a fixed size, so it's written once to find where the data starts.
+        header.write(&mut writer)?;
+        let mut offset = writer.position();
+
+        for shape in Shape::MESHES {
+            for (index, element) in header.elements_mut(shape).iter_mut().enumerate() {
+                if let Some(data) = self.data.get(&(shape, index)) {
+                    element.common.offset = u32::try_from(offset)?;
+                    writer.get_mut().extend(data);
+                    offset += data.len() as u64;
+                }
+            }
+        }
+
+        header.set_sizes(u32::try_from(offset)?);
+        writer.seek(SeekFrom::Start(0))?;
+        header.write(&mut writer)?;
+
+        Ok(writer.into_inner())
+    }
+}
+
+/// A texture ready to be written: encoded, but neither swizzled nor compressed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedTexture {
+    pub format: ResourceTextureFormat,
+    pub width: u32,
+    pub height: u32,
+    /// De-swizzled data of every mip level, largest first.
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl EncodedTexture {
+    /// Encodes a texture without mipmaps.
+    /// `image` is laid out like [`TextureElement::get_image`] returns it.
+    ///
+    /// # Errors
+    /// See [`Self::encode_mip_chain`].
+    pub fn encode(
+        image: &RgbaImage,
+        format: ResourceTextureFormat,
+    ) -> Result<EncodedTexture, TextureError> {
+        EncodedTexture::encode_mip_chain(std::slice::from_ref(image), format)
+    }
+
+    /// Encodes a texture from every mip level, largest first.
+    /// Each level is laid out like [`TextureElement::get_mip_chain`] returns them.
+    ///
+    /// `Bc4` and `Bc5` are encoded from each block's lowest and highest values, which is
+    /// exact for textures with two shades per block.
+    ///
+    /// # Errors
+    /// - `levels` is empty, or its first level is ([`TextureError::Empty`])
+    /// - A level isn't half the size of the one before ([`TextureError::WrongSize`])
+    /// - `format` is `Bc7` or `Astc4x4`, which can't be encoded ([`TextureError::Unsupported`])
+    pub fn encode_mip_chain(
+        levels: &[RgbaImage],
+        format: ResourceTextureFormat,
+    ) -> Result<EncodedTexture, TextureError> {
+        let (width, height) = levels.first().ok_or(TextureError::Empty)?.dimensions();
+        if width == 0 || height == 0 {
+            return Err(TextureError::Empty);
+        }
+
+        let levels = levels
+            .iter()
+            .zip(0..)
+            .map(|(image, level)| {
+                let (level_width, level_height) = mip_size(width, height, level);
+                if image.dimensions() != (level_width, level_height) {
+                    return Err(TextureError::WrongSize {
+                        expected: (level_width * level_height * 4) as usize,
+                        actual: image.as_raw().len(),
+                    });
+                }
+
+                encode_level(format, image)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(EncodedTexture {
+            format,
+            width,
+            height,
+            levels,
+        })
+    }
+
+    /// Reads a texture from a resource file without decoding it.
+    /// Returns `None` for an empty texture.
+    ///
+    /// # Errors
+    /// See [`TextureElement::get_mip_texture_bytes`].
+    pub fn from_element(
+        element: &TextureElement,
+        file: &[u8],
+    ) -> Result<Option<EncodedTexture>, Box<dyn Error>> {
+        if element.texture.width == 0 || element.texture.height == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(EncodedTexture {
+            format: element.texture.format,
+            width: element.texture.width.into(),
+            height: element.texture.height.into(),
+            levels: element.get_mip_texture_bytes(file)?,
+        }))
+    }
+}
+
+/// Builds a Nx texture resource file (e.g. `NXTextureMidSRGB.dat`).
+#[derive(Clone)]
+pub struct TextureFileBuilder {
+    header: ResourceTexture,
+    /// Compressed data of every texture that isn't empty.
+    data: HashMap<(TexturePart, usize), Vec<u8>>,
+    compression_level: u8,
+}
+
+impl Default for TextureFileBuilder {
+    fn default() -> Self {
+        TextureFileBuilder::new()
+    }
+}
+
+impl TextureFileBuilder {
+    /// Starts a file without any textures.
+    #[must_use]
+    pub fn new() -> TextureFileBuilder {
+        TextureFileBuilder {
+            header: ResourceTexture::default(),
+            data: HashMap::new(),
+            compression_level: DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+
+    /// Starts from every texture of `file`.
+    ///
+    /// # Errors
+    /// - A texture is out of bounds of the file
+    pub fn from_file<D: AsRef<[u8]>>(
+        file: &NxTextureFile<D>,
+    ) -> Result<TextureFileBuilder, Box<dyn Error>> {
+        let mut data = HashMap::new();
+
+        for part in TexturePart::ALL {
+            for (index, element) in file.header.part(part).iter().enumerate() {
+                // Same as reading: textures without a size are empty, whatever their offset.
+                if element.texture.width == 0 || element.texture.height == 0 {
+                    continue;
+                }
+
+                let compressed = compressed_range(&element.common, file.data.as_ref())
+                    .ok_or("Texture is out of bounds of the resource file.")?;
+                data.insert((part, index), compressed.to_vec());
+            }
+        }
+
+        Ok(TextureFileBuilder {
+            header: file.header,
+            data,
+            compression_level: DEFAULT_COMPRESSION_LEVEL,
+        })
+    }
+
+    /// Sets the zlib level (`0..=9`) textures set from now on are compressed with.
+    pub fn set_compression_level(&mut self, level: u8) {
+        self.compression_level = level.min(9);
+    }
+
+    /// Swizzles and compresses `texture`, replacing the texture at `index`.
+    ///
+    /// # Errors
+    /// - `part` doesn't have an `index`
+    /// - `texture` is empty, larger than 65535 pixels across, or has too many levels
+    /// - A level is the wrong size for its dimensions ([`TextureError::WrongSize`])
+    /// - Swizzling fails
+    pub fn set_texture(
+        &mut self,
+        part: TexturePart,
+        index: usize,
+        texture: &EncodedTexture,
+    ) -> Result<(), Box<dyn Error>> {
+        let EncodedTexture {
+            format,
+            width,
+            height,
+            ref levels,
+        } = *texture;
+
+        if width == 0 || height == 0 || levels.is_empty() {
+            return Err(TextureError::Empty.into());
+        }
+
+        let (block_size, bytes_per_block) = format.block_layout();
+        let mip_count = u32::try_from(levels.len())?;
+
+        for (level, data) in (0..).zip(levels) {
+            let (level_width, level_height) = mip_size(width, height, level);
+            let expected = deswizzled_mip_size(
+                div_round_up(level_width, block_size),
+                div_round_up(level_height, block_size),
+                1,
+                bytes_per_block,
+            );
+
+            if data.len() != expected {
+                return Err(TextureError::WrongSize {
+                    expected,
+                    actual: data.len(),
+                }
+                .into());
+            }
+        }
+
+        let block_dim = if block_size == 1 {
+            BlockDim::uncompressed()
+        } else {
+            BlockDim::block_4x4()
+        };
+        let swizzled = swizzle_surface(
+            width,
+            height,
+            1,
+            &levels.concat(),
+            block_dim,
+            None,
+            bytes_per_block,
+            mip_count,
+            1,
+        )?;
+        let compressed = deflate_bytes(&swizzled, self.compression_level)?;
+
+        let attribute = ResourceTextureAttribute {
+            alignment: GOB_SIZE * block_height_mip0(div_round_up(height, block_size)) as u32,
+            width: u16::try_from(width)?,
+            height: u16::try_from(height)?,
+            format,
+            mip_count: u8::try_from(mip_count)?,
+            // Block linear, i.e. swizzled.
+            tile_mode: 0,
+            pad: [0],
+        };
+
+        let element = self
+            .header
+            .part_mut(part)
+            .get_mut(index)
+            .ok_or("Texture index is out of range.")?;
+
+        *element = TextureElement {
+            common: common_attribute(swizzled.len(), compressed.len(), self.compression_level)?,
+            texture: attribute,
+        };
+        self.data.insert((part, index), compressed);
+
+        Ok(())
+    }
+
+    /// Empties the texture at `index`.
+    ///
+    /// # Errors
+    /// - `part` doesn't have an `index`
+    pub fn remove_texture(
+        &mut self,
+        part: TexturePart,
+        index: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let element = self
+            .header
+            .part_mut(part)
+            .get_mut(index)
+            .ok_or("Texture index is out of range.")?;
+
+        *element = TextureElement::default();
+        self.data.remove(&(part, index));
+
+        Ok(())
+    }
+
+    /// Lays out every texture after the header and writes the file.
+    ///
+    /// # Errors
+    /// - The file would be larger than 4 GiB
+    pub fn build(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut header = self.header;
+        let mut writer = Cursor::new(Vec::new());
+
+        // The header is a fixed size, so it's written once to find where the data starts.
+        header.write(&mut writer)?;
+        let mut offset = writer.position();
+
+        for part in TexturePart::ALL {
+            for (index, element) in header.part_mut(part).iter_mut().enumerate() {
+                if let Some(data) = self.data.get(&(part, index)) {
+                    element.common.offset = u32::try_from(offset)?;
+                    writer.get_mut().extend(data);
+                    offset += data.len() as u64;
+                }
+            }
+        }
+
+        header.set_sizes(u32::try_from(offset)?);
+        writer.seek(SeekFrom::Start(0))?;
+        header.write(&mut writer)?;
+
+        Ok(writer.into_inner())
+    }
+}
+
+/// The compressed data of a part, or `None` if it's out of bounds.
+fn compressed_range<'a>(common: &ResourceCommonAttribute, file: &'a [u8]) -> Option<&'a [u8]> {
+    let start = common.offset as usize;
+    let end = start + common.size_compressed as usize;
+
+    file.get(start..end)
+}
+
+/// Sizes of a part, placed at offset `0` until the file is built.
+fn common_attribute(
+    size: usize,
+    size_compressed: usize,
+    compression_level: u8,
+) -> Result<ResourceCommonAttribute, Box<dyn Error>> {
+    Ok(ResourceCommonAttribute {
+        offset: 0,
+        size: u32::try_from(size)?,
+        size_compressed: u32::try_from(size_compressed)?,
+        compression_level,
+        memory_level: MEMORY_LEVEL,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{ShapeSource, TextureSource};
+    use image::Rgba;
+
+    type R = Result<(), Box<dyn Error>>;
+
+    fn hair() -> ShapeMesh {
+        let positions = [[0.0, 0.0, 0.0], [10.0, 0.0, -2.0], [0.0, 10.0, 4.0]];
+        let normals = [[0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, -1.0, 0.0]];
+        let uvs = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]];
+
+        ShapeMesh {
+            tangents: Some(vec![[127, 0, 0, 127], [0, -127, 0, 127], [0, 0, 127, -127]]),
+            color_params: Some(vec![[255, 0, 0, 0], [0, 255, 0, 0], [0, 0, 255, 0]]),
+            ..ShapeMesh::from_f32(&positions, Some(&normals), Some(&uvs), vec![0, 1, 2])
+        }
+    }
+
+    /// Byte representations of every part of a mesh, to compare them.
+    fn mesh_bytes(mesh: &ShapeMesh) -> Vec<Vec<u8>> {
+        use bytemuck::cast_slice;
+
+        vec![
+            cast_slice(&mesh.positions).to_vec(),
+            cast_slice(&mesh.indices).to_vec(),
+            mesh.normals
+                .as_deref()
+                .map(cast_slice)
+                .unwrap_or_default()
+                .to_vec(),
+            mesh.uvs
+                .as_deref()
+                .map(cast_slice)
+                .unwrap_or_default()
+                .to_vec(),
+            mesh.tangents
+                .as_deref()
+                .map(cast_slice)
+                .unwrap_or_default()
+                .to_vec(),
+            mesh.color_params
+                .as_deref()
+                .map(cast_slice)
+                .unwrap_or_default()
+                .to_vec(),
+        ]
+    }
+
+    #[test]
+    fn shape_round_trip() -> R {
+        let mut builder = ShapeFileBuilder::new();
+        builder.set_mesh(Shape::HairNormal, 30, &hair())?;
+        builder.set_mesh(Shape::Nose, 1, &hair())?;
+        builder.set_face_line_transform(
+            2,
+            ResourceShapeFacelineTransform {
+                hair_translate: [0.0, 5.0, -1.0],
+                ..Default::default()
+            },
+        )?;
+        assert!(builder.set_mesh(Shape::Glasses, 1, &hair()).is_err());
+
+        let file = NxShapeFile::new(builder.build()?)?;
+        let mesh = file.mesh(Shape::HairNormal, 30)?.ok_or("Mesh is empty")?;
+        assert_eq!(mesh_bytes(&mesh), mesh_bytes(&hair()));
+        assert_eq!(mesh.tangents, hair().tangents);
+        assert_eq!(mesh.color_params, hair().color_params);
+        assert_eq!(
+            file.header.hair_normal[30].shape.bounding_box,
+            [[0.0, 0.0, -2.0], [10.0, 10.0, 4.0]]
+        );
+        assert!(file.mesh(Shape::HairNormal, 31)?.is_none());
+        assert_eq!(
+            file.face_line_transform(2)?.map(|t| t.hair_translate),
+            Some([0.0, 5.0, -1.0])
+        );
+
+        // Repacking without changes gives the same file.
+        let mut repacked = ShapeFileBuilder::from_file(&file)?;
+        assert_eq!(repacked.build()?, file.data);
+
+        repacked.remove_mesh(Shape::Nose, 1)?;
+        let repacked = NxShapeFile::new(repacked.build()?)?;
+        assert!(repacked.mesh(Shape::Nose, 1)?.is_none());
+        assert!(repacked.mesh(Shape::HairNormal, 30)?.is_some());
+        assert!(repacked.data.len() < file.data.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn shape_round_trip_without_tangents() -> R {
+        let mesh = ShapeMesh {
+            tangents: None,
+            color_params: None,
+            ..hair()
+        };
+
+        let mut builder = ShapeFileBuilder::new();
+        builder.set_mesh(Shape::Nose, 0, &mesh)?;
+
+        let file = NxShapeFile::new(builder.build()?)?;
+        let read = file.mesh(Shape::Nose, 0)?.ok_or("Mesh is empty")?;
+        assert_eq!(read.tangents, None);
+        assert_eq!(read.color_params, None);
+        assert_eq!(mesh_bytes(&read), mesh_bytes(&mesh));
+
+        Ok(())
+    }
+
+    #[test]
+    fn mismatched_attributes() {
+        let mesh = ShapeMesh {
+            uvs: Some(Vec::new()),
+            ..hair()
+        };
+
+        assert!(
+            ShapeFileBuilder::new()
+                .set_mesh(Shape::Mask, 0, &mesh)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn texture_round_trip() -> R {
+        use ResourceTextureFormat as Rtf;
+
+        // 8x8, 4x4, 2x2 and 1x1 levels, each filled with a different shade.
+        let chain: Vec<_> = (0..4u8)
+            .map(|level| {
+                let shade = level * 60;
+                RgbaImage::from_pixel(8 >> level, 8 >> level, Rgba([0, 0, shade, 255]))
+            })
+            .collect();
+        let rgba = RgbaImage::from_fn(3, 2, |x, y| Rgba([x as u8, y as u8, 7, 200]));
+        // Two shades, which Bc5 can encode exactly.
+        let bc5 = RgbaImage::from_fn(8, 4, |x, _| {
+            if x % 3 == 0 {
+                Rgba([0, 20, 230, 255])
+            } else {
+                Rgba([0, 180, 10, 255])
+            }
+        });
+
+        let mut builder = TextureFileBuilder::new();
+        builder.set_texture(
+            TexturePart::Eye,
+            2,
+            &EncodedTexture::encode_mip_chain(&chain, Rtf::R)?,
+        )?;
+        builder.set_texture(
+            TexturePart::Mouth,
+            0,
+            &EncodedTexture::encode(&rgba, Rtf::Rgba)?,
+        )?;
+        builder.set_texture(
+            TexturePart::Makeup,
+            5,
+            &EncodedTexture::encode(&bc5, Rtf::Bc5)?,
+        )?;
+
+        let file = NxTextureFile::new(builder.build()?)?;
+        assert_eq!(
+            file.header.eye[2]
+                .get_mip_chain(&file.data)?
+                .ok_or("Texture is empty")?,
+            chain
+        );
+        assert_eq!(file.texture(TexturePart::Mouth, 0)?, Some(rgba));
+        assert_eq!(file.texture(TexturePart::Makeup, 5)?, Some(bc5));
+        assert!(file.texture(TexturePart::Eye, 3)?.is_none());
+
+        // Repacking without changes gives the same file, and re-encoding the same texture.
+        let mut repacked = TextureFileBuilder::from_file(&file)?;
+        assert_eq!(repacked.build()?, file.data);
+
+        let eye = EncodedTexture::from_element(&file.header.eye[2], &file.data)?
+            .ok_or("Texture is empty")?;
+        repacked.set_texture(TexturePart::Eye, 2, &eye)?;
+        assert_eq!(repacked.build()?, file.data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_encoding() {
+        let image = RgbaImage::new(4, 4);
+
+        assert!(matches!(
+            EncodedTexture::encode(&image, ResourceTextureFormat::Bc7),
+            Err(TextureError::Unsupported(ResourceTextureFormat::Bc7))
+        ));
+        assert!(matches!(
+            EncodedTexture::encode_mip_chain(&[image.clone(), image], ResourceTextureFormat::R),
+            Err(TextureError::WrongSize { .. })
+        ));
+    }
+}
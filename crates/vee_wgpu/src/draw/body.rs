@@ -2,10 +2,12 @@ use crate::Model3d;
 use glam::{Mat4, Quat, Vec3};
 use gltf::animation::util::ReadOutputs;
 use std::collections::HashMap;
+use std::error::Error;
 use std::path::Path;
 use vee_models::model::{GenericModel3d, Vertex};
-use vee_parse::NxCharInfo;
-use vee_resources::{color::nx::linear::FAVORITE_COLOR, packing::Float16};
+use vee_resources::color::CharColors;
+use vee_resources::color::nx::ColorModulated;
+use vee_resources::packing::Float16;
 
 #[derive(Clone, Copy, Debug)]
 struct Transform {
@@ -30,16 +32,22 @@ impl Transform {
     }
 }
 
+/// Loads the body at `path`, posed in the first frame of its wait animation, and where the
+/// head goes on it.
+/// # Errors
+/// - The file can't be read or isn't glTF
+/// - An animation channel or a mesh node is missing data
+/// - There's no `head` node
 pub fn load_body<P: AsRef<Path>>(
-    char_info: &NxCharInfo,
+    colors: &CharColors,
     path: P,
-) -> Result<(Vec<Model3d>, Mat4), Box<dyn std::error::Error>> {
+) -> Result<(Vec<Model3d>, Mat4), Box<dyn Error>> {
     let (gltf, buffers, _textures) = gltf::import(path)?;
 
     let mut local_transforms = get_local_transforms(&gltf);
 
     // Apply first-frame animation to avoid the default "t-pose".
-    apply_animations(&gltf, &buffers, &mut local_transforms);
+    apply_animations(&gltf, &buffers, &mut local_transforms)?;
 
     // Move transforms relative to world root.
     let global_transforms = compute_global_transforms(&gltf, &local_transforms);
@@ -50,9 +58,9 @@ pub fn load_body<P: AsRef<Path>>(
     // The issue with this is that the body is not always the same size, due to
     // body scaling. Therefore we have to pass it to the head "CharModel".
     let head_transform = find_labeled_transform("head", &gltf, &global_transforms)
-        .expect("Should always be a `head` transform");
+        .ok_or("The body has no `head` node.")?;
 
-    let models = load_meshes(char_info, &gltf, &buffers, &global_transforms);
+    let models = load_meshes(colors, &gltf, &buffers, &global_transforms)?;
 
     Ok((models, head_transform))
 }
@@ -77,7 +85,7 @@ fn apply_animations(
     gltf: &gltf::Document,
     buffers: &[gltf::buffer::Data],
     local_transforms: &mut HashMap<usize, Transform>,
-) {
+) -> Result<(), Box<dyn Error>> {
     if let Some(anim) = gltf
         .animations()
         .find(|a| a.name().is_some_and(|n| n.to_lowercase().contains("wait")))
@@ -86,8 +94,12 @@ fn apply_animations(
             let target_node_index = channel.target().node().index();
             let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
             // We don't care about later keyframes
-            let _timestamps = reader.read_inputs().unwrap();
-            let outputs = reader.read_outputs().unwrap(); // Values
+            let _timestamps = reader
+                .read_inputs()
+                .ok_or("An animation channel has no keyframe times.")?;
+            let outputs = reader
+                .read_outputs()
+                .ok_or("An animation channel has no keyframe values.")?;
 
             // We want the first keyframe, at least initially.
             // If we don't set an initial keyframe, it will default
@@ -118,6 +130,8 @@ fn apply_animations(
             }
         }
     }
+
+    Ok(())
 }
 
 fn compute_global_transforms(
@@ -173,19 +187,20 @@ fn find_labeled_transform(
 }
 
 fn load_meshes(
-    char_info: &NxCharInfo,
+    colors: &CharColors,
     gltf: &gltf::Document,
     buffers: &[gltf::buffer::Data],
     global_transforms: &HashMap<usize, Mat4>,
-) -> Vec<Model3d> {
+) -> Result<Vec<Model3d>, Box<dyn Error>> {
     let mut models = Vec::new();
 
-    let favorite_color = FAVORITE_COLOR[char_info.favorite_color as usize];
+    let [body_color, ..] = colors.modulate(ColorModulated::Body).channels;
+    let [pants_color, ..] = colors.modulate(ColorModulated::Pants).channels;
     for node in gltf.nodes() {
-        let color = match node.name().unwrap() {
-            "body__mt_body" => favorite_color,
-            "body__mt_pants" => [0.0, 1.0, 0.0], // todo find pants color
-            _ => [1.0, 0.0, 1.0],                // evil magenta
+        let color = match node.name().ok_or("A body node has no name.")? {
+            "body__mt_body" => body_color,
+            "body__mt_pants" => pants_color,
+            _ => [1.0, 0.0, 1.0, 1.0], // evil magenta
         };
 
         if let Some(mesh) = node.mesh() {
@@ -231,7 +246,7 @@ fn load_meshes(
             }
         }
     }
-    models
+    Ok(models)
 }
 
 fn primitive_to_model3d(
@@ -239,7 +254,7 @@ fn primitive_to_model3d(
     buffers: &[gltf::buffer::Data],
     joint_matrices: &[Mat4],
     node_global_transform: Mat4,
-    color: [f32; 3],
+    color: [f32; 4],
 ) -> Model3d {
     let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
 
@@ -321,12 +336,13 @@ fn primitive_to_model3d(
         });
     }
 
-    let color = Vec3::from_array(color).extend(1.0);
-
     GenericModel3d {
         vertices: final_vertices,
+        packed_normals: Vec::new(),
+        tangents: None,
+        color_params: None,
         indices,
-        color,
+        color: color.into(),
         texture: None,
         position: Vec3::ZERO,
         scale: Vec3::splat(10.0),
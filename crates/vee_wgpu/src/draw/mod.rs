@@ -1,20 +1,43 @@
 //! Drawing models and textures.
 
+use std::error::Error;
 use std::path::PathBuf;
 
-use crate::draw::model::{beard, face_line, forehead, glasses, hair, hat, mask, nose, nose_line};
+use crate::draw::model::{clear_texture, render_projected, upload_part};
+use crate::texture::TextureBundle;
 use crate::{Model3d, ProgramState};
+use vee_models::Model2d;
+use vee_models::animation::{self, FaceState};
+use vee_models::attachment::{AttachmentPoint, attachment_frames};
+use vee_models::bounds::Aabb;
+use vee_models::char_model::{self, CharResources};
+use vee_models::expression::{Expression, ExpressionSet};
 use vee_parse::NxCharInfo;
+use vee_resources::color::CharColors;
+use vee_resources::color::nx::PantsColor;
 use vee_resources::packing::Float16;
 use wgpu::{CommandEncoder, TextureView};
 
 pub(crate) mod body;
 pub(crate) mod model;
-pub(crate) mod texture;
 
 type Model = Model3d;
 type ModelOpt = Option<Model3d>;
 
+/// Choices for building a [`CharModel`] that aren't part of the character.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CharModelOptions {
+    /// Replaces the pants color of `colors`, see [`CharColors::with_pants`].
+    /// `None` keeps it, which is what FFL picks unless `colors` says otherwise.
+    pub pants: Option<PantsColor>,
+    /// Expressions to render a mask for, like FFL's `expressionFlag`.
+    /// The lowest one is shown first; an empty set means just [`Expression::Normal`].
+    pub expressions: ExpressionSet,
+    /// `None` draws the character's own Nx colors, see [`CharColors::nx`].
+    /// [`CharColors::new`] keeps the look of another console's palette.
+    pub colors: Option<CharColors>,
+}
+
 /// A bundle of models that in totality represent a `Char`.
 #[derive(Debug)]
 pub struct CharModel {
@@ -29,16 +52,47 @@ pub struct CharModel {
     pub hat: ModelOpt,
     pub extras: Vec<Model3d>,
     pub head_transform: glam::Mat4,
+    /// The expression `mask` is making.
+    pub expression: Expression,
+    /// A mask texture for every expression in [`CharModelOptions::expressions`].
+    pub mask_textures: Vec<(Expression, TextureBundle)>,
+    /// Where [`CharModel::redraw_mask`] draws, kept apart from `mask_textures`.
+    mask_frame: Option<TextureBundle>,
+    /// The colors every part was drawn in, for redrawing the mask.
+    colors: CharColors,
 }
 
 impl CharModel {
+    /// Builds and uploads `char_info` with the default [`CharModelOptions`].
+    /// # Errors
+    /// - See [`CharModel::with_options`]
     pub fn new(
         st: &mut impl ProgramState,
         char_info: &NxCharInfo,
         encoder: &mut CommandEncoder,
-    ) -> CharModel {
+    ) -> Result<CharModel, Box<dyn Error>> {
+        CharModel::with_options(st, char_info, CharModelOptions::default(), encoder)
+    }
+
+    /// Builds and uploads `char_info`, on the body from `resources_here`.
+    /// # Errors
+    /// - `CARGO_WORKSPACE_DIR` isn't set
+    /// - The body can't be loaded, see [`body::load_body`]
+    /// - A part or mask can't be built, see [`char_model::build`] and [`char_model::mask_texture`]
+    pub fn with_options(
+        st: &mut impl ProgramState,
+        char_info: &NxCharInfo,
+        options: CharModelOptions,
+        encoder: &mut CommandEncoder,
+    ) -> Result<CharModel, Box<dyn Error>> {
+        let colors = options.colors.unwrap_or_else(|| CharColors::nx(char_info));
+        let colors = match options.pants {
+            Some(pants) => colors.with_pants(pants),
+            None => colors,
+        };
+
         let res_path: PathBuf = [
-            std::env::var("CARGO_WORKSPACE_DIR").unwrap(),
+            std::env::var("CARGO_WORKSPACE_DIR")?,
             "resources_here".to_string(),
         ]
         .iter()
@@ -46,19 +100,11 @@ impl CharModel {
 
         // TODO: Don't hardcode this path
         let (extras, head_transform) =
-            body::load_body(char_info, res_path.join("miibodymiddle female test.glb"))
-                .unwrap_or_else(|e| {
-                    panic!("Failed to load GLTF: {}", e);
-                });
+            body::load_body(&colors, res_path.join("miibodymiddle female test.glb"))?;
 
         // Helper to transform a model
         let transform_model = |mut model: Model3d| {
-            // Apply scale 10.0 to match the body
-            let scale_matrix = glam::Mat4::from_scale(glam::Vec3::splat(10.0));
-            // Scale head to match body proportion: 0.1 * (10 / 7) = 1/7
-            let head_scale_correction = glam::Mat4::from_scale(glam::Vec3::splat(1.0 / 7.0));
-            // Apply head transform (already in global space relative to scene root)
-            let final_transform = scale_matrix * head_transform * head_scale_correction;
+            let final_transform = head_to_world(head_transform);
 
             // We need to bake the model's local scale and position into the vertices
             // because the head transform must apply to the *final* model-space coordinate.
@@ -104,29 +150,72 @@ impl CharModel {
                 ];
                 vertex.normal = new_normal.to_array();
             }
+            // The shape's normals don't match anymore.
+            model.packed_normals.clear();
+
+            // Tangents turn with the normals. w is the bitangent's sign, and stays.
+            for tangent in model.tangents.iter_mut().flatten() {
+                let [x, y, z, w] = *tangent;
+                let direction = glam::Vec3::from([x, y, z].map(f32::from)) / 127.0;
+                let direction = final_transform
+                    .transform_vector3(direction)
+                    .normalize_or_zero();
+
+                let [x, y, z] = direction.to_array().map(|v| (v * 127.0).round() as i8);
+                *tangent = [x, y, z, w];
+            }
             model
         };
 
-        // Set up required face parts
-        let face_line = face_line(st, char_info, encoder)
-            .map(transform_model)
-            .expect("Required face part");
-        let mask = mask(st, char_info, encoder)
-            .map(transform_model)
-            .expect("Required face part");
-        let nose_line = nose_line(st, char_info, encoder)
-            .map(transform_model)
-            .expect("Required face part");
-
-        // Set up "optional" face parts
-        let forehead = forehead(st, char_info, encoder).map(transform_model);
-        let hair = hair(st, char_info, encoder).map(transform_model);
-        let nose = nose(st, char_info, encoder).map(transform_model);
-        let glasses = glasses(st, char_info, encoder).map(transform_model);
-        let beard = beard(st, char_info, encoder).map(transform_model);
-        let hat = hat(st, char_info, encoder).map(transform_model);
-
-        CharModel {
+        let shapes = st.shape_source();
+        let textures = st.texture_source();
+        let compressed_formats = st.compressed_formats();
+
+        let resources = CharResources {
+            shapes: shapes.as_ref(),
+            textures: textures.as_ref(),
+            compressed_formats: &compressed_formats,
+        };
+        let expressions = if options.expressions.is_empty() {
+            ExpressionSet::default()
+        } else {
+            options.expressions
+        };
+        let expression = expressions.first().unwrap_or_default();
+
+        let data = char_model::build(char_info, &colors, resources, expression)?;
+
+        let mut upload = |part, label| transform_model(upload_part(st, part, label, encoder));
+
+        let face_line = upload(data.face_line, "faceline");
+        let mask = upload(data.mask, "mask");
+        let nose_line = upload(data.nose_line, "nose line");
+        let forehead = data.forehead.map(|part| upload(part, "forehead"));
+        let hair = data.hair.map(|part| upload(part, "hair"));
+        let nose = data.nose.map(|part| upload(part, "nose"));
+        let glasses = data.glasses.map(|part| upload(part, "glasses"));
+        let beard = data.beard.map(|part| upload(part, "beard"));
+        let hat = data.hat.map(|part| upload(part, "hat"));
+
+        // The shown expression's mask is already rendered.
+        let mask_textures = expressions
+            .iter()
+            .map(|other| {
+                let texture = match mask.texture.as_ref() {
+                    Some(texture) if other == expression => texture.clone(),
+                    _ => render_projected(
+                        st,
+                        char_model::mask_texture(char_info, &colors, resources, other)?,
+                        "mask",
+                        encoder,
+                    ),
+                };
+
+                Ok((other, texture))
+            })
+            .collect::<Result<_, Box<dyn Error>>>()?;
+
+        Ok(CharModel {
             face_line,
             forehead,
             mask,
@@ -138,7 +227,155 @@ impl CharModel {
             hat,
             extras,
             head_transform,
+            expression,
+            mask_textures,
+            mask_frame: None,
+            colors,
+        })
+    }
+
+    /// Redraws the mask for `state` on top of the current expression, leaving every other part
+    /// alone. Call it every frame with the state from [`animation::FaceAnimator`].
+    /// The mask's textures are read again on every call, so a caching source like
+    /// [`vee_resources::store::ResourceStore`] saves decoding them each frame.
+    ///
+    /// # Errors
+    /// - See [`animation::mask_draws`]
+    pub fn animate_mask(
+        &mut self,
+        st: &mut impl ProgramState,
+        char_info: &NxCharInfo,
+        state: FaceState,
+        encoder: &mut CommandEncoder,
+    ) -> Result<(), Box<dyn Error>> {
+        let textures = st.texture_source();
+        let shapes = st.shape_source();
+        let compressed_formats = st.compressed_formats();
+
+        let draws = animation::mask_draws(
+            char_info,
+            &self.colors,
+            CharResources {
+                shapes: shapes.as_ref(),
+                textures: textures.as_ref(),
+                compressed_formats: &compressed_formats,
+            },
+            self.expression,
+            state,
+        )?;
+
+        self.redraw_mask(st, draws, encoder);
+
+        Ok(())
+    }
+
+    /// Replaces the mask's texture with `draws`, drawn in order over a cleared texture.
+    /// The target texture is reused between calls, but each draw still uploads its own
+    /// texture and buffers, see [`ProgramState::draw_model_2d`].
+    /// [`CharModel::set_expression`] goes back to the pre-rendered masks.
+    pub fn redraw_mask(
+        &mut self,
+        st: &mut impl ProgramState,
+        draws: Vec<Model2d>,
+        encoder: &mut CommandEncoder,
+    ) {
+        let frame = match self.mask_frame.take() {
+            Some(frame) => {
+                clear_texture(&frame.view, encoder);
+                frame
+            }
+            None => TextureBundle::create_texture(
+                &st.device(),
+                &glam::uvec2(512, 512),
+                "projected texture animated mask",
+            ),
+        };
+
+        for mut draw in draws {
+            st.draw_model_2d(&mut draw, &frame.view, encoder);
         }
+
+        self.mask.texture = Some(frame.clone());
+        self.mask_frame = Some(frame);
+    }
+
+    /// Shows `expression` on the mask. Returns `false`, and keeps the current expression,
+    /// if it wasn't in [`CharModelOptions::expressions`].
+    pub fn set_expression(&mut self, expression: Expression) -> bool {
+        let Some(texture) = self.mask_texture(expression) else {
+            return false;
+        };
+
+        self.mask.texture = Some(texture.clone());
+        self.expression = expression;
+
+        true
+    }
+
+    /// The mask texture for `expression`, if it was rendered.
+    #[must_use]
+    pub fn mask_texture(&self, expression: Expression) -> Option<&TextureBundle> {
+        self.mask_textures
+            .iter()
+            .find(|(rendered, _)| *rendered == expression)
+            .map(|(_, texture)| texture)
+    }
+
+    /// Places the head models (and anything attached to them) in the world.
+    #[must_use]
+    pub fn head_to_world(&self) -> glam::Mat4 {
+        head_to_world(self.head_transform)
+    }
+
+    /// Bounds of the head's models in world space, including the hat and glasses.
+    #[must_use]
+    pub fn head_bounds(&self) -> Option<Aabb> {
+        self.head_models()
+            .filter_map(Model3d::bounds)
+            .reduce(Aabb::union)
+    }
+
+    /// Bounds of every model in world space, the body included.
+    /// Frames full-body shots, or culls the character in a crowd.
+    #[must_use]
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.head_models()
+            .chain(&self.extras)
+            .filter_map(Model3d::bounds)
+            .reduce(Aabb::union)
+    }
+
+    fn head_models(&self) -> impl Iterator<Item = &Model3d> {
+        [&self.face_line, &self.mask, &self.nose_line]
+            .into_iter()
+            .chain(
+                [
+                    &self.forehead,
+                    &self.hair,
+                    &self.nose,
+                    &self.glasses,
+                    &self.beard,
+                    &self.hat,
+                ]
+                .into_iter()
+                .flatten(),
+            )
+    }
+
+    /// Where to draw an accessory at `point` on this character's head, in world space.
+    /// Returns `None` if the resources have no transform for the hairstyle or faceline.
+    ///
+    /// # Errors
+    /// - The shape data is malformed
+    pub fn attachment_matrix(
+        &self,
+        st: &impl ProgramState,
+        char_info: &NxCharInfo,
+        point: AttachmentPoint,
+    ) -> Result<Option<glam::Mat4>, Box<dyn Error>> {
+        let frames = attachment_frames(char_info, st.shape_source().as_ref())?;
+
+        Ok(frames.map(|frames| frames.world_matrix(point, self.head_to_world())))
     }
 
     pub fn render(
@@ -180,3 +417,13 @@ impl CharModel {
         }
     }
 }
+
+/// Head models are in their own space; this puts them on the body.
+fn head_to_world(head_transform: glam::Mat4) -> glam::Mat4 {
+    // Apply scale 10.0 to match the body
+    let scale_matrix = glam::Mat4::from_scale(glam::Vec3::splat(10.0));
+    // Scale head to match body proportion: 0.1 * (10 / 7) = 1/7
+    let head_scale_correction = glam::Mat4::from_scale(glam::Vec3::splat(1.0 / 7.0));
+    // Apply head transform (already in global space relative to scene root)
+    scale_matrix * head_transform * head_scale_correction
+}
@@ -1,34 +1,41 @@
 //! Constructs for rendering without a surface (headlessly) i.e. on a server.
 
-use crate::ProgramState;
+use crate::draw::CharModel;
 use crate::texture::TextureBundle;
+use crate::{ProgramState, VertexLayout, compressed_texture_features};
 use camera::{Camera, CameraUniform};
 use glam::{UVec2, Vec3, uvec2};
 use image::{DynamicImage, RgbaImage};
+use std::error::Error;
 use std::f32::consts::FRAC_PI_2;
-use std::fs::File;
+use std::path::Path;
 use std::rc::Rc;
-use vee_parse::BinRead;
-use vee_resources::shape::ResourceShape;
-use vee_resources::tex::ResourceTexture;
+use vee_parse::NxCharInfo;
+use vee_resources::ResourceStore;
+use vee_resources::source::{ResourceTier, ShapeSource, TextureSource};
 use wgpu::{CommandEncoder, DeviceDescriptor, util::DeviceExt};
 
+/// Scale the body is drawn at, relative to FFL's units.
+const BODY_SCALE: f32 = 10.0;
+/// Textures are viewed as both sRGB and linear, which downlevel (e.g. GL) adapters can't do.
+const REQUIRED_DOWNLEVEL_FLAGS: wgpu::DownlevelFlags = wgpu::DownlevelFlags::VIEW_FORMATS;
+
 pub(crate) struct ResourceData {
-    pub(crate) texture_header: ResourceTexture,
-    pub(crate) shape_header: ResourceShape,
-    pub(crate) texture_data: Rc<Vec<u8>>,
-    pub(crate) shape_data: Rc<Vec<u8>>,
+    pub(crate) textures: Rc<dyn TextureSource>,
+    pub(crate) shapes: Rc<dyn ShapeSource>,
 }
 
 /// Contains all the state required for rendering headlessly.
 pub struct HeadlessRenderer {
     device: wgpu::Device,
     queue: wgpu::Queue,
+    camera_buffer: wgpu::Buffer,
     camera_bgl: wgpu::BindGroupLayout,
     camera_bg: wgpu::BindGroup,
     surface_fmt: wgpu::TextureFormat,
     depth_texture: TextureBundle,
     resource_data: ResourceData,
+    vertex_layout: VertexLayout,
 }
 
 impl ProgramState for HeadlessRenderer {
@@ -56,20 +63,16 @@ impl ProgramState for HeadlessRenderer {
         &self.depth_texture
     }
 
-    fn texture_header(&self) -> ResourceTexture {
-        self.resource_data.texture_header
-    }
-
-    fn shape_header(&self) -> ResourceShape {
-        self.resource_data.shape_header
+    fn texture_source(&self) -> Rc<dyn TextureSource> {
+        self.resource_data.textures.clone()
     }
 
-    fn texture_data(&self) -> Rc<Vec<u8>> {
-        self.resource_data.texture_data.clone()
+    fn shape_source(&self) -> Rc<dyn ShapeSource> {
+        self.resource_data.shapes.clone()
     }
 
-    fn shape_data(&self) -> Rc<Vec<u8>> {
-        self.resource_data.shape_data.clone()
+    fn vertex_layout(&self) -> VertexLayout {
+        self.vertex_layout
     }
 }
 
@@ -77,9 +80,55 @@ impl HeadlessRenderer {
     /// Instantiate a `HeadlessRenderer`.
     /// Requires the shape file and texture file paths.
     pub fn new(shape_file: &str, texture_file: &str) -> HeadlessRenderer {
-        pollster::block_on(HeadlessRenderer::async_new(shape_file, texture_file))
+        let resources = Rc::new(ResourceStore::open_nx(shape_file, texture_file).unwrap());
+
+        HeadlessRenderer::with_sources(resources.clone(), resources)
     }
-    async fn async_new(shape_file: &str, texture_file: &str) -> HeadlessRenderer {
+
+    /// Instantiate a `HeadlessRenderer` from the official Nx files of `tier` in
+    /// `resources_dir`. See [`ResourceStore::open_nx_tier`].
+    ///
+    /// # Panics
+    /// - The resource files can't be opened
+    pub fn with_tier(resources_dir: &Path, tier: ResourceTier) -> HeadlessRenderer {
+        let resources = Rc::new(ResourceStore::open_nx_tier(resources_dir, tier).unwrap());
+
+        HeadlessRenderer::with_sources(resources.clone(), resources)
+    }
+
+    /// Instantiate a `HeadlessRenderer` that reads from any resources,
+    /// e.g. a [`vee_resources::source::ArchResourceFile`] for both.
+    ///
+    /// # Panics
+    /// - See [`HeadlessRenderer::try_with_sources`]
+    pub fn with_sources(
+        textures: Rc<dyn TextureSource>,
+        shapes: Rc<dyn ShapeSource>,
+    ) -> HeadlessRenderer {
+        HeadlessRenderer::try_with_sources(textures, shapes).unwrap()
+    }
+
+    /// [`HeadlessRenderer::with_sources`], for when there might not be a GPU to render with.
+    ///
+    /// # Errors
+    /// - There's no adapter, or it lacks [`wgpu::DownlevelFlags::VIEW_FORMATS`]
+    /// - The device can't be created
+    pub fn try_with_sources(
+        textures: Rc<dyn TextureSource>,
+        shapes: Rc<dyn ShapeSource>,
+    ) -> Result<HeadlessRenderer, Box<dyn Error>> {
+        pollster::block_on(HeadlessRenderer::async_new(ResourceData {
+            textures,
+            shapes,
+        }))
+    }
+
+    /// Sets how 3D models are uploaded, see [`VertexLayout`].
+    pub fn set_vertex_layout(&mut self, vertex_layout: VertexLayout) {
+        self.vertex_layout = vertex_layout;
+    }
+
+    async fn async_new(resource_data: ResourceData) -> Result<HeadlessRenderer, Box<dyn Error>> {
         const SIZE: UVec2 = uvec2(512, 512);
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
@@ -92,11 +141,23 @@ impl HeadlessRenderer {
                 force_fallback_adapter: false,
             })
             .await
-            .unwrap();
+            .or(Err("Failed to find an appropriate adapter"))?;
+
+        let missing = REQUIRED_DOWNLEVEL_FLAGS - adapter.get_downlevel_capabilities().flags;
+        if !missing.is_empty() {
+            return Err(format!(
+                "The adapter ({}) doesn't support {missing:?}",
+                adapter.get_info().name
+            )
+            .into());
+        }
+
         let (device, queue) = adapter
-            .request_device(&DeviceDescriptor::default())
-            .await
-            .unwrap();
+            .request_device(&DeviceDescriptor {
+                required_features: compressed_texture_features(&adapter),
+                ..Default::default()
+            })
+            .await?;
 
         // let surface = instance.create_surface(window.clone()).unwrap();
         // let cap = surface.get_capabilities(&adapter);
@@ -147,102 +208,201 @@ impl HeadlessRenderer {
             label: Some("camera_bind_group"),
         });
 
-        let shape_header = ResourceShape::read(&mut File::open(shape_file).unwrap()).unwrap();
-        let texture_header = ResourceTexture::read(&mut File::open(texture_file).unwrap()).unwrap();
-        let shape_data = Rc::new(std::fs::read(shape_file).unwrap());
-        let texture_data = Rc::new(std::fs::read(texture_file).unwrap());
-
-        let resource_data = ResourceData {
-            shape_header,
-            texture_header,
-            shape_data,
-            texture_data,
-        };
-
-        HeadlessRenderer {
+        Ok(HeadlessRenderer {
             device,
             queue,
+            camera_buffer,
             camera_bgl,
             camera_bg,
             surface_fmt,
             depth_texture,
             resource_data,
+            vertex_layout: VertexLayout::default(),
+        })
+    }
+
+    /// Renders a head-and-shoulders icon of `char_info` over `background`, framed like
+    /// FFL's `makeIcon`.
+    ///
+    /// # Errors
+    /// - The character can't be built, see [`CharModel::new`]
+    /// - The rendered image can't be read back
+    pub fn render_icon(
+        &mut self,
+        char_info: &NxCharInfo,
+        width: u32,
+        height: u32,
+        background: wgpu::Color,
+    ) -> Result<RgbaImage, Box<dyn Error>> {
+        let size = uvec2(width, height);
+        let view_format = self.surface_fmt.add_srgb_suffix();
+
+        let render_texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.surface_fmt,
+            usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: Some("Render Texture"),
+            view_formats: &[view_format],
+        });
+        let texture_view = render_texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(view_format),
+            ..Default::default()
+        });
+
+        let depth_size = self.depth_texture.texture.size();
+        if (depth_size.width, depth_size.height) != (width, height) {
+            self.depth_texture =
+                TextureBundle::create_depth_texture(&self.device, &size, "depth_texture");
         }
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+
+        let mut char_model = CharModel::new(self, char_info, &mut encoder)?;
+
+        // getFaceCamera(): (0, 4.805, 57.553), raised to the head.
+        let head_y = char_model.head_transform.transform_point3(Vec3::ZERO).y * BODY_SCALE;
+        let target = Vec3::new(0.0, 4.805 * BODY_SCALE + head_y, 0.0);
+        let camera = Camera {
+            eye: target + Vec3::new(0.0, 0.0, 57.553 * BODY_SCALE),
+            target,
+            up: Vec3::Y,
+            aspect: width as f32 / height as f32,
+            fov_y_radians: 15.0f32.to_radians(),
+            znear: 50.0,
+            zfar: 1000.0,
+        };
+        let mut camera_uniform = CameraUniform::new();
+        camera_uniform.update_view_proj(&camera);
+        self.queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&[camera_uniform]),
+        );
+
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &texture_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(background),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_texture.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        char_model.render(self, &texture_view, &mut encoder);
+
+        // The render texture is BGRA.
+        let mut data = self.read_texture(&render_texture, encoder)?;
+        for pixel in data.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+
+        Ok(RgbaImage::from_raw(width, height, data).ok_or("Failed to create image buffer")?)
     }
 
     /// After rendering, this function will consume the encoder and output commands to the GPU.
     /// Requires a `TextureBundle` to render to.
-    #[allow(unused)]
+    ///
+    /// # Panics
+    /// - The texture can't be read back
     pub fn output_texture(
         &mut self,
         texture: &TextureBundle,
-        mut encoder: CommandEncoder,
+        encoder: CommandEncoder,
     ) -> DynamicImage {
-        pollster::block_on(async {
-            let u32_size = size_of::<u32>() as u32;
-            let output_buffer_size = wgpu::BufferAddress::from(
-                u32_size * texture.texture.size().width * texture.texture.size().height,
-            );
-            let output_buffer_desc = wgpu::BufferDescriptor {
-                size: output_buffer_size,
-                usage: wgpu::BufferUsages::COPY_DST
-                        // this tells `wpgu` that we want to read this buffer from the CPU
-                        | wgpu::BufferUsages::MAP_READ,
-                label: None,
-                mapped_at_creation: false,
-            };
-            let output_buffer = self.device.create_buffer(&output_buffer_desc);
-
-            encoder.copy_texture_to_buffer(
-                wgpu::TexelCopyTextureInfo {
-                    aspect: wgpu::TextureAspect::All,
-                    texture: &texture.texture,
-                    mip_level: 0,
-                    origin: wgpu::Origin3d::ZERO,
-                },
-                wgpu::TexelCopyBufferInfo {
-                    buffer: &output_buffer,
-                    layout: wgpu::TexelCopyBufferLayout {
-                        offset: 0,
-                        bytes_per_row: Some(u32_size * texture.texture.size().width),
-                        rows_per_image: Some(texture.texture.size().height),
-                    },
+        let size = texture.texture.size();
+        let data = self.read_texture(&texture.texture, encoder).unwrap();
+
+        DynamicImage::ImageRgba8(RgbaImage::from_raw(size.width, size.height, data).unwrap())
+    }
+
+    /// Submits `encoder`, then copies out the pixels of a 4 byte per pixel `texture`,
+    /// row by row.
+    fn read_texture(
+        &self,
+        texture: &wgpu::Texture,
+        mut encoder: CommandEncoder,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let size = texture.size();
+
+        // Rows of a texture copy must be aligned to 256 bytes.
+        let row_bytes = size.width * 4;
+        let padded_row_bytes = row_bytes.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let output_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            size: wgpu::BufferAddress::from(padded_row_bytes * size.height),
+            usage: wgpu::BufferUsages::COPY_DST
+                    // this tells `wpgu` that we want to read this buffer from the CPU
+                    | wgpu::BufferUsages::MAP_READ,
+            label: Some("Output Buffer"),
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                aspect: wgpu::TextureAspect::All,
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &output_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(size.height),
                 },
-                texture.texture.size(),
-            );
-
-            self.queue.submit(Some(encoder.finish()));
-
-            let image;
-            // We need to scope the mapping variables so that we can
-            // unmap the buffer
-            {
-                let buffer_slice = output_buffer.slice(..);
-
-                // NOTE: We have to create the mapping THEN device.poll() before await
-                // the future. Otherwise, the application will freeze.
-                let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
-                buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-                    tx.send(result).unwrap();
-                });
-                self.device.poll(wgpu::PollType::Wait).unwrap();
-                rx.receive().await.unwrap().unwrap();
-
-                let data = &buffer_slice.get_mapped_range()[..];
-
-                use image::{ImageBuffer, Rgba};
-                let buffer: RgbaImage = ImageBuffer::<Rgba<u8>, _>::from_raw(
-                    texture.texture.size().width,
-                    texture.texture.size().height,
-                    data.to_owned(),
-                )
-                .unwrap();
-                image = Some(DynamicImage::ImageRgba8(buffer));
-            }
-            output_buffer.unmap();
+            },
+            size,
+        );
 
-            image.unwrap()
-        })
+        self.queue.submit(Some(encoder.finish()));
+
+        let buffer_slice = output_buffer.slice(..);
+
+        // NOTE: We have to create the mapping THEN device.poll() before await
+        // the future. Otherwise, the application will freeze.
+        let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            tx.send(result).unwrap();
+        });
+        self.device.poll(wgpu::PollType::Wait)?;
+        pollster::block_on(rx.receive()).ok_or("The buffer mapping was dropped")??;
+
+        let mut data = Vec::with_capacity((row_bytes * size.height) as usize);
+        for row in buffer_slice
+            .get_mapped_range()
+            .chunks_exact(padded_row_bytes as usize)
+        {
+            data.extend_from_slice(&row[..row_bytes as usize]);
+        }
+        output_buffer.unmap();
+
+        Ok(data)
     }
 }
 
@@ -3,6 +3,11 @@
 //! implement `ProgramState` on your project's `State` method — that is the method
 //! that contains handles like `wgpu::Device`.
 //!
+//! Textures and shapes are read through `vee_resources::source`, so any resource file
+//! can be rendered from, including Miitomo's `AFLResHigh_2_3.dat` with its extra parts.
+//! A `vee_resources::ResourceStore` memory-maps the files and caches decoded parts, and can
+//! be returned as both sources.
+//!
 //! If you don't need to work in real time, you might want to just
 //! use this library's `HeadlessRenderer`. If you need more help
 //! integrating this library, try reading the source of `lightweight_viewer`
@@ -20,14 +25,11 @@
 //! use vee_wgpu::ProgramState;
 //! use vee_wgpu::texture::TextureBundle;
 //! use vee_parse::{BinRead, NxCharInfo};
-//! use vee_resources::shape::ResourceShape;
-//! use vee_resources::tex::ResourceTexture;
+//! use vee_resources::source::{NxShapeFile, NxTextureFile, ShapeSource, TextureSource};
 //!
 //! pub struct ResourceData {
-//!     pub(crate) texture_header: ResourceTexture,
-//!     pub(crate) shape_header: ResourceShape,
-//!     pub(crate) texture_data: Rc<Vec<u8>>,
-//!     pub(crate) shape_data: Rc<Vec<u8>>,
+//!     pub(crate) textures: Rc<NxTextureFile>,
+//!     pub(crate) shapes: Rc<NxShapeFile>,
 //! }
 //!
 //! pub struct State {
@@ -67,20 +69,12 @@
 //!         &self.depth_texture
 //!     }
 //!
-//!     fn texture_header(&self) -> ResourceTexture {
-//!         self.resource_data.texture_header
-//!     }
-//!
-//!     fn shape_header(&self) -> ResourceShape {
-//!         self.resource_data.shape_header
+//!     fn texture_source(&self) -> Rc<dyn TextureSource> {
+//!         self.resource_data.textures.clone()
 //!     }
 //!
-//!     fn texture_data(&self) -> Rc<Vec<u8>> {
-//!         self.resource_data.texture_data.clone()
-//!     }
-//!
-//!     fn shape_data(&self) -> Rc<Vec<u8>> {
-//!         self.resource_data.shape_data.clone()
+//!     fn shape_source(&self) -> Rc<dyn ShapeSource> {
+//!         self.resource_data.shapes.clone()
 //!     }
 //! }
 //!
@@ -98,7 +92,7 @@
 //!         let mut encoder = self.device.create_command_encoder(&Default::default());
 //!
 //!         let char = NxCharInfo::read(&mut BufReader::new(File::open(&self.char)?))?;
-//!         let mut char = CharModel::new(self, &char, &mut encoder);
+//!         let mut char = CharModel::new(self, &char, &mut encoder)?;
 //!         char.render(self, &texture_view, &mut encoder);
 //!
 //!         let command_buffer = encoder.finish();
@@ -114,16 +108,26 @@
 use bytemuck::cast_slice;
 use std::rc::Rc;
 use texture::TextureBundle;
-use vee_models::model::{DrawableTexture, GenericModel3d, Model2d, Vertex};
-use vee_resources::shape::ResourceShape;
-use vee_resources::tex::ResourceTexture;
+use vee_models::model::{
+    DrawableTexture, GenericModel3d, Model2d, PackedVertex, Vertex, VertexParams,
+};
+use vee_resources::source::{ShapeSource, TextureSource};
+use vee_resources::tex::ResourceTextureFormat;
 use wgpu::{BlendState, CommandEncoder, TextureView};
-use wgpu::{PipelineCompilationOptions, TexelCopyTextureInfo, include_wgsl, util::DeviceExt};
+use wgpu::{PipelineCompilationOptions, include_wgsl, util::DeviceExt};
 
 pub mod draw;
 pub mod headless;
 pub mod texture;
 
+/// The compressed texture features `adapter` supports.
+/// Request these when creating a device, so textures can skip decoding on the CPU.
+#[must_use]
+pub fn compressed_texture_features(adapter: &wgpu::Adapter) -> wgpu::Features {
+    adapter.features()
+        & (wgpu::Features::TEXTURE_COMPRESSION_BC | wgpu::Features::TEXTURE_COMPRESSION_ASTC)
+}
+
 /// A 3d model.
 pub type Model3d = GenericModel3d<TextureBundle>;
 
@@ -140,23 +144,60 @@ struct CharShapeUniform {
 
 /// I got tired of reimplementing this for every uniform buffer.
 trait UniformBuffer {
-    const ATTRIBS: [wgpu::VertexAttribute; 3];
+    const ATTRIBS: &'static [wgpu::VertexAttribute];
 
     fn desc() -> wgpu::VertexBufferLayout<'static>;
 }
 impl UniformBuffer for Vertex {
-    const ATTRIBS: [wgpu::VertexAttribute; 3] =
-        wgpu::vertex_attr_array![0 => Float16x4, 1 => Float16x2, 2 => Float32x3];
+    const ATTRIBS: &'static [wgpu::VertexAttribute] =
+        &wgpu::vertex_attr_array![0 => Float16x4, 1 => Float16x2, 2 => Float32x3];
 
     fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             array_stride: size_of::<Vertex>() as wgpu::BufferAddress,
             step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &Self::ATTRIBS,
+            attributes: Self::ATTRIBS,
+        }
+    }
+}
+impl UniformBuffer for PackedVertex {
+    const ATTRIBS: &'static [wgpu::VertexAttribute] =
+        &wgpu::vertex_attr_array![0 => Float16x4, 1 => Float16x2, 2 => Uint32];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<PackedVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: Self::ATTRIBS,
+        }
+    }
+}
+
+/// The second vertex buffer of 3D models, read alongside either [`VertexLayout`].
+impl UniformBuffer for VertexParams {
+    const ATTRIBS: &'static [wgpu::VertexAttribute] =
+        &wgpu::vertex_attr_array![3 => Snorm8x4, 4 => Unorm8x4];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<VertexParams>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: Self::ATTRIBS,
         }
     }
 }
 
+/// How 3D model vertices are laid out in their vertex buffers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VertexLayout {
+    /// [`Vertex`], with normals as three `f32`s.
+    #[default]
+    Unpacked,
+    /// [`PackedVertex`], with normals packed into a `u32` and unpacked in the shader.
+    /// Uses a third less memory, for large batch renders.
+    Packed,
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 struct TextureTransformUniform {
@@ -165,7 +206,9 @@ struct TextureTransformUniform {
     channel_replacements_g: [f32; 4],
     channel_replacements_b: [f32; 4],
     texture_type: u32,
-    pad: [u32; 3],
+    srgb_decode: u32,
+    swap_red_blue: u32,
+    pad: u32,
 }
 
 /// `wgpu` requires a lot of state.
@@ -178,10 +221,21 @@ pub trait ProgramState {
     fn surface_fmt(&self) -> wgpu::TextureFormat;
     fn depth_texture(&self) -> &TextureBundle;
 
-    fn texture_header(&self) -> ResourceTexture;
-    fn shape_header(&self) -> ResourceShape;
-    fn texture_data(&self) -> Rc<Vec<u8>>;
-    fn shape_data(&self) -> Rc<Vec<u8>>;
+    /// Where textures are read from, e.g. a [`vee_resources::source::NxTextureFile`].
+    fn texture_source(&self) -> Rc<dyn TextureSource>;
+    /// Where shapes are read from, e.g. a [`vee_resources::source::NxShapeFile`].
+    fn shape_source(&self) -> Rc<dyn ShapeSource>;
+
+    /// How 3D models are uploaded. Defaults to [`VertexLayout::Unpacked`].
+    fn vertex_layout(&self) -> VertexLayout {
+        VertexLayout::Unpacked
+    }
+
+    /// Texture formats that are uploaded without decoding them first.
+    /// Defaults to whatever the device has features for, see [`compressed_texture_features`].
+    fn compressed_formats(&self) -> Vec<ResourceTextureFormat> {
+        texture::compressed_formats(self.device().features())
+    }
 
     fn draw_texture(
         &mut self,
@@ -192,6 +246,7 @@ pub trait ProgramState {
         self.draw_model_2d(&mut tex.model_2d(), view, encoder)
     }
 
+    /// Draws `mesh` over `view`. Its buffers, texture and pipeline are created on every call.
     fn draw_model_2d(
         &mut self,
         mesh: &mut Model2d,
@@ -218,38 +273,12 @@ pub trait ProgramState {
                 usage: wgpu::BufferUsages::INDEX,
             });
 
-        let shape_texture_rgba = mesh.tex.to_rgba8();
-        let shape_texture_dimensions = shape_texture_rgba.dimensions();
-        let shape_texture_size = wgpu::Extent3d {
-            width: shape_texture_dimensions.0,
-            height: shape_texture_dimensions.1,
-            depth_or_array_layers: 1,
-        };
-        let shape_diffuse_texture = self.device().create_texture(&wgpu::TextureDescriptor {
-            size: shape_texture_size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: self.surface_fmt(),
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            label: Some("diffuse_texture"),
-            view_formats: &[self.surface_fmt()],
-        });
-
-        self.queue().write_texture(
-            TexelCopyTextureInfo {
-                texture: &shape_diffuse_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            &shape_texture_rgba,
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * shape_texture_dimensions.0),
-                rows_per_image: Some(shape_texture_dimensions.1),
-            },
-            shape_texture_size,
+        let (shape_diffuse_texture, sampling) = texture::upload_model_texture(
+            &self.device(),
+            &self.queue(),
+            &mesh.tex,
+            self.surface_fmt(),
+            self.texture_source().color_space(),
         );
 
         let shape_diffuse_texture_view =
@@ -311,6 +340,8 @@ pub trait ProgramState {
             channel_replacements_g: mesh.modulation.channels[1],
             channel_replacements_b: mesh.modulation.channels[2],
             texture_type: Into::<u8>::into(mesh.modulation.mode).into(),
+            srgb_decode: sampling.srgb_decode.into(),
+            swap_red_blue: sampling.swap_red_blue.into(),
             pad: Default::default(),
         };
 
@@ -444,11 +475,30 @@ pub trait ProgramState {
         view: &TextureView,
         encoder: &mut CommandEncoder,
     ) {
+        let vertex_layout = self.vertex_layout();
+
+        let packed_vertices: Vec<PackedVertex>;
+        let vertices: &[u8] = match vertex_layout {
+            VertexLayout::Unpacked => cast_slice(&mesh.vertices),
+            VertexLayout::Packed => {
+                packed_vertices = mesh.packed_vertices();
+                cast_slice(&packed_vertices)
+            }
+        };
+
         let vertex_buffer = self
             .device()
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("Vertex Buffer"),
-                contents: cast_slice(&mesh.vertices),
+                contents: vertices,
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+        let params_buffer = self
+            .device()
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Vertex Params Buffer"),
+                contents: cast_slice(&mesh.vertex_params()),
                 usage: wgpu::BufferUsages::VERTEX,
             });
 
@@ -550,6 +600,11 @@ pub trait ProgramState {
             })
         });
 
+        let (vertex_entry_point, vertex_buffer_layout) = match vertex_layout {
+            VertexLayout::Unpacked => ("vs_main", Vertex::desc()),
+            VertexLayout::Packed => ("vs_main_packed", PackedVertex::desc()),
+        };
+
         let render_pipeline_layout =
             self.device()
                 .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -564,8 +619,8 @@ pub trait ProgramState {
                     layout: Some(&render_pipeline_layout),
                     vertex: wgpu::VertexState {
                         module: &shader_module,
-                        entry_point: Some("vs_main"),
-                        buffers: &[Vertex::desc()],
+                        entry_point: Some(vertex_entry_point),
+                        buffers: &[vertex_buffer_layout, VertexParams::desc()],
                         compilation_options: PipelineCompilationOptions::default(),
                     },
                     fragment: Some(wgpu::FragmentState {
@@ -639,6 +694,7 @@ pub trait ProgramState {
             render_pass.set_bind_group(1, &char_shape_bind_group, &[]);
             render_pass.set_bind_group(2, &projected_texture_bind_group, &[]);
             render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, params_buffer.slice(..));
             render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
 
             render_pass.draw_indexed(0..mesh.indices.len().try_into().unwrap(), 0, 0..1);
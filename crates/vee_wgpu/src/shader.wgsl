@@ -4,6 +4,8 @@ struct TransformUniform {
     color_g: vec4<f32>,
     color_b: vec4<f32>,
     modulation_mode: u32,
+    srgb_decode: u32,
+    swap_red_blue: u32,
 };
 
 @group(1) @binding(0) // 1.
@@ -36,7 +38,7 @@ var s_diffuse: sampler;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let color: vec4<f32> = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    let color: vec4<f32> = normalize_sample(textureSample(t_diffuse, s_diffuse, in.tex_coords));
 
     // if color.a == 0.0 {
     //    discard;
@@ -69,6 +71,28 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     }
 }
 
+// Compressed textures are sampled as-is, so make them read
+// the same as textures decoded on the CPU.
+fn normalize_sample(sampled: vec4f) -> vec4f {
+    var color = sampled;
+
+    if mvp.swap_red_blue != 0 {
+        color = color.bgra;
+    }
+    if mvp.srgb_decode != 0 {
+        color = vec4f(srgb_to_linear(color.rgb), color.a);
+    }
+
+    return color;
+}
+
+fn srgb_to_linear(srgb: vec3f) -> vec3f {
+    let low = srgb / 12.92;
+    let high = pow((srgb + 0.055) / 1.055, vec3f(2.4));
+
+    return select(high, low, srgb <= vec3f(0.04045));
+}
+
 // Two trivial cases
 fn modulate_single_color(color: vec4f) -> vec4f {
     return mvp.color_r;
@@ -24,27 +24,87 @@ struct VertexInput {
     @location(2) normal: vec3<f32>,
 }
 
+struct PackedVertexInput {
+    @location(0) position: vec4<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) normal: u32,
+}
+
+// The second vertex buffer, zeroed for parts without tangents.
+struct VertexParamsInput {
+    @location(3) tangent: vec4<f32>,
+    @location(4) color_param: vec4<f32>,
+}
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
     @location(1) world_normal: vec3<f32>,
     @location(2) world_position: vec3<f32>,
+    @location(3) world_tangent: vec3<f32>,
+    @location(4) specular_mix: f32,
 }
 
 @vertex
 fn vs_main(
     model: VertexInput,
+    params: VertexParamsInput,
     // instance: InstanceInput,
 ) -> VertexOutput {
+    return transform_vertex(model.position, model.tex_coords, model.normal, params);
+}
+
+// Same as `vs_main`, but the normal is still packed as Snorm [10, 10, 10, 2].
+@vertex
+fn vs_main_packed(
+    model: PackedVertexInput,
+    params: VertexParamsInput,
+) -> VertexOutput {
+    return transform_vertex(model.position, model.tex_coords, unpack_snorm_10_10_10(model.normal), params);
+}
+
+fn transform_vertex(position: vec4<f32>, tex_coords: vec2<f32>, normal: vec3<f32>, params: VertexParamsInput) -> VertexOutput {
     var out: VertexOutput;
-    out.tex_coords = model.tex_coords;
-    out.world_normal = model.normal;
-    var world_position: vec4<f32> = vec4<f32>((char_shape.scale * model.position.xyz) + char_shape.position, 1.0);
+    out.tex_coords = tex_coords;
+    out.world_normal = normal;
+    out.world_tangent = params.tangent.xyz;
+    out.specular_mix = params.color_param.r;
+    var world_position: vec4<f32> = vec4<f32>((char_shape.scale * position.xyz) + char_shape.position, 1.0);
     out.world_position = world_position.xyz;
     out.clip_position = camera.view_proj * world_position;
     return out;
 }
 
+// Wgsl doesn't have a [10, 10, 10, 2] unpacking method.
+// Sign extend each 10 bit component, discarding w.
+fn unpack_snorm_10_10_10(packed: u32) -> vec3<f32> {
+    let x = bitcast<i32>(packed << 22u) >> 22u;
+    let y = bitcast<i32>(packed << 12u) >> 22u;
+    let z = bitcast<i32>(packed << 2u) >> 22u;
+
+    return vec3<f32>(vec3<i32>(x, y, z)) / 511.0;
+}
+
+// FFL's default light, and a camera in front of the face.
+const LIGHT_DIR: vec3<f32> = vec3<f32>(-0.4531539, 0.4226183, 0.7848578);
+const VIEW_DIR: vec3<f32> = vec3<f32>(0.0, 0.0, 1.0);
+const HIGHLIGHT_POWER: f32 = 40.0;
+const HIGHLIGHT_STRENGTH: f32 = 0.25;
+
+// Anisotropic highlight along the tangent, Kajiya-Kay style.
+// Zero for parts without tangents, which is everything but hair.
+fn hair_highlight(in: VertexOutput) -> f32 {
+    if in.specular_mix == 0.0 || dot(in.world_tangent, in.world_tangent) == 0.0 {
+        return 0.0;
+    }
+
+    let half_dir = normalize(LIGHT_DIR + VIEW_DIR);
+    let dot_th = dot(normalize(in.world_tangent), half_dir);
+    let sin_th = sqrt(max(1.0 - dot_th * dot_th, 0.0));
+
+    return in.specular_mix * HIGHLIGHT_STRENGTH * pow(sin_th, HIGHLIGHT_POWER);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
@@ -58,7 +118,7 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
        discard;
     }
 
-    return color;
+    return vec4<f32>(color.rgb + hair_highlight(in), color.a);
 
 }
 
@@ -71,5 +131,5 @@ fn fs_color_only(in: VertexOutput) -> @location(0) vec4<f32> {
         discard;
     }
 
-    return color;
+    return vec4<f32>(color.rgb + hair_highlight(in), color.a);
 }
@@ -1,11 +1,14 @@
 //! Holding texture data in GPU VRAM.
 
+use std::borrow::Cow;
 use std::error::Error;
 use std::fmt::Debug;
 
 use glam::UVec2;
-use image::GenericImageView;
-use wgpu::TextureFormat;
+use vee_models::model::ModelTexture;
+use vee_resources::color::ColorSpace;
+use vee_resources::tex::ResourceTextureFormat;
+use wgpu::{AstcBlock, AstcChannel, TextureFormat};
 
 /// Contains pointers to the texture in VRAM, a view of the texture, and a sampler of the texture.
 /// These are often stored together during rendering.
@@ -40,8 +43,25 @@ impl TextureBundle {
         img: &image::DynamicImage,
         label: Option<&str>,
     ) -> Result<Self, Box<dyn Error>> {
-        let rgba = img.to_rgba8();
-        let dimensions = img.dimensions();
+        Self::from_mip_chain(device, queue, &[img.to_rgba8()], label)
+    }
+
+    /// Uploads a texture along with its mip levels, largest first.
+    /// Each level should be half the size of the last, like
+    /// [`vee_resources::tex::TextureElement::get_mip_chain`] returns.
+    ///
+    /// # Errors
+    /// - `levels` is empty
+    pub fn from_mip_chain(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        levels: &[image::RgbaImage],
+        label: Option<&str>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let dimensions = levels
+            .first()
+            .ok_or("No mip levels to upload.")?
+            .dimensions();
 
         let size = wgpu::Extent3d {
             width: dimensions.0,
@@ -51,7 +71,7 @@ impl TextureBundle {
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label,
             size,
-            mip_level_count: 1,
+            mip_level_count: u32::try_from(levels.len())?,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: TextureFormat::Rgba8UnormSrgb,
@@ -59,21 +79,25 @@ impl TextureBundle {
             view_formats: &[],
         });
 
-        queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                aspect: wgpu::TextureAspect::All,
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &rgba,
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
-            },
-            size,
-        );
+        for (rgba, mip_level) in levels.iter().zip(0..) {
+            let dimensions = rgba.dimensions();
+
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                rgba,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * dimensions.0),
+                    rows_per_image: Some(dimensions.1),
+                },
+                size.mip_level_size(mip_level, wgpu::TextureDimension::D2),
+            );
+        }
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
@@ -82,7 +106,7 @@ impl TextureBundle {
             address_mode_w: wgpu::AddressMode::ClampToEdge,
             mag_filter: wgpu::FilterMode::Linear,
             min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Linear,
             ..Default::default()
         });
 
@@ -222,3 +246,156 @@ impl TextureBundle {
         }
     }
 }
+
+/// The resource formats the GPU can sample, given its enabled `features`.
+pub(crate) fn compressed_formats(features: wgpu::Features) -> Vec<ResourceTextureFormat> {
+    let mut formats = Vec::new();
+
+    if features.contains(wgpu::Features::TEXTURE_COMPRESSION_BC) {
+        formats.extend([
+            ResourceTextureFormat::Bc4,
+            ResourceTextureFormat::Bc5,
+            ResourceTextureFormat::Bc7,
+        ]);
+    }
+    if features.contains(wgpu::Features::TEXTURE_COMPRESSION_ASTC) {
+        formats.push(ResourceTextureFormat::Astc4x4);
+    }
+
+    formats
+}
+
+/// What the shader has to do to a sampled texture so it reads the same
+/// as a texture decoded on the CPU.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct TextureSampling {
+    /// The format has no sRGB variant, but the CPU path would have had one.
+    pub srgb_decode: bool,
+    /// The CPU path writes `Bgra` pixels, which come out swapped on non-`Bgra` surfaces.
+    pub swap_red_blue: bool,
+}
+
+/// Uploads the texture of a 2D model, in the same format as the surface
+/// if it's decoded, or in its own block compressed format otherwise.
+///
+/// Textures from `Linear` resource files are already linear, so they're uploaded without
+/// an sRGB format (or decode in the shader), and sampled as they are.
+pub(crate) fn upload_model_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &ModelTexture,
+    surface_format: TextureFormat,
+    color_space: ColorSpace,
+) -> (wgpu::Texture, TextureSampling) {
+    let srgb = surface_format.is_srgb() && color_space == ColorSpace::Srgb;
+    let surface_format = if srgb {
+        surface_format
+    } else {
+        surface_format.remove_srgb_suffix()
+    };
+
+    let (format, data, (width, height), bytes_per_row, rows_per_image, sampling) = match texture {
+        ModelTexture::Image(image) => {
+            let rgba = image.to_rgba8();
+            let dimensions = rgba.dimensions();
+
+            (
+                surface_format,
+                Cow::Owned(rgba.into_raw()),
+                dimensions,
+                4 * dimensions.0,
+                dimensions.1,
+                TextureSampling::default(),
+            )
+        }
+        ModelTexture::Shared(image) => {
+            let dimensions = image.dimensions();
+
+            (
+                surface_format,
+                Cow::Borrowed(image.as_raw().as_slice()),
+                dimensions,
+                4 * dimensions.0,
+                dimensions.1,
+                TextureSampling::default(),
+            )
+        }
+        ModelTexture::Compressed(texture) => {
+            let (format, srgb_decode) = match texture.format {
+                ResourceTextureFormat::Bc4 => (TextureFormat::Bc4RUnorm, srgb),
+                ResourceTextureFormat::Bc5 => (TextureFormat::Bc5RgUnorm, srgb),
+                ResourceTextureFormat::Bc7 if srgb => (TextureFormat::Bc7RgbaUnormSrgb, false),
+                ResourceTextureFormat::Bc7 => (TextureFormat::Bc7RgbaUnorm, false),
+                ResourceTextureFormat::Astc4x4 => (
+                    TextureFormat::Astc {
+                        block: AstcBlock::B4x4,
+                        channel: if srgb {
+                            AstcChannel::UnormSrgb
+                        } else {
+                            AstcChannel::Unorm
+                        },
+                    },
+                    false,
+                ),
+                ResourceTextureFormat::R
+                | ResourceTextureFormat::Rg
+                | ResourceTextureFormat::Rgba => {
+                    unreachable!("Uncompressed textures are always decoded")
+                }
+            };
+            let (blocks_x, blocks_y) = texture.blocks();
+            let (_, bytes_per_block) = texture.format.block_layout();
+
+            let swap_red_blue = !matches!(
+                surface_format,
+                TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb
+            );
+
+            (
+                format,
+                Cow::Borrowed(texture.data.as_slice()),
+                (texture.width, texture.height),
+                blocks_x * bytes_per_block,
+                blocks_y,
+                TextureSampling {
+                    srgb_decode,
+                    swap_red_blue,
+                },
+            )
+        }
+    };
+
+    let size = wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        label: Some("diffuse_texture"),
+        view_formats: &[format],
+    });
+
+    queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        &data,
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(bytes_per_row),
+            rows_per_image: Some(rows_per_image),
+        },
+        size,
+    );
+
+    (texture, sampling)
+}
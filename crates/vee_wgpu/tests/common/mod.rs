@@ -8,13 +8,14 @@ use std::{
     str::FromStr,
 };
 use vee_parse::{BinRead, NxCharInfo};
-use vee_resources::shape::ResourceShape;
-use vee_resources::tex::ResourceTexture;
+use vee_resources::source::{NxShapeFile, NxTextureFile};
 use vee_wgpu::texture::TextureBundle;
 use vee_wgpu::{ProgramState, headless::HeadlessRenderer};
 use wgpu::CommandEncoder;
 
 pub mod ffl_runner;
+#[allow(dead_code)] // Only the tests on placeholder resources use these.
+pub mod synthetic;
 
 #[allow(unused)]
 pub struct Everything {
@@ -22,10 +23,8 @@ pub struct Everything {
     pub encoder: CommandEncoder,
     pub texture: TextureBundle,
     pub char: NxCharInfo,
-    pub shape_header: ResourceShape,
-    pub texture_header: ResourceTexture,
-    pub shape_data: Vec<u8>,
-    pub texture_data: Vec<u8>,
+    pub shapes: NxShapeFile,
+    pub textures: NxTextureFile,
 }
 
 pub fn setup_renderer_linear_color() -> Everything {
@@ -53,20 +52,16 @@ pub fn setup_renderer_linear_color() -> Everything {
     .unwrap();
     let char = NxCharInfo::read(&mut char_info).unwrap();
 
-    let shape_header = ResourceShape::read(&mut File::open(&shape_file_path).unwrap()).unwrap();
-    let texture_header = ResourceTexture::read(&mut File::open(&tex_file_path).unwrap()).unwrap();
-    let shape_data = fs::read(&shape_file_path).unwrap();
-    let texture_data = fs::read(&tex_file_path).unwrap();
+    let shapes = NxShapeFile::new(fs::read(&shape_file_path).unwrap()).unwrap();
+    let textures = NxTextureFile::new(fs::read(&tex_file_path).unwrap()).unwrap();
 
     Everything {
         render: renderer,
         encoder,
         texture,
         char,
-        shape_header,
-        texture_header,
-        shape_data,
-        texture_data,
+        shapes,
+        textures,
     }
 }
 
@@ -95,20 +90,16 @@ pub fn setup_renderer() -> Everything {
     .unwrap();
     let char = NxCharInfo::read(&mut char_info).unwrap();
 
-    let shape_header = ResourceShape::read(&mut File::open(&shape_file_path).unwrap()).unwrap();
-    let texture_header = ResourceTexture::read(&mut File::open(&tex_file_path).unwrap()).unwrap();
-    let shape_data = fs::read(&shape_file_path).unwrap();
-    let texture_data = fs::read(&tex_file_path).unwrap();
+    let shapes = NxShapeFile::new(fs::read(&shape_file_path).unwrap()).unwrap();
+    let textures = NxTextureFile::new(fs::read(&tex_file_path).unwrap()).unwrap();
 
     Everything {
         render: renderer,
         encoder,
         texture,
         char,
-        shape_header,
-        texture_header,
-        shape_data,
-        texture_data,
+        shapes,
+        textures,
     }
 }
 
@@ -0,0 +1,36 @@
+//! Placeholder resources and a character to build from them, so tests run without dumped files.
+
+use std::io::Cursor;
+use vee_models::char_model::CharResources;
+use vee_parse::generic::{AsGenericChar, FromGenericChar};
+use vee_parse::{BinRead, CtrStoreData, NxCharInfo};
+use vee_resources::color::CharColors;
+use vee_resources::source::{NxShapeFile, NxTextureFile};
+use vee_resources::synthetic;
+
+const J0_FFSD: &[u8] = include_bytes!("../../../../resources_here/j0.ffsd");
+
+pub struct Synthetic {
+    pub char: NxCharInfo,
+    pub colors: CharColors,
+    pub shapes: NxShapeFile,
+    pub textures: NxTextureFile,
+}
+
+impl Synthetic {
+    pub fn new() -> Synthetic {
+        let char = CtrStoreData::read(&mut Cursor::new(J0_FFSD)).unwrap();
+        let char = NxCharInfo::from_generic(char.as_generic().unwrap());
+
+        Synthetic {
+            colors: CharColors::nx(&char),
+            char,
+            shapes: synthetic::shape_file(),
+            textures: synthetic::texture_file(),
+        }
+    }
+
+    pub fn resources(&self) -> CharResources<'_> {
+        CharResources::new(&self.shapes, &self.textures)
+    }
+}
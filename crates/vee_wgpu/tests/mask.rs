@@ -1,6 +1,7 @@
 use approx::assert_relative_eq;
 use common::{get_mask_data, setup_renderer_linear_color};
 use vee_models::building::{MaskModels, mask_texture_meshes};
+use vee_models::expression::Expression;
 use vee_wgpu::ProgramState;
 
 mod common;
@@ -9,7 +10,7 @@ mod common;
 fn render_mask() {
     let mut e = setup_renderer_linear_color();
 
-    let shapes = mask_texture_meshes(&e.char, &e.texture_header, &e.texture_data);
+    let shapes = mask_texture_meshes(&e.char, Expression::Normal, &e.textures).unwrap();
 
     for mut shape in shapes.all() {
         e.render
@@ -30,7 +31,7 @@ fn render_mask() {
 fn render_mask_eyebrows() {
     let mut e = setup_renderer_linear_color();
 
-    let meshes = mask_texture_meshes(&e.char, &e.texture_header, &e.texture_data);
+    let meshes = mask_texture_meshes(&e.char, Expression::Normal, &e.textures).unwrap();
 
     if meshes.right_brow.is_none() {
         return;
@@ -62,11 +63,11 @@ fn mask_mtx() {
         right_eye,
         left_brow,
         right_brow,
-        left_mustache,
-        right_mustache,
+        left_mustache: _,
+        right_mustache: _,
         mouth,
         mole: _,
-    } = mask_texture_meshes(&e.char, &e.texture_header, &e.texture_data);
+    } = mask_texture_meshes(&e.char, Expression::Normal, &e.textures).unwrap();
 
     let comparisons = [
         (mouth, test_mask.mouth),
@@ -0,0 +1,175 @@
+//! Builds and renders masks from placeholder resources, so these run without dumped files.
+
+use common::synthetic::Synthetic;
+use glam::uvec2;
+use image::RgbaImage;
+use std::error::Error;
+use std::rc::Rc;
+use vee_models::Model2d;
+use vee_models::animation::{self, FaceState};
+use vee_models::building::mask_texture_meshes;
+use vee_models::char_model;
+use vee_models::expression::{Expression, ExpressionSet};
+use vee_resources::source::{NxTextureFile, TextureSource};
+use vee_resources::synthetic;
+use vee_resources::tex::TexturePart;
+use vee_wgpu::texture::TextureBundle;
+use vee_wgpu::{ProgramState, headless::HeadlessRenderer};
+
+#[allow(dead_code)] // The helpers for dumped resources are only used by `mask.rs`.
+mod common;
+
+#[test]
+fn mask_meshes() {
+    let s = Synthetic::new();
+
+    let meshes = mask_texture_meshes(&s.char, Expression::Normal, &s.textures).unwrap();
+
+    assert!(meshes.all().iter().all(|model| model.indices.len() == 6));
+}
+
+#[test]
+fn missing_mouth_texture() {
+    /// The synthetic textures without any mouths.
+    struct NoMouths(NxTextureFile);
+
+    impl TextureSource for NoMouths {
+        fn texture_count(&self, part: TexturePart) -> usize {
+            self.0.texture_count(part)
+        }
+
+        fn texture(
+            &self,
+            part: TexturePart,
+            index: usize,
+        ) -> Result<Option<RgbaImage>, Box<dyn Error>> {
+            if part == TexturePart::Mouth {
+                return Ok(None);
+            }
+
+            self.0.texture(part, index)
+        }
+    }
+
+    let s = Synthetic::new();
+    let textures = NoMouths(s.textures);
+
+    let error = mask_texture_meshes(&s.char, Expression::Normal, &textures)
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("mouth"));
+}
+
+#[test]
+fn char_model_data() {
+    let s = Synthetic::new();
+
+    let data = char_model::build(&s.char, &s.colors, s.resources(), Expression::Normal).unwrap();
+
+    // Everything is built on the CPU, with the mask drawn from the same quads as on its own.
+    let mask = data.mask.texture.as_ref().unwrap();
+    assert_eq!(
+        mask.draws.len(),
+        mask_texture_meshes(&s.char, Expression::Normal, &s.textures)
+            .unwrap()
+            .all()
+            .len()
+    );
+    assert!(data.hair.is_some());
+    assert!(data.parts().all(|part| !part.vertices.is_empty()));
+}
+
+#[test]
+fn expression_masks() {
+    let s = Synthetic::new();
+    // Listed in `FFLExpression` order, which the masks come back in.
+    let expressions = [
+        Expression::Surprise,
+        Expression::Blink,
+        Expression::WinkLeft,
+    ];
+
+    let masks = char_model::expression_masks(
+        &s.char,
+        &s.colors,
+        s.resources(),
+        expressions.into_iter().collect::<ExpressionSet>(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        masks
+            .iter()
+            .map(|(expression, _)| *expression)
+            .collect::<Vec<_>>(),
+        expressions
+    );
+    assert!(masks.iter().all(|(_, mask)| !mask.draws.is_empty()));
+}
+
+#[test]
+fn animated_mask() {
+    let s = Synthetic::new();
+    let matrices = |draws: Vec<Model2d>| {
+        draws
+            .into_iter()
+            .map(|draw| (draw.label.unwrap(), draw.mvp_matrix))
+            .collect::<Vec<_>>()
+    };
+    let posed = |state| {
+        matrices(
+            animation::mask_draws(&s.char, &s.colors, s.resources(), Expression::Normal, state)
+                .unwrap(),
+        )
+    };
+    // Height on the mask of the first draw of `part`.
+    let height = |draws: &[(String, glam::Mat4)], part: &str| {
+        let (_, matrix) = draws.iter().find(|(label, _)| label == part).unwrap();
+        matrix.y_axis.truncate().length()
+    };
+
+    // At rest, the animated mask is the expression's own.
+    let rest = posed(FaceState::default());
+    assert_eq!(
+        rest,
+        matrices(
+            char_model::mask_draws(&s.char, &s.colors, s.resources(), Expression::Normal).unwrap()
+        )
+    );
+
+    // Eyes squash while closing, and the mouth grows with the voice.
+    let talking = posed(FaceState {
+        eyes_closed: 0.5,
+        mouth_open: 0.5,
+    });
+    let shouting = posed(FaceState {
+        eyes_closed: 0.5,
+        mouth_open: 1.0,
+    });
+    assert!(height(&talking, "Eye") < height(&rest, "Eye"));
+    assert!(height(&talking, "Mouth") < height(&shouting, "Mouth"));
+}
+
+#[test]
+#[ignore = "needs a GPU adapter with DownlevelFlags::VIEW_FORMATS"]
+fn render_mask() {
+    let s = Synthetic::new();
+    let mut render = HeadlessRenderer::with_sources(
+        Rc::new(synthetic::texture_file()),
+        Rc::new(synthetic::shape_file()),
+    );
+    let mut encoder = render.device().create_command_encoder(&Default::default());
+    let texture =
+        TextureBundle::create_texture_linear_color(&render.device(), &uvec2(256, 256), "tex");
+
+    for mut model in mask_texture_meshes(&s.char, Expression::Normal, &s.textures)
+        .unwrap()
+        .all()
+    {
+        render.draw_model_2d(&mut model, &texture.view, &mut encoder);
+    }
+
+    let image = render.output_texture(&texture, encoder);
+
+    assert_eq!((image.width(), image.height()), (256, 256));
+}
@@ -1,6 +1,5 @@
 use std::sync::Arc;
 
-use egui_winit::EventResponse;
 use winit::{
     application::ApplicationHandler,
     event::WindowEvent,
@@ -33,7 +32,7 @@ impl ApplicationHandler for App {
     fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
         let state = self.state.as_mut().unwrap();
 
-        let consumed = state
+        let _consumed = state
             .egui
             .winit_state
             .on_window_event(&state.window, &event);
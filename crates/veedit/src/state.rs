@@ -7,14 +7,15 @@ use nest_struct::nest_struct;
 use std::rc::Rc;
 use std::sync::{Mutex, MutexGuard, OnceLock};
 use std::{f32::consts::FRAC_PI_2, fs::File, sync::Arc};
-use vfl::impl_wgpu::ProgramState;
 use vfl::impl_wgpu::draw::CharModel;
 use vfl::impl_wgpu::texture::TextureBundle;
-use vfl::parse::generic::{AsGenericChar, FromGenericChar};
-use vfl::parse::{BinRead, CtrStoreData, NtrCharData, NtrStoreData, NxCharInfo};
-use vfl::res::shape::ResourceShape;
-use vfl::res::tex::ResourceTexture;
-use wgpu::{Backends, Features, util::DeviceExt};
+use vfl::impl_wgpu::{ProgramState, compressed_texture_features};
+use vfl::parse::generic::{AsGenericChar, FromGenericChar, GenericColor};
+use vfl::parse::{BinRead, CtrStoreData, NxCharInfo};
+use vfl::res::ResourceStore;
+use vfl::res::color::{ColorSpace, ResolveColor};
+use vfl::res::source::{ResourceTier, ShapeSource, TextureSource};
+use wgpu::{Backends, util::DeviceExt};
 use winit::window::Window;
 
 /// Yeah, yeah.
@@ -75,7 +76,6 @@ pub struct State {
     pub egui: EguiState! {
         pub winit_state: egui_winit::State,
         pub wgpu_renderer: egui_wgpu::Renderer,
-        frame_started: bool,
         scale_factor: f32,
         tab: UiTab,
     },
@@ -88,12 +88,7 @@ pub struct State {
         pub depth_texture: TextureBundle,
         pub rotation_count: usize,
     },
-    pub resources: ResourceData! {
-        pub texture_header: ResourceTexture,
-        pub shape_header: ResourceShape,
-        pub texture_data: Rc<Vec<u8>>,
-        pub shape_data: Rc<Vec<u8>>,
-    },
+    pub resources: Rc<ResourceStore>,
 }
 
 impl State {
@@ -108,7 +103,10 @@ impl State {
             .await
             .unwrap();
         let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor::default())
+            .request_device(&wgpu::DeviceDescriptor {
+                required_features: compressed_texture_features(&adapter),
+                ..Default::default()
+            })
             .await
             .unwrap();
 
@@ -191,17 +189,8 @@ impl State {
             }
         };
 
-        let shape_file_path = format!(
-            "{}/resources_here/ShapeMid.dat",
-            std::env::var("CARGO_WORKSPACE_DIR").unwrap_or(
-                std::env::current_dir()
-                    .unwrap()
-                    .to_string_lossy()
-                    .to_string()
-            ),
-        );
-        let tex_file_path = format!(
-            "{}/resources_here/NXTextureMidSRGB.dat",
+        let resources_dir = format!(
+            "{}/resources_here",
             std::env::var("CARGO_WORKSPACE_DIR").unwrap_or(
                 std::env::current_dir()
                     .unwrap()
@@ -209,11 +198,14 @@ impl State {
                     .to_string()
             ),
         );
-        let shape_header = ResourceShape::read(&mut File::open(&shape_file_path).unwrap()).unwrap();
-        let texture_header =
-            ResourceTexture::read(&mut File::open(&tex_file_path).unwrap()).unwrap();
-        let shape_data = Rc::new(std::fs::read(&shape_file_path).unwrap());
-        let texture_data = Rc::new(std::fs::read(&tex_file_path).unwrap());
+        // `low`, `mid` or `high`, picks between e.g. `ShapeMid.dat` and `ShapeHigh.dat`.
+        let tier = std::env::var("RESOURCE_TIER").map_or(ResourceTier::default(), |tier| {
+            tier.parse().unwrap_or_else(|error| {
+                eprintln!("{error} Using {:?}.", ResourceTier::default());
+                ResourceTier::default()
+            })
+        });
+        let resources = Rc::new(ResourceStore::open_nx_tier(resources_dir, tier).unwrap());
 
         let egui = {
             let egui_ctx = egui::Context::default();
@@ -235,19 +227,11 @@ impl State {
             EguiState {
                 winit_state,
                 wgpu_renderer,
-                frame_started: false,
                 scale_factor: 1.0,
                 tab: UiTab::Info,
             }
         };
 
-        let resources = ResourceData {
-            shape_header,
-            texture_header,
-            shape_data,
-            texture_data,
-        };
-
         let state = State {
             window,
             device,
@@ -316,16 +300,18 @@ impl State {
 
         // Instantiate a CharModel if we need it.
         if CHAR_MODEL.get().is_none() {
-            let new_model = CharModel::new(self, &char_info, &mut encoder);
+            let new_model = CharModel::new(self, &char_info, &mut encoder)
+                .unwrap_or_else(|error| panic!("Failed to build character: {error}"));
             CHAR_MODEL.set(Mutex::new(new_model)).unwrap();
         }
         if self.char_remake {
             self.char_remake = false;
 
-            let new_model = CharModel::new(self, &char_info, &mut encoder);
-
-            let mut state = CHAR_MODEL.get().unwrap().lock().unwrap();
-            *state = new_model;
+            // Keep showing the last character that could be built.
+            match CharModel::new(self, &char_info, &mut encoder) {
+                Ok(new_model) => *CHAR_MODEL.get().unwrap().lock().unwrap() = new_model,
+                Err(error) => eprintln!("Failed to build character: {error}"),
+            }
         }
 
         // Actually render a CharModel.
@@ -434,14 +420,15 @@ impl State {
                                 .selected_text(format!("{}", favcol))
                                 .show_ui(ui, |ui| {
                                     let palette_size = vec2(48.0, 20.0);
-                                    let colors = vfl::res::color::nx::linear::FAVORITE_COLOR;
 
                                     egui::Grid::new("some_unique_id")
                                         .spacing(vec2(4.0, 4.0))
                                         .striped(true)
                                         .show(ui, |ui| {
                                             let mut palette = |index| {
-                                                let col = colors[index as usize];
+                                                let col = GenericColor::favorite_color(index)
+                                                    .resolve(ColorSpace::Srgb);
+                                                let [r, g, b, _] = col.to_srgb8();
                                                 let sel = *favcol == index;
                                                 if index == 6 {
                                                     ui.end_row();
@@ -469,12 +456,9 @@ impl State {
                                                                 Stroke::NONE
                                                             })
                                                             .min_size(palette_size)
-                                                            .fill(
-                                                                egui::Rgba::from_rgba_premultiplied(
-                                                                    col[0], col[1], col[2], 1.0,
-                                                                ),
-                                                            ),
+                                                            .fill(Color32::from_rgb(r, g, b)),
                                                     )
+                                                    .on_hover_text(col.to_hex())
                                                     .clicked()
                                                 {
                                                     *favcol = index;
@@ -772,7 +756,7 @@ impl State {
             &self.queue,
             &mut encoder,
             &tris,
-            &screen_descriptor,
+            screen_descriptor,
         );
 
         let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -793,7 +777,7 @@ impl State {
         self.egui.wgpu_renderer.render(
             &mut render_pass.forget_lifetime(),
             &tris,
-            &screen_descriptor,
+            screen_descriptor,
         );
         for x in &full_output.textures_delta.free {
             self.egui.wgpu_renderer.free_texture(x)
@@ -907,19 +891,11 @@ impl ProgramState for State {
         &self.camera.depth_texture
     }
 
-    fn texture_header(&self) -> ResourceTexture {
-        self.resources.texture_header
-    }
-
-    fn shape_header(&self) -> ResourceShape {
-        self.resources.shape_header
-    }
-
-    fn texture_data(&self) -> Rc<Vec<u8>> {
-        self.resources.texture_data.clone()
+    fn texture_source(&self) -> Rc<dyn TextureSource> {
+        self.resources.clone()
     }
 
-    fn shape_data(&self) -> Rc<Vec<u8>> {
-        self.resources.shape_data.clone()
+    fn shape_source(&self) -> Rc<dyn ShapeSource> {
+        self.resources.clone()
     }
 }
@@ -0,0 +1,20 @@
+//! Generates the C header into `$OUT_DIR/vfl.h`.
+//!
+//! The checked-in `include/vfl.h` is only rewritten on request, by building with
+//! `VFL_CAPI_WRITE_HEADER=1`, so builds don't touch the source tree.
+
+fn main() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+
+    println!("cargo::rerun-if-changed=src");
+    println!("cargo::rerun-if-changed=cbindgen.toml");
+    println!("cargo::rerun-if-env-changed=VFL_CAPI_WRITE_HEADER");
+
+    let bindings = cbindgen::generate(&crate_dir).expect("Unable to generate C bindings");
+    bindings.write_to_file(format!("{out_dir}/vfl.h"));
+
+    if std::env::var_os("VFL_CAPI_WRITE_HEADER").is_some() {
+        bindings.write_to_file(format!("{crate_dir}/include/vfl.h"));
+    }
+}
@@ -0,0 +1,203 @@
+#ifndef VFL_H
+#define VFL_H
+
+/* Generated by cbindgen from `crates/vfl-capi`. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Character data formats. See `vee_parse::format::CharFormat`.
+ */
+typedef enum VflFormat {
+  VFL_FORMAT_UNKNOWN = 0,
+  VFL_FORMAT_CHARINFO,
+  VFL_FORMAT_FFSD,
+  VFL_FORMAT_RCD,
+  VFL_FORMAT_RSD,
+  VFL_FORMAT_NCD,
+  VFL_FORMAT_NSD,
+  /**
+   * Raw Mii Studio data.
+   */
+  VFL_FORMAT_MNMS,
+  /**
+   * Obfuscated Mii Studio data, as in Mii Studio URLs.
+   */
+  VFL_FORMAT_STUDIO,
+} VflFormat;
+
+/**
+ * Result of a fallible call. See [`vfl_last_error`] for details on failure.
+ */
+typedef enum VflStatus {
+  VFL_STATUS_OK = 0,
+  /**
+   * A required pointer was null, or an argument was out of range.
+   */
+  VFL_STATUS_INVALID_ARGUMENT,
+  /**
+   * The operation failed.
+   */
+  VFL_STATUS_ERROR,
+  /**
+   * The library panicked. This is a bug.
+   */
+  VFL_STATUS_PANIC,
+} VflStatus;
+
+/**
+ * A character, read from any supported format.
+ */
+typedef struct VflChar VflChar;
+
+/**
+ * A headless GPU renderer.
+ */
+typedef struct VflRenderer VflRenderer;
+
+/**
+ * Shape and texture resource files.
+ */
+typedef struct VflResources VflResources;
+
+/**
+ * Bytes owned by the library. Release with [`vfl_buffer_free`].
+ */
+typedef struct VflBuffer {
+  uint8_t *data;
+  size_t len;
+} VflBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Describes the last failure on this thread. The string is valid until the next call into the
+ * library on this thread, and is empty if nothing has failed yet.
+ */
+const char *vfl_last_error(void);
+
+/**
+ * Releases a buffer returned by the library. Releasing an empty buffer does nothing.
+ *
+ * # Safety
+ * `buffer` must have come from this library, and not already been released.
+ */
+void vfl_buffer_free(struct VflBuffer buffer);
+
+/**
+ * Loads Nx shape and texture resource files (e.g. `ShapeMid.dat`, `NXTextureMidSRGB.dat`).
+ *
+ * # Safety
+ * Both paths must be null or null-terminated strings.
+ */
+struct VflResources *vfl_resources_load_path(const char *shape_path, const char *texture_path);
+
+/**
+ * Loads Nx shape and texture resource files from memory. The data is copied.
+ *
+ * # Safety
+ * `shape` and `texture` must be valid for `shape_len` and `texture_len` bytes.
+ */
+struct VflResources *vfl_resources_load_buffer(const uint8_t *shape,
+                                               size_t shape_len,
+                                               const uint8_t *texture,
+                                               size_t texture_len);
+
+/**
+ * # Safety
+ * `resources` must be null or a handle from `vfl_resources_load_*`, and not already freed.
+ */
+void vfl_resources_free(struct VflResources *resources);
+
+/**
+ * Guesses the format of character data.
+ *
+ * # Safety
+ * `data` must be valid for `len` bytes.
+ */
+enum VflFormat vfl_detect_format(const uint8_t *data, size_t len);
+
+/**
+ * Reads character data of any supported format.
+ *
+ * # Safety
+ * `data` must be valid for `len` bytes.
+ */
+struct VflChar *vfl_char_parse(const uint8_t *data, size_t len);
+
+/**
+ * The format a character was read from.
+ *
+ * # Safety
+ * `chr` must be null or a handle from [`vfl_char_parse`].
+ */
+enum VflFormat vfl_char_format(const struct VflChar *chr);
+
+/**
+ * Writes a character as `target`. On success, `out` must be released with [`vfl_buffer_free`].
+ *
+ * # Safety
+ * `chr` must be null or a handle from [`vfl_char_parse`]. `out` must be null or writable.
+ */
+enum VflStatus vfl_char_convert(const struct VflChar *chr,
+                                enum VflFormat target,
+                                struct VflBuffer *out);
+
+/**
+ * # Safety
+ * `chr` must be null or a handle from [`vfl_char_parse`], and not already freed.
+ */
+void vfl_char_free(struct VflChar *chr);
+
+/**
+ * Creates a GPU renderer for `resources`. Returns `NULL` if no GPU adapter is available.
+ * The resources are shared, so `resources` may be freed afterwards.
+ *
+ * # Safety
+ * `resources` must be null or a handle from `vfl_resources_load_*`.
+ */
+struct VflRenderer *vfl_renderer_new(const struct VflResources *resources);
+
+/**
+ * # Safety
+ * `renderer` must be null or a handle from [`vfl_renderer_new`], and not already freed.
+ */
+void vfl_renderer_free(struct VflRenderer *renderer);
+
+/**
+ * Renders a head-and-shoulders icon of `chr`, as `width * height` tightly packed 8-bit RGBA.
+ * On success, `out` must be released with [`vfl_buffer_free`].
+ *
+ * # Safety
+ * `renderer` and `chr` must be null or valid handles. `out` must be null or writable.
+ */
+enum VflStatus vfl_render_icon_rgba(struct VflRenderer *renderer,
+                                    const struct VflChar *chr,
+                                    uint32_t width,
+                                    uint32_t height,
+                                    struct VflBuffer *out);
+
+/**
+ * Renders a head-and-shoulders icon of `chr`, as a PNG file.
+ * On success, `out` must be released with [`vfl_buffer_free`].
+ *
+ * # Safety
+ * `renderer` and `chr` must be null or valid handles. `out` must be null or writable.
+ */
+enum VflStatus vfl_render_icon_png(struct VflRenderer *renderer,
+                                   const struct VflChar *chr,
+                                   uint32_t width,
+                                   uint32_t height,
+                                   struct VflBuffer *out);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* VFL_H */
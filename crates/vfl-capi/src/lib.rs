@@ -0,0 +1,473 @@
+//! C bindings for `vfl`. The header is checked in at `include/vfl.h`; rebuild with
+//! `VFL_CAPI_WRITE_HEADER=1` to regenerate it after changing the API.
+//!
+//! Everything is handed out behind opaque handles, which must be released with the matching
+//! `vfl_*_free` function. Handles are not thread-safe: use each one from a single thread.
+//!
+//! Functions that return a handle return `NULL` on failure, and functions that return a
+//! [`VflStatus`] return something other than [`VflStatus::Ok`]. In both cases,
+//! [`vfl_last_error`] describes what went wrong.
+//!
+//! ```c
+//! #include "vfl.h"
+//!
+//! VflResources *resources = vfl_resources_load_path("ShapeMid.dat", "NXTextureMidSRGB.dat");
+//! VflChar *chr = vfl_char_parse(data, data_len);
+//!
+//! VflBuffer charinfo;
+//! if (vfl_char_convert(chr, VFL_FORMAT_CHARINFO, &charinfo) != VFL_STATUS_OK) {
+//!     fprintf(stderr, "%s\n", vfl_last_error());
+//! }
+//! vfl_buffer_free(charinfo);
+//!
+//! VflRenderer *renderer = vfl_renderer_new(resources);
+//! VflBuffer png;
+//! vfl_render_icon_png(renderer, chr, 256, 256, &png);
+//! ```
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::fmt::Display;
+use std::io::Cursor;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::rc::Rc;
+use vee_parse::NxCharInfo;
+use vee_parse::format::{self, CharFormat};
+use vee_parse::generic::{AsGenericChar, FromGenericChar};
+use vee_resources::source::{NxShapeFile, NxTextureFile, ShapeSource, TextureSource};
+
+mod render;
+
+/// Result of a fallible call. See [`vfl_last_error`] for details on failure.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VflStatus {
+    Ok = 0,
+    /// A required pointer was null, or an argument was out of range.
+    InvalidArgument,
+    /// The operation failed.
+    Error,
+    /// The library panicked. This is a bug.
+    Panic,
+}
+
+/// Character data formats. See `vee_parse::format::CharFormat`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VflFormat {
+    Unknown = 0,
+    Charinfo,
+    Ffsd,
+    Rcd,
+    Rsd,
+    Ncd,
+    Nsd,
+    /// Raw Mii Studio data.
+    Mnms,
+    /// Obfuscated Mii Studio data, as in Mii Studio URLs.
+    Studio,
+}
+
+impl VflFormat {
+    fn from_format(format: CharFormat) -> VflFormat {
+        match format {
+            CharFormat::NxCharInfo => VflFormat::Charinfo,
+            CharFormat::CtrStoreData => VflFormat::Ffsd,
+            CharFormat::RvlCharData => VflFormat::Rcd,
+            CharFormat::RvlStoreData => VflFormat::Rsd,
+            CharFormat::NtrCharData => VflFormat::Ncd,
+            CharFormat::NtrStoreData => VflFormat::Nsd,
+            CharFormat::StudioCharInfo => VflFormat::Mnms,
+            CharFormat::StudioObfuscated => VflFormat::Studio,
+        }
+    }
+
+    fn to_format(self) -> Option<CharFormat> {
+        Some(match self {
+            VflFormat::Unknown => return None,
+            VflFormat::Charinfo => CharFormat::NxCharInfo,
+            VflFormat::Ffsd => CharFormat::CtrStoreData,
+            VflFormat::Rcd => CharFormat::RvlCharData,
+            VflFormat::Rsd => CharFormat::RvlStoreData,
+            VflFormat::Ncd => CharFormat::NtrCharData,
+            VflFormat::Nsd => CharFormat::NtrStoreData,
+            VflFormat::Mnms => CharFormat::StudioCharInfo,
+            VflFormat::Studio => CharFormat::StudioObfuscated,
+        })
+    }
+}
+
+/// Bytes owned by the library. Release with [`vfl_buffer_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct VflBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl VflBuffer {
+    const EMPTY: VflBuffer = VflBuffer {
+        data: std::ptr::null_mut(),
+        len: 0,
+    };
+
+    fn from_vec(vec: Vec<u8>) -> VflBuffer {
+        let slice = Box::into_raw(vec.into_boxed_slice());
+
+        VflBuffer {
+            data: slice.cast(),
+            len: slice.len(),
+        }
+    }
+}
+
+/// Shape and texture resource files.
+pub struct VflResources {
+    shapes: Rc<dyn ShapeSource>,
+    textures: Rc<dyn TextureSource>,
+}
+
+/// A character, read from any supported format.
+pub struct VflChar {
+    format: CharFormat,
+    char_info: NxCharInfo,
+}
+
+pub use render::VflRenderer;
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn set_error(error: impl Display) {
+    let message = CString::new(error.to_string().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+/// Runs `f`, recording any error or panic for [`vfl_last_error`].
+fn guard<T>(f: impl FnOnce() -> Result<T, String>) -> Result<T, VflStatus> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(error)) => {
+            set_error(error);
+            Err(VflStatus::Error)
+        }
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(ToString::to_string)
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            set_error(format!("panic: {message}"));
+            Err(VflStatus::Panic)
+        }
+    }
+}
+
+fn guard_status(f: impl FnOnce() -> Result<(), String>) -> VflStatus {
+    match guard(f) {
+        Ok(()) => VflStatus::Ok,
+        Err(status) => status,
+    }
+}
+
+fn guard_ptr<T>(f: impl FnOnce() -> Result<T, String>) -> *mut T {
+    guard(f).map_or(std::ptr::null_mut(), |value| Box::into_raw(Box::new(value)))
+}
+
+/// # Safety
+/// `data` must be null or valid for `len` bytes.
+unsafe fn slice<'a>(data: *const u8, len: usize) -> Result<&'a [u8], String> {
+    if data.is_null() {
+        return Err("data is null".to_string());
+    }
+    // SAFETY: Upheld by caller.
+    Ok(unsafe { std::slice::from_raw_parts(data, len) })
+}
+
+/// # Safety
+/// `ptr` must be null or a valid handle.
+unsafe fn handle<'a, T>(ptr: *const T, name: &str) -> Result<&'a T, String> {
+    // SAFETY: Upheld by caller.
+    unsafe { ptr.as_ref() }.ok_or_else(|| format!("{name} is null"))
+}
+
+/// Describes the last failure on this thread. The string is valid until the next call into the
+/// library on this thread, and is empty if nothing has failed yet.
+#[unsafe(no_mangle)]
+pub extern "C" fn vfl_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
+
+/// Releases a buffer returned by the library. Releasing an empty buffer does nothing.
+///
+/// # Safety
+/// `buffer` must have come from this library, and not already been released.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vfl_buffer_free(buffer: VflBuffer) {
+    if !buffer.data.is_null() {
+        // SAFETY: Created by `VflBuffer::from_vec`.
+        drop(unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(buffer.data, buffer.len)) });
+    }
+}
+
+fn load_resources(shape_data: Vec<u8>, texture_data: Vec<u8>) -> Result<VflResources, String> {
+    let shapes =
+        NxShapeFile::new(shape_data).map_err(|e| format!("Invalid shape resource: {e}"))?;
+    let textures =
+        NxTextureFile::new(texture_data).map_err(|e| format!("Invalid texture resource: {e}"))?;
+
+    Ok(VflResources {
+        shapes: Rc::new(shapes),
+        textures: Rc::new(textures),
+    })
+}
+
+/// Loads Nx shape and texture resource files (e.g. `ShapeMid.dat`, `NXTextureMidSRGB.dat`).
+///
+/// # Safety
+/// Both paths must be null or null-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vfl_resources_load_path(
+    shape_path: *const c_char,
+    texture_path: *const c_char,
+) -> *mut VflResources {
+    guard_ptr(|| {
+        // SAFETY: Upheld by caller.
+        let [shape_path, texture_path] = [shape_path, texture_path].map(|path| unsafe {
+            path.as_ref()
+                .map(|path| CStr::from_ptr(path).to_string_lossy().into_owned())
+        });
+        let shape_path = shape_path.ok_or("shape_path is null")?;
+        let texture_path = texture_path.ok_or("texture_path is null")?;
+
+        let shape_data =
+            std::fs::read(&shape_path).map_err(|e| format!("Reading {shape_path}: {e}"))?;
+        let texture_data =
+            std::fs::read(&texture_path).map_err(|e| format!("Reading {texture_path}: {e}"))?;
+
+        load_resources(shape_data, texture_data)
+    })
+}
+
+/// Loads Nx shape and texture resource files from memory. The data is copied.
+///
+/// # Safety
+/// `shape` and `texture` must be valid for `shape_len` and `texture_len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vfl_resources_load_buffer(
+    shape: *const u8,
+    shape_len: usize,
+    texture: *const u8,
+    texture_len: usize,
+) -> *mut VflResources {
+    guard_ptr(|| {
+        // SAFETY: Upheld by caller.
+        let (shape, texture) = unsafe { (slice(shape, shape_len)?, slice(texture, texture_len)?) };
+
+        load_resources(shape.to_vec(), texture.to_vec())
+    })
+}
+
+/// # Safety
+/// `resources` must be null or a handle from `vfl_resources_load_*`, and not already freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vfl_resources_free(resources: *mut VflResources) {
+    if !resources.is_null() {
+        // SAFETY: Upheld by caller.
+        drop(unsafe { Box::from_raw(resources) });
+    }
+}
+
+/// Guesses the format of character data.
+///
+/// # Safety
+/// `data` must be valid for `len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vfl_detect_format(data: *const u8, len: usize) -> VflFormat {
+    // SAFETY: Upheld by caller.
+    let format = guard(|| Ok(CharFormat::detect(unsafe { slice(data, len)? })));
+
+    match format {
+        Ok(Some(format)) => VflFormat::from_format(format),
+        _ => VflFormat::Unknown,
+    }
+}
+
+/// Reads character data of any supported format.
+///
+/// # Safety
+/// `data` must be valid for `len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vfl_char_parse(data: *const u8, len: usize) -> *mut VflChar {
+    guard_ptr(|| {
+        // SAFETY: Upheld by caller.
+        let data = unsafe { slice(data, len)? };
+        let (format, char) = format::read_generic(data).map_err(|e| e.to_string())?;
+
+        Ok(VflChar {
+            format,
+            char_info: NxCharInfo::from_generic(char),
+        })
+    })
+}
+
+/// The format a character was read from.
+///
+/// # Safety
+/// `chr` must be null or a handle from [`vfl_char_parse`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vfl_char_format(chr: *const VflChar) -> VflFormat {
+    // SAFETY: Upheld by caller.
+    unsafe { chr.as_ref() }.map_or(VflFormat::Unknown, |chr| VflFormat::from_format(chr.format))
+}
+
+/// Writes a character as `target`. On success, `out` must be released with [`vfl_buffer_free`].
+///
+/// # Safety
+/// `chr` must be null or a handle from [`vfl_char_parse`]. `out` must be null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vfl_char_convert(
+    chr: *const VflChar,
+    target: VflFormat,
+    out: *mut VflBuffer,
+) -> VflStatus {
+    // SAFETY: Upheld by caller.
+    let Some(out) = (unsafe { out.as_mut() }) else {
+        set_error("out is null");
+        return VflStatus::InvalidArgument;
+    };
+    *out = VflBuffer::EMPTY;
+
+    let Some(target) = target.to_format() else {
+        set_error("target format is unknown");
+        return VflStatus::InvalidArgument;
+    };
+
+    guard_status(|| {
+        // SAFETY: Upheld by caller.
+        let chr = unsafe { handle(chr, "chr")? };
+
+        let char = chr.char_info.as_generic().map_err(|e| e.to_string())?;
+        let data = target.write_generic(char).map_err(|e| e.to_string())?;
+
+        *out = VflBuffer::from_vec(data);
+        Ok(())
+    })
+}
+
+/// # Safety
+/// `chr` must be null or a handle from [`vfl_char_parse`], and not already freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vfl_char_free(chr: *mut VflChar) {
+    if !chr.is_null() {
+        // SAFETY: Upheld by caller.
+        drop(unsafe { Box::from_raw(chr) });
+    }
+}
+
+/// Creates a GPU renderer for `resources`. Returns `NULL` if no GPU adapter is available.
+/// The resources are shared, so `resources` may be freed afterwards.
+///
+/// # Safety
+/// `resources` must be null or a handle from `vfl_resources_load_*`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vfl_renderer_new(resources: *const VflResources) -> *mut VflRenderer {
+    guard_ptr(|| {
+        // SAFETY: Upheld by caller.
+        let resources = unsafe { handle(resources, "resources")? };
+
+        VflRenderer::new(resources)
+    })
+}
+
+/// # Safety
+/// `renderer` must be null or a handle from [`vfl_renderer_new`], and not already freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vfl_renderer_free(renderer: *mut VflRenderer) {
+    if !renderer.is_null() {
+        // SAFETY: Upheld by caller.
+        drop(unsafe { Box::from_raw(renderer) });
+    }
+}
+
+/// # Safety
+/// See [`vfl_render_icon_rgba`].
+unsafe fn render_icon(
+    renderer: *mut VflRenderer,
+    chr: *const VflChar,
+    width: u32,
+    height: u32,
+    out: *mut VflBuffer,
+    encode: impl FnOnce(image::RgbaImage) -> Result<Vec<u8>, String>,
+) -> VflStatus {
+    // SAFETY: Upheld by caller.
+    let Some(out) = (unsafe { out.as_mut() }) else {
+        set_error("out is null");
+        return VflStatus::InvalidArgument;
+    };
+    *out = VflBuffer::EMPTY;
+
+    if width == 0 || height == 0 {
+        set_error("width and height must be non-zero");
+        return VflStatus::InvalidArgument;
+    }
+
+    guard_status(|| {
+        // SAFETY: Upheld by caller.
+        let renderer = unsafe { renderer.as_mut() }.ok_or("renderer is null")?;
+        // SAFETY: Upheld by caller.
+        let chr = unsafe { handle(chr, "chr")? };
+
+        let image = renderer.render_icon(&chr.char_info, width, height)?;
+
+        *out = VflBuffer::from_vec(encode(image)?);
+        Ok(())
+    })
+}
+
+/// Renders a head-and-shoulders icon of `chr`, as `width * height` tightly packed 8-bit RGBA.
+/// On success, `out` must be released with [`vfl_buffer_free`].
+///
+/// # Safety
+/// `renderer` and `chr` must be null or valid handles. `out` must be null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vfl_render_icon_rgba(
+    renderer: *mut VflRenderer,
+    chr: *const VflChar,
+    width: u32,
+    height: u32,
+    out: *mut VflBuffer,
+) -> VflStatus {
+    // SAFETY: Upheld by caller.
+    unsafe {
+        render_icon(renderer, chr, width, height, out, |image| {
+            Ok(image.into_raw())
+        })
+    }
+}
+
+/// Renders a head-and-shoulders icon of `chr`, as a PNG file.
+/// On success, `out` must be released with [`vfl_buffer_free`].
+///
+/// # Safety
+/// `renderer` and `chr` must be null or valid handles. `out` must be null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vfl_render_icon_png(
+    renderer: *mut VflRenderer,
+    chr: *const VflChar,
+    width: u32,
+    height: u32,
+    out: *mut VflBuffer,
+) -> VflStatus {
+    // SAFETY: Upheld by caller.
+    unsafe {
+        render_icon(renderer, chr, width, height, out, |image| {
+            let mut png = Cursor::new(Vec::new());
+            image
+                .write_to(&mut png, image::ImageFormat::Png)
+                .map_err(|e| e.to_string())?;
+            Ok(png.into_inner())
+        })
+    }
+}
@@ -0,0 +1,32 @@
+use crate::VflResources;
+use vee_parse::NxCharInfo;
+use vee_wgpu::headless::HeadlessRenderer;
+
+/// A headless GPU renderer.
+pub struct VflRenderer {
+    renderer: HeadlessRenderer,
+}
+
+impl VflRenderer {
+    pub(crate) fn new(resources: &VflResources) -> Result<VflRenderer, String> {
+        let renderer = HeadlessRenderer::try_with_sources(
+            resources.textures.clone(),
+            resources.shapes.clone(),
+        )
+        .map_err(|e| e.to_string())?;
+
+        Ok(VflRenderer { renderer })
+    }
+
+    /// Renders a head-and-shoulders icon, as in `render_server`.
+    pub(crate) fn render_icon(
+        &mut self,
+        char_info: &NxCharInfo,
+        width: u32,
+        height: u32,
+    ) -> Result<image::RgbaImage, String> {
+        self.renderer
+            .render_icon(char_info, width, height, wgpu::Color::TRANSPARENT)
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,135 @@
+/* Exercises the C API. Built and run by `tests/c_api.rs`, with the paths of a character and
+ * of placeholder shape and texture resources. Exits with SKIPPED if there's no GPU to render
+ * with, once everything else has passed. */
+#include "vfl.h"
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#define SKIPPED 77
+#define ICON_SIZE 64
+
+#define CHECK(cond)                                                              \
+    do {                                                                         \
+        if (!(cond)) {                                                           \
+            fprintf(stderr, "%s:%d: check failed: %s (last error: %s)\n",        \
+                    __FILE__, __LINE__, #cond, vfl_last_error());                \
+            return 1;                                                            \
+        }                                                                        \
+    } while (0)
+
+static unsigned char *read_file(const char *path, size_t *len) {
+    FILE *file = fopen(path, "rb");
+    if (!file) {
+        return NULL;
+    }
+
+    fseek(file, 0, SEEK_END);
+    *len = (size_t)ftell(file);
+    fseek(file, 0, SEEK_SET);
+
+    unsigned char *data = malloc(*len);
+    if (fread(data, 1, *len, file) != *len) {
+        free(data);
+        data = NULL;
+    }
+    fclose(file);
+
+    return data;
+}
+
+/* Renders `chr`, and checks it's drawn in the middle of a transparent background. */
+static int render_icon(VflRenderer *renderer, const VflChar *chr) {
+    VflBuffer rgba;
+    CHECK(vfl_render_icon_rgba(renderer, chr, ICON_SIZE, ICON_SIZE, &rgba) == VFL_STATUS_OK);
+    CHECK(rgba.len == ICON_SIZE * ICON_SIZE * 4);
+
+    const unsigned char *corner = rgba.data;
+    const unsigned char *center = rgba.data + ((ICON_SIZE / 2) * ICON_SIZE + ICON_SIZE / 2) * 4;
+    CHECK(corner[3] == 0);
+    CHECK(center[3] == 255);
+    vfl_buffer_free(rgba);
+
+    CHECK(vfl_render_icon_rgba(renderer, chr, 0, ICON_SIZE, &rgba) == VFL_STATUS_INVALID_ARGUMENT);
+
+    VflBuffer png;
+    CHECK(vfl_render_icon_png(renderer, chr, ICON_SIZE, ICON_SIZE, &png) == VFL_STATUS_OK);
+    CHECK(png.len > 8 && memcmp(png.data, "\x89PNG", 4) == 0);
+    vfl_buffer_free(png);
+
+    return 0;
+}
+
+int main(int argc, char **argv) {
+    CHECK(argc == 4);
+
+    size_t ffsd_len;
+    unsigned char *ffsd = read_file(argv[1], &ffsd_len);
+    CHECK(ffsd != NULL);
+
+    CHECK(vfl_detect_format(ffsd, ffsd_len) == VFL_FORMAT_FFSD);
+    CHECK(vfl_detect_format(ffsd, 3) == VFL_FORMAT_UNKNOWN);
+
+    VflChar *chr = vfl_char_parse(ffsd, ffsd_len);
+    CHECK(chr != NULL);
+    CHECK(vfl_char_format(chr) == VFL_FORMAT_FFSD);
+
+    /* Round trip through charinfo. */
+    VflBuffer charinfo;
+    CHECK(vfl_char_convert(chr, VFL_FORMAT_CHARINFO, &charinfo) == VFL_STATUS_OK);
+    CHECK(charinfo.len == 0x58);
+    CHECK(vfl_detect_format(charinfo.data, charinfo.len) == VFL_FORMAT_CHARINFO);
+
+    VflChar *from_charinfo = vfl_char_parse(charinfo.data, charinfo.len);
+    CHECK(from_charinfo != NULL);
+    vfl_buffer_free(charinfo);
+
+    VflBuffer roundtrip;
+    CHECK(vfl_char_convert(from_charinfo, VFL_FORMAT_FFSD, &roundtrip) == VFL_STATUS_OK);
+    CHECK(vfl_detect_format(roundtrip.data, roundtrip.len) == VFL_FORMAT_FFSD);
+    vfl_buffer_free(roundtrip);
+    vfl_char_free(from_charinfo);
+
+    VflBuffer studio;
+    CHECK(vfl_char_convert(chr, VFL_FORMAT_STUDIO, &studio) == VFL_STATUS_OK);
+    CHECK(studio.len == 47);
+    vfl_buffer_free(studio);
+
+    /* Failures are reported, not fatal. */
+    VflBuffer unsupported;
+    CHECK(vfl_char_convert(chr, VFL_FORMAT_RCD, &unsupported) == VFL_STATUS_ERROR);
+    CHECK(unsupported.data == NULL);
+    CHECK(strlen(vfl_last_error()) > 0);
+
+    CHECK(vfl_char_convert(chr, VFL_FORMAT_UNKNOWN, &unsupported) == VFL_STATUS_INVALID_ARGUMENT);
+    CHECK(vfl_char_convert(NULL, VFL_FORMAT_FFSD, &unsupported) == VFL_STATUS_ERROR);
+    CHECK(vfl_char_parse(ffsd, 3) == NULL);
+    CHECK(vfl_resources_load_path("does-not-exist", "does-not-exist") == NULL);
+    CHECK(vfl_renderer_new(NULL) == NULL);
+
+    VflBuffer png;
+    CHECK(vfl_render_icon_png(NULL, chr, 64, 64, &png) == VFL_STATUS_ERROR);
+
+    /* Headless rendering, with placeholder resources. */
+    VflResources *resources = vfl_resources_load_path(argv[2], argv[3]);
+    CHECK(resources != NULL);
+
+    int status = 0;
+    VflRenderer *renderer = vfl_renderer_new(resources);
+    if (renderer == NULL) {
+        CHECK(strstr(vfl_last_error(), "adapter") != NULL);
+        fprintf(stderr, "Skipping rendering: %s\n", vfl_last_error());
+        status = SKIPPED;
+    } else {
+        CHECK(render_icon(renderer, chr) == 0);
+        vfl_renderer_free(renderer);
+    }
+
+    vfl_resources_free(resources);
+    vfl_char_free(chr);
+    vfl_char_free(NULL);
+    free(ffsd);
+
+    return status;
+}
@@ -0,0 +1,69 @@
+//! Builds `tests/c/test.c` against the generated header and the cdylib, then runs it.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use vee_resources::synthetic;
+
+/// `test.c` exits with this when there's no GPU adapter to render with, after every other check
+/// has passed. Same as Automake's skip code.
+const SKIPPED: i32 = 77;
+
+#[test]
+fn c_api() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let workspace_dir = PathBuf::from(std::env::var("CARGO_WORKSPACE_DIR").unwrap());
+
+    // Integration tests live in `target/<profile>/deps`, along with the cdylib. The copy in
+    // `target/<profile>` is only updated by `cargo build`, so it can be stale.
+    let test_exe = std::env::current_exe().unwrap();
+    let lib_dir = test_exe.parent().unwrap();
+    let out_exe = lib_dir.join("vfl_capi_test");
+
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+
+    let status = Command::new(compiler)
+        .arg(manifest_dir.join("tests/c/test.c"))
+        .arg("-I")
+        .arg(env!("OUT_DIR"))
+        .arg("-L")
+        .arg(lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lvfl_capi")
+        .arg("-o")
+        .arg(&out_exe)
+        .status()
+        .expect("C compiler should run");
+    assert!(status.success(), "test.c failed to compile");
+
+    let resource_dir = lib_dir.join("vfl_capi_test_resources");
+    std::fs::create_dir_all(&resource_dir).unwrap();
+    let (shape_path, texture_path) = synthetic::write_files(&resource_dir).unwrap();
+
+    let status = Command::new(&out_exe)
+        // Cargo's library path includes `target/<profile>` and would win over the rpath.
+        .env("LD_LIBRARY_PATH", lib_dir)
+        .arg(workspace_dir.join("resources_here/j0.ffsd"))
+        .arg(shape_path)
+        .arg(texture_path)
+        .status()
+        .unwrap();
+
+    if status.code() == Some(SKIPPED) {
+        eprintln!("c_api: skipped rendering, no GPU adapter available");
+    } else {
+        assert!(status.success(), "test.c failed");
+    }
+}
+
+#[test]
+fn header_up_to_date() {
+    let generated = std::fs::read_to_string(Path::new(env!("OUT_DIR")).join("vfl.h")).unwrap();
+    let checked_in =
+        std::fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("include/vfl.h"))
+            .unwrap();
+
+    assert!(
+        generated == checked_in,
+        "include/vfl.h is out of date, rebuild with VFL_CAPI_WRITE_HEADER=1"
+    );
+}
@@ -1,15 +1,14 @@
-use clap::{Parser, Subcommand, ValueEnum, arg};
+use clap::{Parser, Subcommand, ValueEnum};
 use mesh_tools::compat::point3_new;
 use mesh_tools::{GltfBuilder, Triangle};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::PathBuf;
-use std::u8;
 use vfl::parse::BinRead;
 use vfl::res::packing::Float16;
 use vfl::res::shape::{GenericResourceShape, ResourceShape, Shape, ShapeMesh};
-use vfl::res::tex::{ResourceTexture, TextureElement};
+use vfl::res::tex::{ResourceTexture, TextureDecodeOptions, TextureElement};
 // TODO: use real names
 // https://github.com/ariankordi/ffl/blob/97eecdf3688f92c4c95cecf5d6ab3e84c0ee42c0/tools/FFLResource.py#L448
 #[derive(Debug, Copy, Clone, ValueEnum)]
@@ -63,6 +62,9 @@ enum Subcommands {
         texture_type: TextureType,
         #[arg(short, long)]
         index: usize,
+        /// Spread one and two channel textures over every channel
+        #[arg(short, long)]
+        normalize: bool,
         output: PathBuf,
     },
 
@@ -92,6 +94,14 @@ enum Subcommands {
         index: usize,
         output: PathBuf,
     },
+
+    /// Identify a resource file and check that its parts decompress
+    Inspect {
+        resource_file: PathBuf,
+        /// Decode every mesh and texture too
+        #[arg(short, long)]
+        verify: bool,
+    },
 }
 
 // This is kind of `clap`-slop. I just need a quick debug tool.
@@ -99,10 +109,24 @@ fn main() {
     let args = Args::parse();
 
     match args.subcommands {
+        Subcommands::Inspect {
+            resource_file,
+            verify,
+        } => {
+            let report = if verify {
+                vfl::res::verify(resource_file)
+            } else {
+                vfl::res::inspect(resource_file)
+            }
+            .unwrap();
+
+            println!("{report}");
+        }
         Subcommands::Texture {
             resource_file,
             texture_type,
             index,
+            normalize,
             output,
         } => {
             let res_tex = ResourceTexture::read(&mut BufReader::new(
@@ -114,7 +138,10 @@ fn main() {
 
             // *YandereDev Voice* If only there was a better way...
             let texture_element = lookup_texture_type(texture_type, index, res_tex).unwrap();
-            let el = texture_element.get_image(&res_file).unwrap().unwrap();
+            let el = texture_element
+                .get_image_with(&res_file, TextureDecodeOptions { normalize })
+                .unwrap()
+                .unwrap();
             el.save(output).unwrap();
         }
         Subcommands::TextureExists {
@@ -205,7 +232,7 @@ fn main() {
 
                 let mesh = shape.mesh(&res_file).unwrap();
 
-                if mesh.positions.len() == 0 {
+                if mesh.positions.is_empty() {
                     exists.push(false);
 
                     continue;
@@ -249,10 +276,11 @@ fn main() {
                 indices,
                 normals: _,
                 uvs: _,
+                tangents: _,
                 color_params: _,
             } = mesh;
 
-            if positions.len() == 0 {
+            if positions.is_empty() {
                 println!("Empty model! Try again.");
                 return;
             }
@@ -294,7 +322,7 @@ fn main() {
 
             builder.add_scene(Some("Mii Scene".to_string()), Some(vec![mii_shape_node]));
 
-            builder.export_glb(&output.to_str().unwrap()).unwrap();
+            builder.export_glb(output.to_str().unwrap()).unwrap();
         }
     }
 }
@@ -0,0 +1,285 @@
+//! Python classes for the character formats.
+
+use crate::{error, target_format};
+use bilge::prelude::Number;
+use binrw::{BinRead, BinWrite};
+use pyo3::exceptions::PyKeyError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
+use vee_parse::ctr::{
+    BeardField, EyeField, EyePositionField, EyebrowField, EyebrowPositionField, FaceField,
+    GlassField, HairField, MoleField, MouthField, MouthPositionField, NoseField,
+    PersonalInfo1Field, PersonalInfo2Field,
+};
+use vee_parse::format::{self, CharFormat};
+use vee_parse::generic::AsGenericChar;
+use vee_parse::studio::StudioCharInfo;
+use vee_parse::{CtrStoreData, FixedLengthWideString, NxCharInfo};
+
+/// Conversion to and from a Python `dict`.
+trait DictConvert: Sized {
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>>;
+    fn from_dict(dict: &Bound<'_, PyDict>) -> PyResult<Self>;
+}
+
+fn serde_to_dict<'py>(
+    py: Python<'py>,
+    value: &impl serde::Serialize,
+) -> PyResult<Bound<'py, PyDict>> {
+    let json = serde_json::to_string(value).map_err(error)?;
+
+    Ok(py
+        .import("json")?
+        .call_method1("loads", (json,))?
+        .cast_into()?)
+}
+
+fn serde_from_dict<T: serde::de::DeserializeOwned>(dict: &Bound<'_, PyDict>) -> PyResult<T> {
+    let json: String = dict
+        .py()
+        .import("json")?
+        .call_method1("dumps", (dict,))?
+        .extract()?;
+
+    serde_json::from_str(&json).map_err(error)
+}
+
+impl DictConvert for NxCharInfo {
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        serde_to_dict(py, self)
+    }
+
+    fn from_dict(dict: &Bound<'_, PyDict>) -> PyResult<Self> {
+        serde_from_dict(dict)
+    }
+}
+
+impl DictConvert for StudioCharInfo {
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        serde_to_dict(py, self)
+    }
+
+    fn from_dict(dict: &Bound<'_, PyDict>) -> PyResult<Self> {
+        serde_from_dict(dict)
+    }
+}
+
+fn get_item<'py>(dict: &Bound<'py, PyDict>, key: &str) -> PyResult<Bound<'py, PyAny>> {
+    dict.get_item(key)?
+        .ok_or_else(|| PyKeyError::new_err(key.to_string()))
+}
+
+fn get_field<T: Number<UnderlyingType = u8>>(dict: &Bound<'_, PyDict>, key: &str) -> PyResult<T> {
+    let value: u8 = get_item(dict, key)?.extract()?;
+
+    T::try_new(value).map_err(|_| error(format!("`{key}` is out of range.")))
+}
+
+/// Bitfields are converted field-by-field. Reserved and padding fields are left out, and zeroed.
+macro_rules! bitfield_dicts {
+    ($($ty:ident { $($field:ident),* $(,)? })*) => {$(
+        impl DictConvert for $ty {
+            fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+                let dict = PyDict::new(py);
+                $(dict.set_item(stringify!($field), self.$field().value())?;)*
+                Ok(dict)
+            }
+
+            fn from_dict(dict: &Bound<'_, PyDict>) -> PyResult<Self> {
+                Ok($ty::new($(get_field(dict, stringify!($field))?),*))
+            }
+        }
+    )*};
+}
+
+bitfield_dicts! {
+    PersonalInfo1Field {
+        char_version, copyable, ng_word, region_move, font_region, room_index, position_in_room,
+        author_type, birth_platform,
+    }
+    PersonalInfo2Field { gender, birth_month, birth_day, favorite_color, favorite }
+    FaceField { local_only, face_type, face_color, face_texture, face_makeup }
+    HairField { hair_type, hair_color, hair_flip }
+    EyeField { eye_type, eye_color, eye_scale, eye_aspect }
+    EyePositionField { eye_rotate, eye_x, eye_y }
+    EyebrowField { eyebrow_type, eyebrow_color, eyebrow_scale, eyebrow_aspect }
+    EyebrowPositionField { eyebrow_rotate, eyebrow_x, eyebrow_y }
+    NoseField { nose_type, nose_scale, nose_y }
+    MouthField { mouth_type, mouth_color, mouth_scale, mouth_aspect }
+    MouthPositionField { mouth_y, mustache_type }
+    BeardField { beard_type, beard_color, mustache_scale, mustache_y }
+    GlassField { glass_type, glass_color, glass_scale, glass_y }
+    MoleField { mole_type, mole_scale, mole_x, mole_y }
+}
+
+/// Like the bitfields, `padding` is left out. The checksum is left out, and recalculated.
+impl DictConvert for CtrStoreData {
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+
+        dict.set_item("personal_info_1", self.personal_info_1.to_dict(py)?)?;
+        dict.set_item("author_id", PyList::new(py, self.author_id.data)?)?;
+        dict.set_item("create_id", PyList::new(py, self.create_id.data)?)?;
+        dict.set_item("reserved", PyList::new(py, self.reserved)?)?;
+        dict.set_item("personal_info_2", self.personal_info_2.to_dict(py)?)?;
+        dict.set_item("name", self.name.to_string())?;
+        dict.set_item("height", self.height)?;
+        dict.set_item("build", self.build)?;
+        dict.set_item("face", self.face.to_dict(py)?)?;
+        dict.set_item("hair", self.hair.to_dict(py)?)?;
+        dict.set_item("eye", self.eye.to_dict(py)?)?;
+        dict.set_item("eye_position", self.eye_position.to_dict(py)?)?;
+        dict.set_item("eyebrow", self.eyebrow.to_dict(py)?)?;
+        dict.set_item("eyebrow_position", self.eyebrow_position.to_dict(py)?)?;
+        dict.set_item("nose", self.nose.to_dict(py)?)?;
+        dict.set_item("mouth", self.mouth.to_dict(py)?)?;
+        dict.set_item("mouth_position", self.mouth_position.to_dict(py)?)?;
+        dict.set_item("beard", self.beard.to_dict(py)?)?;
+        dict.set_item("glass", self.glass.to_dict(py)?)?;
+        dict.set_item("mole", self.mole.to_dict(py)?)?;
+        dict.set_item("creator_name", self.creator_name.to_string())?;
+
+        Ok(dict)
+    }
+
+    fn from_dict(dict: &Bound<'_, PyDict>) -> PyResult<Self> {
+        fn sub<T: DictConvert>(dict: &Bound<'_, PyDict>, key: &str) -> PyResult<T> {
+            T::from_dict(get_item(dict, key)?.cast()?)
+        }
+
+        let mut store_data = CtrStoreData {
+            personal_info_1: sub(dict, "personal_info_1")?,
+            author_id: vee_parse::ctr::CtrAuthorId {
+                data: get_item(dict, "author_id")?.extract()?,
+            },
+            create_id: vee_parse::ctr::CtrCreateId {
+                data: get_item(dict, "create_id")?.extract()?,
+            },
+            reserved: get_item(dict, "reserved")?.extract()?,
+            personal_info_2: sub(dict, "personal_info_2")?,
+            name: FixedLengthWideString::from_string(get_item(dict, "name")?.extract()?),
+            height: get_item(dict, "height")?.extract()?,
+            build: get_item(dict, "build")?.extract()?,
+            face: sub(dict, "face")?,
+            hair: sub(dict, "hair")?,
+            eye: sub(dict, "eye")?,
+            eye_position: sub(dict, "eye_position")?,
+            eyebrow: sub(dict, "eyebrow")?,
+            eyebrow_position: sub(dict, "eyebrow_position")?,
+            nose: sub(dict, "nose")?,
+            mouth: sub(dict, "mouth")?,
+            mouth_position: sub(dict, "mouth_position")?,
+            beard: sub(dict, "beard")?,
+            glass: sub(dict, "glass")?,
+            mole: sub(dict, "mole")?,
+            creator_name: FixedLengthWideString::from_string(
+                get_item(dict, "creator_name")?.extract()?,
+            ),
+            padding: 0,
+            crc: 0,
+        };
+        store_data.update_crc();
+
+        Ok(store_data)
+    }
+}
+
+fn read<T: for<'a> BinRead<Args<'a> = ()>>(format: CharFormat, data: &[u8]) -> PyResult<T> {
+    if data.len() != format.size() {
+        return Err(error(format!(
+            "`{format}` data must be {} bytes, not {}.",
+            format.size(),
+            data.len()
+        )));
+    }
+
+    T::read_le(&mut std::io::Cursor::new(data)).map_err(error)
+}
+
+fn write<'py, T: for<'a> BinWrite<Args<'a> = ()>>(
+    py: Python<'py>,
+    value: &T,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let mut data = std::io::Cursor::new(Vec::new());
+    value.write_le(&mut data).map_err(error)?;
+
+    Ok(PyBytes::new(py, data.get_ref()))
+}
+
+/// Defines a Python class wrapping a character format.
+macro_rules! char_class {
+    ($(#[$attr:meta])* $wrapper:ident($inner:ty), $name:literal, $format:expr) => {
+        $(#[$attr])*
+        #[pyclass(name = $name, module = "vfl")]
+        pub struct $wrapper(pub $inner);
+
+        #[pymethods]
+        impl $wrapper {
+            /// Reads the format's binary representation.
+            #[staticmethod]
+            fn from_bytes(data: &[u8]) -> PyResult<Self> {
+                read($format, data).map(Self)
+            }
+
+            /// Writes the format's binary representation.
+            fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+                write(py, &self.0)
+            }
+
+            /// Reads a `dict`, as from `to_dict`.
+            #[staticmethod]
+            fn from_dict(dict: &Bound<'_, PyDict>) -> PyResult<Self> {
+                DictConvert::from_dict(dict).map(Self)
+            }
+
+            /// Writes the fields to a `dict`.
+            fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+                self.0.to_dict(py)
+            }
+
+            /// Converts to `target`, returning its binary representation.
+            fn convert<'py>(&self, py: Python<'py>, target: &str) -> PyResult<Bound<'py, PyBytes>> {
+                let char = self.0.as_generic().map_err(error)?;
+                let data = target_format(target)?.write_generic(char).map_err(error)?;
+
+                Ok(PyBytes::new(py, &data))
+            }
+
+            /// Checks the checksum, if any, and that every field is in range.
+            fn validate(&self, py: Python<'_>) -> PyResult<()> {
+                format::validate(self.to_bytes(py)?.as_bytes()).map_err(error)?;
+
+                Ok(())
+            }
+
+            fn __eq__(&self, py: Python<'_>, other: &Self) -> PyResult<bool> {
+                Ok(self.to_bytes(py)?.as_bytes() == other.to_bytes(py)?.as_bytes())
+            }
+
+            fn __repr__(&self) -> String {
+                format!("{:?}", self.0)
+            }
+        }
+    };
+}
+
+char_class!(
+    /// The `charinfo` format.
+    PyNxCharInfo(NxCharInfo),
+    "NxCharInfo",
+    CharFormat::NxCharInfo
+);
+
+char_class!(
+    /// The `ffsd` format.
+    PyCtrStoreData(CtrStoreData),
+    "CtrStoreData",
+    CharFormat::CtrStoreData
+);
+
+char_class!(
+    /// The `mnms` (raw Mii Studio data) format.
+    PyStudioCharInfo(StudioCharInfo),
+    "StudioCharInfo",
+    CharFormat::StudioCharInfo
+);
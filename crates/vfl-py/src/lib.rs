@@ -0,0 +1,98 @@
+//! Python bindings for `vfl`.
+//!
+//! Exposes character parsing and conversion from [`vee_parse`],
+//! and texture extraction from [`vee_resources`].
+//! Build with `maturin develop -m crates/vfl-py/Cargo.toml`, and test with `pytest crates/vfl-py`.
+//!
+//! Formats are named by [`CharFormat::name`]: `charinfo`, `ffsd`, `rcd`, `rsd`, `ncd`, `nsd`,
+//! `mnms` (raw Mii Studio data) and `studio` (obfuscated Mii Studio data).
+//!
+//! ```python
+//! import vfl
+//!
+//! data = open("Alice.ffsd", "rb").read()
+//!
+//! vfl.detect_format(data)  # "ffsd"
+//! vfl.parse(data).to_dict()["nickname"]  # "Alice"
+//! charinfo = vfl.convert(data, "charinfo")
+//! ```
+
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::fmt::Display;
+use vee_parse::NxCharInfo;
+use vee_parse::format::{self, CharFormat};
+use vee_parse::generic::FromGenericChar;
+
+mod chars;
+mod texture;
+
+pyo3::create_exception!(
+    vfl,
+    VflError,
+    pyo3::exceptions::PyValueError,
+    "Data couldn't be read or written."
+);
+
+fn error(error: impl Display) -> PyErr {
+    VflError::new_err(error.to_string())
+}
+
+fn target_format(name: &str) -> PyResult<CharFormat> {
+    CharFormat::from_name(name).ok_or_else(|| error(format!("Unknown format `{name}`.")))
+}
+
+/// Names of the supported formats.
+#[pyfunction]
+fn formats() -> Vec<&'static str> {
+    CharFormat::ALL.map(CharFormat::name).to_vec()
+}
+
+/// Guesses the format of character data. Returns `None` if it isn't recognised.
+#[pyfunction]
+fn detect_format(data: &[u8]) -> Option<&'static str> {
+    CharFormat::detect(data).map(CharFormat::name)
+}
+
+/// Reads character data of any format as `NxCharInfo`.
+#[pyfunction]
+fn parse(data: &[u8]) -> PyResult<chars::PyNxCharInfo> {
+    let (_, char) = format::read_generic(data).map_err(error)?;
+
+    Ok(chars::PyNxCharInfo(NxCharInfo::from_generic(char)))
+}
+
+/// Converts character data of any format to `target`.
+#[pyfunction]
+fn convert<'py>(py: Python<'py>, data: &[u8], target: &str) -> PyResult<Bound<'py, PyBytes>> {
+    let (_, char) = format::read_generic(data).map_err(error)?;
+    let data = target_format(target)?.write_generic(char).map_err(error)?;
+
+    Ok(PyBytes::new(py, &data))
+}
+
+/// Checks character data is well formed. Returns the name of the format.
+#[pyfunction]
+fn validate(data: &[u8]) -> PyResult<&'static str> {
+    Ok(format::validate(data).map_err(error)?.name())
+}
+
+#[pymodule(name = "vfl")]
+fn vfl_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("VflError", m.py().get_type::<VflError>())?;
+
+    m.add_function(wrap_pyfunction!(formats, m)?)?;
+    m.add_function(wrap_pyfunction!(detect_format, m)?)?;
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(convert, m)?)?;
+    m.add_function(wrap_pyfunction!(validate, m)?)?;
+
+    m.add_class::<chars::PyNxCharInfo>()?;
+    m.add_class::<chars::PyCtrStoreData>()?;
+    m.add_class::<chars::PyStudioCharInfo>()?;
+
+    m.add_class::<texture::Texture>()?;
+    m.add_class::<texture::TextureFile>()?;
+
+    Ok(())
+}
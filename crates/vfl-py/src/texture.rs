@@ -0,0 +1,98 @@
+//! Texture extraction from Nx texture resource files.
+
+use crate::error;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use std::io::Cursor;
+use vee_parse::BinRead;
+use vee_resources::tex::{ResourceTexture, TextureElement, TexturePart};
+
+/// A decoded texture. `data` is tightly packed 8-bit RGBA.
+///
+/// Implements the NumPy array interface, so `numpy.asarray(texture)` gives a read-only
+/// `(height, width, 4)` array of `uint8`.
+#[pyclass(module = "vfl", frozen)]
+pub struct Texture {
+    #[pyo3(get)]
+    width: u32,
+    #[pyo3(get)]
+    height: u32,
+    #[pyo3(get)]
+    data: Py<PyBytes>,
+}
+
+#[pymethods]
+impl Texture {
+    #[getter]
+    fn __array_interface__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let interface = PyDict::new(py);
+        interface.set_item("shape", (self.height, self.width, 4))?;
+        interface.set_item("typestr", "|u1")?;
+        interface.set_item("data", self.data.bind(py))?;
+        interface.set_item("version", 3)?;
+
+        Ok(interface)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Texture(width={}, height={})", self.width, self.height)
+    }
+}
+
+/// A Nx texture resource file (e.g. `NXTextureMidSRGB.dat`).
+#[pyclass(module = "vfl")]
+pub struct TextureFile {
+    header: ResourceTexture,
+    data: Vec<u8>,
+}
+
+#[pymethods]
+impl TextureFile {
+    #[new]
+    fn new(data: Vec<u8>) -> PyResult<TextureFile> {
+        let header = ResourceTexture::read(&mut Cursor::new(&data)).map_err(error)?;
+
+        Ok(TextureFile { header, data })
+    }
+
+    /// Reads a texture resource file from `path`.
+    #[staticmethod]
+    fn open(path: std::path::PathBuf) -> PyResult<TextureFile> {
+        TextureFile::new(std::fs::read(path)?)
+    }
+
+    /// Names of the texture parts, for use with `count` and `get`.
+    #[staticmethod]
+    fn parts() -> Vec<&'static str> {
+        TexturePart::ALL.map(TexturePart::name).to_vec()
+    }
+
+    /// Number of textures of a part. Some of these may be empty.
+    fn count(&self, part: &str) -> PyResult<usize> {
+        Ok(self.part(part)?.len())
+    }
+
+    /// Decodes a texture. Returns `None` for an empty texture.
+    fn get(&self, py: Python<'_>, part: &str, index: usize) -> PyResult<Option<Texture>> {
+        let element = self
+            .part(part)?
+            .get(index)
+            .ok_or_else(|| error(format!("No `{part}` texture at {index}.")))?;
+
+        let image = element.get_rgba_image(&self.data).map_err(error)?;
+
+        Ok(image.map(|image| Texture {
+            width: image.width(),
+            height: image.height(),
+            data: PyBytes::new(py, image.as_raw()).unbind(),
+        }))
+    }
+}
+
+impl TextureFile {
+    fn part(&self, part: &str) -> PyResult<&[TextureElement]> {
+        let part: TexturePart = part.parse().map_err(error)?;
+
+        Ok(self.header.part(part))
+    }
+}
@@ -0,0 +1,114 @@
+"""Run with `maturin develop -m crates/vfl-py/Cargo.toml && pytest crates/vfl-py`."""
+
+from pathlib import Path
+
+import pytest
+
+import vfl
+
+RESOURCES = Path(__file__).parents[3] / "resources_here"
+J0_FFSD = (RESOURCES / "j0.ffsd").read_bytes()
+JAIN_RCD = (RESOURCES / "Jain.rcd").read_bytes()
+
+
+def test_detect():
+    assert vfl.detect_format(J0_FFSD) == "ffsd"
+    assert vfl.detect_format(JAIN_RCD) == "rcd"
+    assert vfl.detect_format(bytes(3)) is None
+    assert "charinfo" in vfl.formats()
+
+
+def test_parse():
+    charinfo = vfl.parse(J0_FFSD)
+    assert charinfo.to_dict()["nickname"] == "Jo Null"
+    charinfo.validate()
+
+
+def test_convert_and_validate():
+    charinfo = vfl.convert(J0_FFSD, "charinfo")
+    assert vfl.validate(charinfo) == "charinfo"
+
+    ffsd = vfl.convert(charinfo, "ffsd")
+    assert vfl.validate(ffsd) == "ffsd"
+
+    corrupt = bytearray(ffsd)
+    corrupt[0x30] ^= 0xFF
+    with pytest.raises(vfl.VflError):
+        vfl.validate(bytes(corrupt))
+
+    with pytest.raises(vfl.VflError):
+        vfl.convert(J0_FFSD, "nonsense")
+
+
+def test_nx_char_info_dict():
+    charinfo = vfl.NxCharInfo.from_bytes(vfl.convert(J0_FFSD, "charinfo"))
+
+    as_dict = charinfo.to_dict()
+    assert vfl.NxCharInfo.from_dict(as_dict) == charinfo
+
+    as_dict["nickname"] = "Alice"
+    renamed = vfl.NxCharInfo.from_dict(as_dict)
+    assert vfl.parse(renamed.to_bytes()).to_dict()["nickname"] == "Alice"
+
+
+def test_ctr_store_data_dict():
+    store_data = vfl.CtrStoreData.from_bytes(J0_FFSD)
+    assert store_data.to_bytes() == J0_FFSD
+
+    as_dict = store_data.to_dict()
+    assert as_dict["name"] == "Jo Null"
+    assert vfl.CtrStoreData.from_dict(as_dict).to_dict() == as_dict
+
+    # The checksum is recalculated.
+    as_dict["hair"]["hair_color"] = 3
+    recoloured = vfl.CtrStoreData.from_dict(as_dict)
+    recoloured.validate()
+    assert recoloured.to_dict()["hair"]["hair_color"] == 3
+
+    as_dict["hair"]["hair_color"] = 8
+    with pytest.raises(vfl.VflError):
+        vfl.CtrStoreData.from_dict(as_dict)
+
+    del as_dict["hair"]
+    with pytest.raises(KeyError):
+        vfl.CtrStoreData.from_dict(as_dict)
+
+
+def test_studio_char_info():
+    studio = vfl.StudioCharInfo.from_bytes(vfl.convert(J0_FFSD, "mnms"))
+    assert vfl.StudioCharInfo.from_dict(studio.to_dict()) == studio
+    assert vfl.parse(studio.convert("charinfo")).to_dict()["hair_type"] == studio.to_dict()["hair_type"]
+
+    with pytest.raises(vfl.VflError):
+        vfl.StudioCharInfo.from_bytes(J0_FFSD)
+
+
+def texture_file():
+    path = RESOURCES / "NXTextureMidSRGB.dat"
+    if not path.exists():
+        pytest.skip(f"{path} is not present")
+
+    return vfl.TextureFile.open(path)
+
+
+def test_textures():
+    textures = texture_file()
+
+    for part in vfl.TextureFile.parts():
+        for index in range(textures.count(part)):
+            texture = textures.get(part, index)
+            if texture is not None:
+                assert len(texture.data) == texture.width * texture.height * 4
+
+    with pytest.raises(vfl.VflError):
+        textures.get("nonsense", 0)
+
+
+def test_textures_numpy():
+    np = pytest.importorskip("numpy")
+    texture = texture_file().get("eye", 0)
+
+    array = np.asarray(texture)
+    assert array.shape == (texture.height, texture.width, 4)
+    assert array.dtype == np.uint8
+    assert array.tobytes() == texture.data
@@ -0,0 +1,204 @@
+//! WebAssembly bindings for `vfl`.
+//!
+//! Exposes character parsing and conversion from [`vee_parse`],
+//! and texture extraction from [`vee_resources`].
+//! Build with `wasm-pack build crates/vfl-wasm`, and test with `wasm-pack test --node crates/vfl-wasm`.
+//!
+//! Formats are named by [`CharFormat::name`]: `charinfo`, `ffsd`, `rcd`, `rsd`, `ncd`, `nsd`,
+//! `mnms` (raw Mii Studio data) and `studio` (obfuscated Mii Studio data).
+//!
+//! ```js
+//! import { detectFormat, parseToJson, convert } from "vfl-wasm";
+//!
+//! const ffsd = new Uint8Array(await (await fetch("Alice.ffsd")).arrayBuffer());
+//!
+//! detectFormat(ffsd); // "ffsd"
+//! JSON.parse(parseToJson(ffsd)).nickname; // "Alice"
+//! const charinfo = convert(ffsd, "charinfo");
+//! ```
+
+use std::io::Cursor;
+use vee_parse::{
+    BinRead, NxCharInfo,
+    format::{self, CharFormat},
+    generic::{AsGenericChar, FromGenericChar},
+    studio::{
+        STUDIO_DATA_ENCODED_LENGTH, studio_url_obfuscation_decode, studio_url_obfuscation_encode,
+    },
+};
+use vee_resources::tex::{ResourceTexture, TextureElement, TexturePart};
+use wasm_bindgen::prelude::*;
+
+fn target_format(name: &str) -> Result<CharFormat, JsError> {
+    CharFormat::from_name(name).ok_or_else(|| JsError::new(&format!("Unknown format `{name}`.")))
+}
+
+/// Guesses the format of character data. Returns `undefined` if it isn't recognised.
+#[wasm_bindgen(js_name = detectFormat)]
+pub fn detect_format(data: &[u8]) -> Option<String> {
+    CharFormat::detect(data).map(|format| format.name().to_string())
+}
+
+/// Reads character data of any format, and returns it as `charinfo` JSON.
+///
+/// # Errors
+/// - The format isn't recognised, or the data can't be read
+#[wasm_bindgen(js_name = parseToJson)]
+pub fn parse_to_json(data: &[u8]) -> Result<String, JsError> {
+    let (_, char) = format::read_generic(data)?;
+
+    Ok(serde_json::to_string(&NxCharInfo::from_generic(char))?)
+}
+
+/// Writes `charinfo` JSON (as from [`parse_to_json`]) as `target`.
+///
+/// # Errors
+/// - The JSON isn't a valid `charinfo`
+/// - `target` can't be written
+#[wasm_bindgen(js_name = fromJson)]
+pub fn from_json(json: &str, target: &str) -> Result<Vec<u8>, JsError> {
+    let char: NxCharInfo = serde_json::from_str(json)?;
+
+    Ok(target_format(target)?.write_generic(char.as_generic()?)?)
+}
+
+/// Converts character data of any format to `target`.
+///
+/// # Errors
+/// - The format isn't recognised, or the data can't be read
+/// - `target` can't be written
+#[wasm_bindgen]
+pub fn convert(data: &[u8], target: &str) -> Result<Vec<u8>, JsError> {
+    let (_, char) = format::read_generic(data)?;
+
+    Ok(target_format(target)?.write_generic(char)?)
+}
+
+/// Checks character data is well formed. Returns the name of the format.
+///
+/// # Errors
+/// - The format isn't recognised, or the data can't be read
+/// - The checksum doesn't match
+/// - A field is out of range
+#[wasm_bindgen]
+pub fn validate(data: &[u8]) -> Result<String, JsError> {
+    Ok(format::validate(data)?.name().to_string())
+}
+
+/// Obfuscates raw Mii Studio data (`mnms`), as used in Mii Studio URLs.
+///
+/// # Errors
+/// - `data` isn't 46 bytes
+#[wasm_bindgen(js_name = studioEncode)]
+pub fn studio_encode(data: &[u8], seed: u8) -> Result<Vec<u8>, JsError> {
+    let data = data
+        .try_into()
+        .map_err(|_| JsError::new("Studio data must be 46 bytes."))?;
+
+    Ok(studio_url_obfuscation_encode(data, seed).to_vec())
+}
+
+/// Deobfuscates Mii Studio URL data into raw Mii Studio data (`mnms`).
+///
+/// # Errors
+/// - `data` isn't 47 bytes
+#[wasm_bindgen(js_name = studioDecode)]
+pub fn studio_decode(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    if data.len() != STUDIO_DATA_ENCODED_LENGTH {
+        return Err(JsError::new("Obfuscated studio data must be 47 bytes."));
+    }
+
+    let mut data = data.to_vec();
+    studio_url_obfuscation_decode(&mut data);
+    data.truncate(STUDIO_DATA_ENCODED_LENGTH - 1);
+
+    Ok(data)
+}
+
+/// A decoded texture. `data` is tightly packed 8-bit RGBA, suitable for `ImageData`.
+#[wasm_bindgen]
+pub struct Texture {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl Texture {
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+}
+
+/// A Nx texture resource file (e.g. `NXTextureMidSRGB.dat`).
+#[wasm_bindgen]
+pub struct TextureFile {
+    header: ResourceTexture,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl TextureFile {
+    /// # Errors
+    /// - `data` isn't a texture resource file
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>) -> Result<TextureFile, JsError> {
+        let header = ResourceTexture::read(&mut Cursor::new(&data))?;
+
+        Ok(TextureFile { header, data })
+    }
+
+    /// Names of the texture parts, for use with [`Self::count`] and [`Self::get`].
+    pub fn parts() -> Vec<String> {
+        TexturePart::ALL
+            .map(|part| part.name().to_string())
+            .to_vec()
+    }
+
+    fn part(&self, part: &str) -> Result<&[TextureElement], JsError> {
+        let part: TexturePart = part.parse().map_err(|e: String| JsError::new(&e))?;
+
+        Ok(self.header.part(part))
+    }
+
+    /// Number of textures of a part. Some of these may be empty.
+    ///
+    /// # Errors
+    /// - `part` isn't a texture part
+    pub fn count(&self, part: &str) -> Result<usize, JsError> {
+        Ok(self.part(part)?.len())
+    }
+
+    /// Decodes a texture. Returns `undefined` for an empty texture.
+    ///
+    /// # Errors
+    /// - `part` isn't a texture part, or `index` is out of range
+    /// - The texture can't be decoded
+    pub fn get(&self, part: &str, index: usize) -> Result<Option<Texture>, JsError> {
+        let element = self
+            .part(part)?
+            .get(index)
+            .ok_or_else(|| JsError::new(&format!("No `{part}` texture at {index}.")))?;
+
+        let image = element
+            .get_rgba_image(&self.data)
+            .map_err(|e| JsError::new(&e.to_string()))?;
+
+        Ok(image.map(|image| Texture {
+            width: image.width(),
+            height: image.height(),
+            data: image.into_raw(),
+        }))
+    }
+}
@@ -0,0 +1,48 @@
+//! Run with `wasm-pack test --node crates/vfl-wasm`.
+#![cfg(target_arch = "wasm32")]
+
+use vfl_wasm::{
+    convert, detect_format, from_json, parse_to_json, studio_decode, studio_encode, validate,
+};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const J0_FFSD: &[u8] = include_bytes!("../../../resources_here/j0.ffsd");
+const JAIN_RCD: &[u8] = include_bytes!("../../../resources_here/Jain.rcd");
+
+#[wasm_bindgen_test]
+fn detect() {
+    assert_eq!(detect_format(J0_FFSD).as_deref(), Some("ffsd"));
+    assert_eq!(detect_format(JAIN_RCD).as_deref(), Some("rcd"));
+    assert_eq!(detect_format(&[0; 3]), None);
+}
+
+#[wasm_bindgen_test]
+fn json_roundtrip() {
+    let json = parse_to_json(J0_FFSD).unwrap();
+    assert!(json.contains("\"nickname\":\"Jo Null\""));
+
+    let charinfo = from_json(&json, "charinfo").unwrap();
+    assert_eq!(parse_to_json(&charinfo).unwrap(), json);
+}
+
+#[wasm_bindgen_test]
+fn convert_and_validate() {
+    let charinfo = convert(J0_FFSD, "charinfo").unwrap();
+    assert_eq!(validate(&charinfo).unwrap(), "charinfo");
+
+    let ffsd = convert(&charinfo, "ffsd").unwrap();
+    assert_eq!(validate(&ffsd).unwrap(), "ffsd");
+
+    let mut corrupt = ffsd.clone();
+    corrupt[0x30] ^= 0xFF;
+    assert!(validate(&corrupt).is_err());
+}
+
+#[wasm_bindgen_test]
+fn studio_roundtrip() {
+    let mnms = convert(J0_FFSD, "mnms").unwrap();
+
+    let obfuscated = studio_encode(&mnms, 0x42).unwrap();
+    assert_eq!(validate(&obfuscated).unwrap(), "studio");
+    assert_eq!(studio_decode(&obfuscated).unwrap(), mnms);
+}